@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+//! `cargo code-digest`: runs `code-digest --cargo` with the remaining arguments.
+//!
+//! cargo runs `cargo-code-digest code-digest [args]` for `cargo code-digest [args]`, so the
+//! subcommand name is dropped before passing the arguments on. The `code-digest` binary is expected
+//! next to this one, which is where `cargo install` puts it.
+
+use std::process::Command;
+
+fn main() {
+    let mut args = std::env::args_os().skip(1).peekable();
+    if args.peek().is_some_and(|arg| arg == "code-digest") {
+        args.next();
+    }
+
+    let code_digest = std::env::current_exe()
+        .map(|exe| exe.with_file_name(format!("code-digest{}", std::env::consts::EXE_SUFFIX)))
+        .unwrap_or_else(|_| "code-digest".into());
+    let status = Command::new(&code_digest)
+        .arg("--cargo")
+        .args(args)
+        .status()
+        .unwrap_or_else(|e| {
+            eprintln!("Error running {}: {}", code_digest.display(), e);
+            std::process::exit(1);
+        });
+    std::process::exit(status.code().unwrap_or(1));
+}
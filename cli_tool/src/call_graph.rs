@@ -0,0 +1,191 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+
+use file_system::{File, FileKind};
+use language_parsers::function_calls;
+
+use crate::file_processor::ParseConfigs;
+
+/// A function defined in the repository, with the names of the functions it calls.
+#[derive(Debug)]
+struct Function {
+    path: String,
+    name: String,
+    start_line: usize,
+    calls: Vec<String>,
+}
+
+/// Which functions call which other functions in the repository, matched by name alone. Calls to
+/// functions that are not defined in the repository, such as those of the standard library, are
+/// left out.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    functions: Vec<Function>,
+}
+
+impl CallGraph {
+    /// Find the functions and their calls in every file. Directories and files in languages without
+    /// a tree-sitter grammar are skipped.
+    pub fn collect(
+        files: impl Iterator<Item = File>,
+        read_file: impl Fn(&Path) -> std::io::Result<String>,
+        parse_configs: &ParseConfigs,
+    ) -> CallGraph {
+        let mut functions = vec![];
+        for file in files {
+            if file.kind != FileKind::File || file.skipped.is_some() {
+                continue;
+            }
+            let parse_config = match parse_configs.for_path(&file.path) {
+                Some(parse_config) => parse_config,
+                None => continue,
+            };
+            let source_code = match read_file(&file.path) {
+                Ok(source_code) => source_code,
+                Err(e) => {
+                    eprintln!("Error reading file {}: {}", file.path.display(), e);
+                    continue;
+                }
+            };
+            for function in function_calls(&source_code, parse_config) {
+                functions.push(Function {
+                    path: file.path.display().to_string(),
+                    name: function.name,
+                    start_line: function.start_line,
+                    calls: function.calls,
+                });
+            }
+        }
+
+        let defined = functions
+            .iter()
+            .map(|function| function.name.clone())
+            .collect::<HashSet<_>>();
+        for function in &mut functions {
+            function.calls.retain(|callee| defined.contains(callee));
+        }
+        CallGraph { functions }
+    }
+
+    /// Each call from one function to another, as the names of the caller and the callee, without
+    /// duplicates.
+    pub fn edges(&self) -> BTreeSet<(&str, &str)> {
+        self.functions
+            .iter()
+            .flat_map(|function| {
+                function
+                    .calls
+                    .iter()
+                    .map(|callee| (function.name.as_str(), callee.as_str()))
+            })
+            .collect()
+    }
+
+    /// A Markdown list of the functions that call or are called by other functions, each with where
+    /// it is defined, what it calls, and what calls it.
+    pub fn to_markdown(&self) -> String {
+        let mut callers: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (caller, callee) in self.edges() {
+            callers.entry(callee).or_default().insert(caller);
+        }
+
+        let mut output = String::new();
+        for function in &self.functions {
+            let called_by = callers.get(function.name.as_str());
+            if function.calls.is_empty() && called_by.is_none() {
+                continue;
+            }
+            let mut annotations = vec![];
+            if !function.calls.is_empty() {
+                annotations.push(format!("calls {}", code_list(&function.calls)));
+            }
+            if let Some(called_by) = called_by {
+                annotations.push(format!("called by {}", code_list(called_by)));
+            }
+            output.push_str(&format!(
+                "- `{}` (`{}:{}`): {}\n",
+                function.name,
+                function.path,
+                function.start_line,
+                annotations.join("; ")
+            ));
+        }
+        output
+    }
+}
+
+/// The names as a comma-separated list of inline code.
+fn code_list<T: AsRef<str>>(names: impl IntoIterator<Item = T>) -> String {
+    names
+        .into_iter()
+        .map(|name| format!("`{}`", name.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_call_graph() {
+        let files = vec![
+            File {
+                path: PathBuf::from("src/lib.rs"),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+            File {
+                path: PathBuf::from("src/shapes.py"),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+        ];
+        let read_file = |path: &Path| match path.to_str().unwrap() {
+            "src/lib.rs" => Ok(r#"
+fn area(width: f64, height: f64) -> f64 {
+    multiply(width, height)
+}
+
+fn multiply(a: f64, b: f64) -> f64 {
+    a.mul_add(b, 0.0)
+}
+"#
+            .to_string()),
+            _ => Ok(r#"
+def describe(shape):
+    print(area(shape.width, shape.height))
+"#
+            .to_string()),
+        };
+        let graph = CallGraph::collect(files.into_iter(), read_file, &ParseConfigs::new());
+        assert_eq!(
+            graph.edges().into_iter().collect::<Vec<_>>(),
+            vec![("area", "multiply"), ("describe", "area")]
+        );
+        assert_eq!(
+            graph.to_markdown(),
+            "- `area` (`src/lib.rs:2`): calls `multiply`; called by `describe`\n\
+             - `multiply` (`src/lib.rs:6`): called by `area`\n\
+             - `describe` (`src/shapes.py:2`): calls `area`\n"
+        );
+    }
+}
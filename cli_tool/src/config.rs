@@ -8,11 +8,17 @@
  * SPDX-License-Identifier: MPL-2.0
  */
 
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgGroup, Args, Parser, Subcommand};
 use once_cell::sync::OnceCell;
 
+use crate::docs::DocsTier;
+use crate::file_processor::{GranularityMode, SyntaxErrorMode, TestCodeMode};
+use crate::github::GithubRepository;
+use crate::llm::Provider;
+
 #[derive(thiserror::Error, Debug)]
 pub enum ConfigError {
     #[error("Failed to parse CLI arguments: {0}")]
@@ -33,8 +39,89 @@ pub struct AppConfig {
     /// Glob patterns for which to include the full file contents, e.g. `*.md` (optional, zero or more)
     pub include: Vec<String>,
 
+    /// Extensions or file names to treat as a language, e.g. `.pyi=python` (optional, zero or more)
+    pub language_map: Vec<String>,
+
     /// Print a file tree for each directory (optional, default false)
     pub tree: bool,
+
+    /// Include READMEs, `docs/`, ADRs, and CONTRIBUTING at this tier (optional)
+    pub with_docs: Option<DocsTier>,
+
+    /// Git commit, tag, or branch to read files from instead of the working tree (optional)
+    pub rev: Option<String>,
+
+    /// GitHub repository to download and digest instead of a local directory (optional)
+    pub github: Option<GithubRepository>,
+
+    /// Annotate captured items with who last changed them and when, from git blame (optional, default false)
+    pub blame: bool,
+
+    /// Annotate captured items with the lines they span in the source (optional, default false)
+    pub line_numbers: bool,
+
+    /// Include the doc comments and docstrings of captured items (optional, default false)
+    pub doc_comments: bool,
+
+    /// Remove comments from captured items (optional, default false)
+    pub strip_comments: bool,
+
+    /// Reduce captured items to their doc comments and signatures (optional, default false)
+    pub signatures_only: bool,
+
+    /// How much of each captured item to keep (optional, default from the language configs)
+    pub granularity: Option<GranularityMode>,
+
+    /// What to do with files that have syntax errors (optional, default warn)
+    pub syntax_errors: SyntaxErrorMode,
+
+    /// What to do with tests (optional, default keep)
+    pub tests: TestCodeMode,
+
+    /// Seconds to spend parsing a file before giving up on it (optional, default no limit)
+    pub parse_timeout: Option<u64>,
+
+    /// Skip files larger than this many bytes (optional, default no limit)
+    pub max_file_size: Option<u64>,
+
+    /// Walk into symlinked directories outside the directory (optional, default false)
+    pub follow_symlinks: bool,
+
+    /// How many directory levels below the directory to digest (optional, default all)
+    pub max_depth: Option<usize>,
+
+    /// Digest hidden files and directories, whose names start with `.` (optional, default false)
+    pub hidden: bool,
+
+    /// Hidden paths to digest even without `hidden`, e.g. `.github/workflows` (optional, zero or more)
+    pub include_hidden: Vec<PathBuf>,
+
+    /// Digest files in unsupported languages by their first lines and definition-like lines (optional, default false)
+    pub fallback: bool,
+
+    /// Treat the directory as a Cargo workspace, ordering and labelling output by crate (optional, default false)
+    pub cargo: bool,
+
+    /// Group and order output by the go.mod, package.json, and pyproject.toml packages found in the directory (optional, default false)
+    pub packages: bool,
+
+    /// Print lines of code, comments, and blanks per language before the digest (optional, default false)
+    pub stats: bool,
+
+    /// Print which functions call which before the digest (optional, default false)
+    pub call_graph: bool,
+
+    /// Log how long each file took to parse to stderr (optional, default false)
+    pub verbose: bool,
+
+    /// Directory to build a full-text search index of captured items in (optional)
+    pub index: Option<PathBuf>,
+
+    /// Run as a JSON-RPC daemon on stdin and stdout for editor integrations (optional, default false)
+    pub daemon: bool,
+
+    /// Subcommand to run instead of printing a digest (optional)
+    pub command: Option<Command>,
 }
 
 impl AppConfig {
@@ -52,10 +139,39 @@ impl AppConfig {
             }
         };
         Ok(Self {
-            directory: cli.directory,
+            directory: cli
+                .directory
+                .unwrap_or_else(|| if cli.cargo { "." } else { "" }.to_string()),
             ignore: cli.ignore,
             include: cli.include,
+            language_map: cli.language_map,
             tree: cli.tree,
+            with_docs: cli.with_docs,
+            rev: cli.rev,
+            github: cli.github,
+            blame: cli.blame,
+            line_numbers: cli.line_numbers,
+            doc_comments: cli.doc_comments,
+            strip_comments: cli.strip_comments,
+            signatures_only: cli.signatures_only,
+            granularity: cli.granularity,
+            syntax_errors: cli.syntax_errors,
+            tests: cli.tests,
+            parse_timeout: cli.parse_timeout,
+            max_file_size: cli.max_file_size,
+            follow_symlinks: cli.follow_symlinks,
+            max_depth: cli.max_depth,
+            hidden: cli.hidden,
+            include_hidden: cli.include_hidden,
+            fallback: cli.fallback,
+            cargo: cli.cargo,
+            packages: cli.packages,
+            stats: cli.stats,
+            call_graph: cli.call_graph,
+            verbose: cli.verbose,
+            index: cli.index,
+            daemon: cli.daemon,
+            command: cli.command,
         })
     }
 }
@@ -81,11 +197,17 @@ fn after_long_help() -> &'static str {
     author,
     version,
     long_about = long_about(),
-    after_long_help = after_long_help()
+    after_long_help = after_long_help(),
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The path to the directory containing the files.
-    pub directory: String,
+    #[clap(required_unless_present_any = ["github", "cargo", "daemon"])]
+    pub directory: Option<String>,
 
     /// Additional directories to ignore (optional, zero or more)
     #[clap(short = 'i', long)]
@@ -95,9 +217,306 @@ pub struct Cli {
     #[clap(short = 'I', long)]
     pub include: Vec<String>,
 
+    /// Treat files with an extension, e.g. `.pyi=python`, or with a file name, e.g.
+    /// `BUILD=python`, as being in a language, named as for language config files (optional,
+    /// zero or more)
+    #[clap(long)]
+    pub language_map: Vec<String>,
+
     /// Print a file tree for each directory (optional, default false)
     #[clap(short = 't', long)]
     pub tree: bool,
+
+    /// Include documentation, i.e. READMEs, files in `docs/` and ADR directories, and CONTRIBUTING,
+    /// in full or, with `--with-docs=outline`, as a Markdown outline of headings (optional)
+    #[clap(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "full"
+    )]
+    pub with_docs: Option<DocsTier>,
+
+    /// Git commit, tag, or branch to read files from instead of the working tree (optional)
+    #[clap(short = 'r', long)]
+    pub rev: Option<String>,
+
+    /// GitHub repository to download and digest instead of a local directory, as `org/repo` or
+    /// `org/repo@ref`. Uses the token in `GITHUB_TOKEN` if set (optional)
+    #[clap(long, conflicts_with_all = ["directory", "rev", "blame"])]
+    pub github: Option<GithubRepository>,
+
+    /// Annotate captured items with who last changed them and when, from git blame (optional, default false)
+    #[clap(short = 'b', long)]
+    pub blame: bool,
+
+    /// Annotate captured items with the lines they span in the source, such as `// lines 120-164`,
+    /// so that items an LLM refers to can be found in the source (optional, default false)
+    #[clap(long)]
+    pub line_numbers: bool,
+
+    /// Include the doc comments above captured items, such as `///` comments in Rust and Javadoc,
+    /// and Python docstrings (optional, default false)
+    #[clap(long)]
+    pub doc_comments: bool,
+
+    /// Remove comments from captured items, keeping the doc comments of top-level items if
+    /// `--doc-comments` is given, to fit more code in fewer tokens (optional, default false)
+    #[clap(long)]
+    pub strip_comments: bool,
+
+    /// Reduce captured items to their doc comments and signatures, everything before their
+    /// bodies, for a digest like an API reference (optional, default false)
+    #[clap(long)]
+    pub signatures_only: bool,
+
+    /// How much of each captured item to keep: `signatures` only, `types` with their fields and
+    /// function signatures, `small-bodies` that also keeps function bodies of up to ten lines, or
+    /// the `full` source of every file. Overrides any `granularity` in the language configs
+    /// (optional, default from the language configs)
+    #[clap(long, value_enum)]
+    pub granularity: Option<GranularityMode>,
+
+    /// What to do with files that have syntax errors, whose digest may be missing items: `ignore`
+    /// them, `warn` about them on stderr, or `fail` and leave them out (optional, default warn)
+    #[clap(long, value_enum, default_value_t = SyntaxErrorMode::Warn)]
+    pub syntax_errors: SyntaxErrorMode,
+
+    /// What to do with tests, which often double the size of a digest: `keep` them, `summarize`
+    /// test functions as their signatures and modules or classes of tests as how many they hold,
+    /// or `skip` them, leaving out files such as `tests/*`, `test_*.py` and `*_test.go` too
+    /// (optional, default keep)
+    #[clap(long, value_enum, default_value_t = TestCodeMode::Keep)]
+    pub tests: TestCodeMode,
+
+    /// Seconds to spend parsing a file before giving up on it with an error, so that a
+    /// pathological file such as huge generated code cannot hang the digest (optional, default no
+    /// limit)
+    #[clap(long, value_name = "SECONDS")]
+    pub parse_timeout: Option<u64>,
+
+    /// Skip files larger than this many bytes without reading them, such as data files or
+    /// minified bundles that would take a lot of memory and say little (optional, default no
+    /// limit)
+    #[clap(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+
+    /// Walk into symlinked directories outside the directory, such as shared packages linked into
+    /// a monorepo, as if they were in it. Each is walked once however many links lead to it, and
+    /// links back into the directory are skipped (optional, default false)
+    #[clap(long)]
+    pub follow_symlinks: bool,
+
+    /// How many directory levels below the directory to digest, such as 1 for only the files
+    /// directly in it or 2 to also include those in its subdirectories, for an overview of a very
+    /// deep repository (optional, default all)
+    #[clap(long, value_name = "LEVELS")]
+    pub max_depth: Option<usize>,
+
+    /// Digest hidden files and directories, whose names start with `.`, which are otherwise left
+    /// out. Ones such as `.idea` can still be left out with `--ignore` (optional, default false)
+    #[clap(long)]
+    pub hidden: bool,
+
+    /// A hidden path relative to the directory to digest even without `--hidden`, such as
+    /// `.github/workflows`, with everything inside it (optional, can be specified multiple times)
+    #[clap(long, value_name = "PATH")]
+    pub include_hidden: Vec<PathBuf>,
+
+    /// Digest files in languages without a parser by their first lines and lines that look like
+    /// definitions, such as those starting with `def`, `function`, or `class`, rather than
+    /// skipping them (optional, default false)
+    #[clap(long)]
+    pub fallback: bool,
+
+    /// Treat the directory as a Cargo workspace: output crates in dependency order, each under a
+    /// heading, and skip the target directory. Defaults to the current directory (optional,
+    /// default false)
+    #[clap(long, conflicts_with_all = ["rev", "github"])]
+    pub cargo: bool,
+
+    /// Find packages from go.mod, package.json, and pyproject.toml files, and output them in
+    /// dependency order with the least-dependent first, each under a heading (optional, default
+    /// false)
+    #[clap(long, conflicts_with_all = ["rev", "cargo"])]
+    pub packages: bool,
+
+    /// Print lines of code, comments, and blanks per language, and the number of captured items,
+    /// before the digest (optional, default false)
+    #[clap(short = 's', long)]
+    pub stats: bool,
+
+    /// Print each function that calls or is called by another function in the directory, with
+    /// its callers and callees, before the digest. Calls are matched by function name, so this is
+    /// approximate (optional, default false)
+    #[clap(long)]
+    pub call_graph: bool,
+
+    /// Log each file as it is parsed to stderr, with the time taken and the number of tree-sitter
+    /// nodes, items, and syntax errors (optional, default false)
+    #[clap(short = 'v', long)]
+    pub verbose: bool,
+
+    /// Also build a full-text search index of captured items in this directory, for use with
+    /// `code-digest search` (optional)
+    #[clap(long, value_name = "DIR")]
+    pub index: Option<PathBuf>,
+
+    /// Run as a JSON-RPC daemon on stdin and stdout for editor integrations. Only
+    /// `--parse-timeout` applies to it (optional, default false)
+    #[clap(long, conflicts_with_all = ["directory", "github", "cargo", "packages", "rev"])]
+    pub daemon: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Serve digests of a directory as JSON over HTTP
+    Serve(ServeArgs),
+
+    /// Ask an LLM a question about a directory, sending it a digest that fits in a token budget
+    Ask(AskArgs),
+
+    /// Check or update a digest file kept in the repository, for use as a pre-commit hook
+    Hook(HookArgs),
+
+    /// Print lines of code, comments, and blanks per language, and the number of captured items
+    Stats(StatsArgs),
+
+    /// Search an index built with `--index` for captured items
+    Search(SearchArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("mode").required(true).multiple(true).args(["http", "html"])))]
+pub struct ServeArgs {
+    /// The path to the directory containing the files.
+    pub directory: String,
+
+    /// Address to listen on for HTTP requests, e.g. `127.0.0.1:8080`. Defaults to
+    /// `127.0.0.1:8080` with `--html`
+    #[clap(long)]
+    pub http: Option<SocketAddr>,
+
+    /// Also serve the digest as an HTML page at `/` that reloads when files change
+    #[clap(long)]
+    pub html: bool,
+
+    /// Additional directories to ignore (optional, zero or more)
+    #[clap(short = 'i', long)]
+    pub ignore: Vec<PathBuf>,
+
+    /// Seconds to spend parsing a file before giving up on it with an error (optional, default no
+    /// limit)
+    #[clap(long, value_name = "SECONDS")]
+    pub parse_timeout: Option<u64>,
+
+    /// Skip files larger than this many bytes without reading them (optional, default no limit)
+    #[clap(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+
+    /// Walk into symlinked directories outside the directory as if they were in it (optional,
+    /// default false)
+    #[clap(long)]
+    pub follow_symlinks: bool,
+
+    /// How many directory levels below the directory to serve (optional, default all)
+    #[clap(long, value_name = "LEVELS")]
+    pub max_depth: Option<usize>,
+
+    /// Serve hidden files and directories, whose names start with `.` (optional, default false)
+    #[clap(long)]
+    pub hidden: bool,
+
+    /// A hidden path relative to the directory to serve even without `--hidden` (optional, can be
+    /// specified multiple times)
+    #[clap(long, value_name = "PATH")]
+    pub include_hidden: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AskArgs {
+    /// The question to ask about the code.
+    pub question: String,
+
+    /// The path to the directory containing the files.
+    pub directory: String,
+
+    /// Additional directories to ignore (optional, zero or more)
+    #[clap(short = 'i', long)]
+    pub ignore: Vec<PathBuf>,
+
+    /// Glob patterns for which to include the full file contents, e.g. `*.md` (optional, zero or more)
+    #[clap(short = 'I', long)]
+    pub include: Vec<String>,
+
+    /// Maximum estimated number of tokens of digest to send; files that do not fit are left out
+    #[clap(long, default_value_t = 50_000)]
+    pub token_budget: usize,
+
+    /// API that the endpoint speaks
+    #[clap(long, value_enum, default_value_t = Provider::OpenAi)]
+    pub provider: Provider,
+
+    /// Model to ask, e.g. `gpt-4`
+    #[clap(long)]
+    pub model: String,
+
+    /// URL to send the request to, instead of the provider's default (optional)
+    #[clap(long)]
+    pub endpoint: Option<String>,
+
+    /// File containing a prompt template with `{question}` and `{digest}` placeholders (optional)
+    #[clap(long)]
+    pub prompt_template: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("mode").required(true).args(["check", "write"])))]
+pub struct HookArgs {
+    /// Exit with an error if this digest file does not match the digest of the staged files
+    #[clap(long, value_name = "OUTPUT_FILE")]
+    pub check: Option<PathBuf>,
+
+    /// Update this digest file to match the digest of the staged files
+    #[clap(long, value_name = "OUTPUT_FILE")]
+    pub write: Option<PathBuf>,
+
+    /// The path to the directory containing the files.
+    #[clap(default_value = ".")]
+    pub directory: String,
+
+    /// Additional directories to ignore (optional, zero or more)
+    #[clap(short = 'i', long)]
+    pub ignore: Vec<PathBuf>,
+
+    /// Glob patterns for which to include the full file contents, e.g. `*.md` (optional, zero or more)
+    #[clap(short = 'I', long)]
+    pub include: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StatsArgs {
+    /// The path to the directory containing the files.
+    pub directory: String,
+
+    /// Additional directories to ignore (optional, zero or more)
+    #[clap(short = 'i', long)]
+    pub ignore: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SearchArgs {
+    /// The query, e.g. `tokenize` or `path:parser AND struct`
+    pub query: String,
+
+    /// Directory containing the index, as passed to `--index`
+    #[clap(long, value_name = "DIR")]
+    pub index: PathBuf,
+
+    /// Maximum number of results
+    #[clap(short = 'n', long, default_value_t = 10)]
+    pub limit: usize,
 }
 
 #[cfg(test)]
@@ -107,7 +526,7 @@ mod tests {
 
     #[test]
     fn test_parse_cli_args() {
-        let args = vec![
+        let args = [
             "code-digest",
             "--ignore",
             "/path/to/ignore",
@@ -127,5 +546,520 @@ mod tests {
         assert_eq!(app_config.ignore, vec![PathBuf::from("/path/to/ignore")]);
         assert_eq!(app_config.include, vec!["*.md"]);
         assert!(app_config.tree);
+        assert_eq!(app_config.with_docs, None);
+        assert_eq!(app_config.rev, None);
+        assert!(!app_config.blame);
+        assert!(app_config.command.is_none());
+    }
+
+    #[test]
+    fn test_parse_cli_args_language_map() {
+        let args = [
+            "code-digest",
+            "--language-map",
+            ".pyi=python",
+            "--language-map",
+            "BUILD=python",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().language_map,
+            vec![".pyi=python", "BUILD=python"]
+        );
+    }
+
+    #[test]
+    fn test_parse_cli_args_rev() {
+        let args = [
+            "code-digest",
+            "--rev",
+            "v1.0.0",
+            "--blame",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+
+        assert_eq!(app_config.directory, "/path/to/directory");
+        assert_eq!(app_config.rev, Some("v1.0.0".to_string()));
+        assert!(app_config.blame);
+    }
+
+    #[test]
+    fn test_parse_cli_args_line_numbers() {
+        let args = ["code-digest", "--line-numbers", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().line_numbers);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().line_numbers);
+    }
+
+    #[test]
+    fn test_parse_cli_args_github() {
+        let args = ["code-digest", "--github", "asimihsan/code-digest@main"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        let github = app_config.github.unwrap();
+        assert_eq!(github.owner, "asimihsan");
+        assert_eq!(github.repo, "code-digest");
+        assert_eq!(github.reference, Some("main".to_string()));
+
+        let args = [
+            "code-digest",
+            "--github",
+            "asimihsan/code-digest",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+
+        let args = ["code-digest", "--github", "code-digest"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_args_cargo() {
+        let args = ["code-digest", "--cargo", "--tree"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert!(app_config.cargo);
+        assert_eq!(app_config.directory, ".");
+
+        let args = ["code-digest", "--cargo", "--rev", "HEAD", "."];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+
+        let args = ["code-digest", "--packages", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().packages);
+    }
+
+    #[test]
+    fn test_parse_cli_args_serve() {
+        let args = [
+            "code-digest",
+            "serve",
+            "--http",
+            "127.0.0.1:8080",
+            "--ignore",
+            "/path/to/ignore",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+
+        match app_config.command {
+            Some(Command::Serve(serve_args)) => {
+                assert_eq!(serve_args.directory, "/path/to/directory");
+                assert_eq!(serve_args.http, Some("127.0.0.1:8080".parse().unwrap()));
+                assert!(!serve_args.html);
+                assert_eq!(serve_args.ignore, vec![PathBuf::from("/path/to/ignore")]);
+                assert_eq!(serve_args.parse_timeout, None);
+            }
+            _ => panic!("expected serve command"),
+        }
+
+        let args = [
+            "code-digest",
+            "serve",
+            "--html",
+            "--parse-timeout",
+            "5",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        match AppConfig::new(&args).unwrap().command {
+            Some(Command::Serve(serve_args)) => assert_eq!(serve_args.parse_timeout, Some(5)),
+            _ => panic!("expected serve command"),
+        }
+
+        let args = [
+            "code-digest",
+            "serve",
+            "--html",
+            "--max-file-size",
+            "1000",
+            "--follow-symlinks",
+            "--max-depth",
+            "2",
+            "--hidden",
+            "--include-hidden",
+            ".github",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        match AppConfig::new(&args).unwrap().command {
+            Some(Command::Serve(serve_args)) => {
+                assert_eq!(serve_args.max_file_size, Some(1000));
+                assert!(serve_args.follow_symlinks);
+                assert_eq!(serve_args.max_depth, Some(2));
+                assert!(serve_args.hidden);
+                assert_eq!(serve_args.include_hidden, vec![PathBuf::from(".github")]);
+            }
+            _ => panic!("expected serve command"),
+        }
+
+        let args = ["code-digest", "serve", "--html", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        match AppConfig::new(&args).unwrap().command {
+            Some(Command::Serve(serve_args)) => {
+                assert_eq!(serve_args.http, None);
+                assert!(serve_args.html);
+            }
+            _ => panic!("expected serve command"),
+        }
+
+        let args = ["code-digest", "serve", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_args_ask() {
+        let args = [
+            "code-digest",
+            "ask",
+            "--provider",
+            "anthropic",
+            "--model",
+            "claude-2",
+            "--include",
+            "*.md",
+            "How are files parsed?",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+
+        match app_config.command {
+            Some(Command::Ask(ask_args)) => {
+                assert_eq!(ask_args.question, "How are files parsed?");
+                assert_eq!(ask_args.directory, "/path/to/directory");
+                assert_eq!(ask_args.include, vec!["*.md"]);
+                assert_eq!(ask_args.provider, Provider::Anthropic);
+                assert_eq!(ask_args.model, "claude-2");
+                assert_eq!(ask_args.token_budget, 50_000);
+                assert_eq!(ask_args.endpoint, None);
+            }
+            _ => panic!("expected ask command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_cli_args_hook() {
+        let args = ["code-digest", "hook", "--check", "ARCHITECTURE.digest.md"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        match app_config.command {
+            Some(Command::Hook(hook_args)) => {
+                assert_eq!(
+                    hook_args.check,
+                    Some(PathBuf::from("ARCHITECTURE.digest.md"))
+                );
+                assert_eq!(hook_args.write, None);
+                assert_eq!(hook_args.directory, ".");
+            }
+            _ => panic!("expected hook command"),
+        }
+
+        let args = ["code-digest", "hook", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+
+        let args = ["code-digest", "hook", "--check", "a.md", "--write", "a.md"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_args_doc_comments() {
+        let args = ["code-digest", "--doc-comments", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().doc_comments);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().doc_comments);
+    }
+
+    #[test]
+    fn test_parse_cli_args_syntax_errors() {
+        let args = [
+            "code-digest",
+            "--syntax-errors",
+            "fail",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().syntax_errors,
+            SyntaxErrorMode::Fail
+        );
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().syntax_errors,
+            SyntaxErrorMode::Warn
+        );
+
+        let args = [
+            "code-digest",
+            "--syntax-errors",
+            "loud",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_args_strip_comments() {
+        let args = ["code-digest", "--strip-comments", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().strip_comments);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().strip_comments);
+    }
+
+    #[test]
+    fn test_parse_cli_args_signatures_only() {
+        let args = ["code-digest", "--signatures-only", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().signatures_only);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().signatures_only);
+    }
+
+    #[test]
+    fn test_parse_cli_args_granularity() {
+        let args = [
+            "code-digest",
+            "--granularity",
+            "small-bodies",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().granularity,
+            Some(GranularityMode::SmallBodies)
+        );
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().granularity, None);
+    }
+
+    #[test]
+    fn test_parse_cli_args_tests() {
+        let args = ["code-digest", "--tests", "summarize", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().tests,
+            TestCodeMode::Summarize
+        );
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().tests, TestCodeMode::Keep);
+    }
+
+    #[test]
+    fn test_parse_cli_args_parse_timeout() {
+        let args = ["code-digest", "--parse-timeout", "5", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().parse_timeout, Some(5));
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().parse_timeout, None);
+    }
+
+    #[test]
+    fn test_parse_cli_args_max_file_size() {
+        let args = [
+            "code-digest",
+            "--max-file-size",
+            "1000",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().max_file_size, Some(1000));
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().max_file_size, None);
+    }
+
+    #[test]
+    fn test_parse_cli_args_follow_symlinks() {
+        let args = ["code-digest", "--follow-symlinks", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().follow_symlinks);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().follow_symlinks);
+    }
+
+    #[test]
+    fn test_parse_cli_args_max_depth() {
+        let args = ["code-digest", "--max-depth", "2", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().max_depth, Some(2));
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(AppConfig::new(&args).unwrap().max_depth, None);
+    }
+
+    #[test]
+    fn test_parse_cli_args_hidden() {
+        let args = [
+            "code-digest",
+            "--include-hidden",
+            ".github/workflows",
+            "--include-hidden",
+            ".cargo",
+            "/path/to/directory",
+        ];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert!(!app_config.hidden);
+        assert_eq!(
+            app_config.include_hidden,
+            vec![PathBuf::from(".github/workflows"), PathBuf::from(".cargo")]
+        );
+
+        let args = ["code-digest", "--hidden", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert!(app_config.hidden);
+        assert!(app_config.include_hidden.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cli_args_fallback() {
+        let args = ["code-digest", "--fallback", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().fallback);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().fallback);
+    }
+
+    #[test]
+    fn test_parse_cli_args_call_graph() {
+        let args = ["code-digest", "--call-graph", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().call_graph);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().call_graph);
+    }
+
+    #[test]
+    fn test_parse_cli_args_verbose() {
+        let args = ["code-digest", "-v", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().verbose);
+
+        let args = ["code-digest", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(!AppConfig::new(&args).unwrap().verbose);
+    }
+
+    #[test]
+    fn test_parse_cli_args_stats() {
+        let args = ["code-digest", "--stats", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).unwrap().stats);
+
+        let args = ["code-digest", "stats", "-i", "target", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        match AppConfig::new(&args).unwrap().command {
+            Some(Command::Stats(stats_args)) => {
+                assert_eq!(stats_args.directory, "/path/to/directory");
+                assert_eq!(stats_args.ignore, vec![PathBuf::from("target")]);
+            }
+            _ => panic!("expected stats command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_cli_args_with_docs() {
+        let args = ["code-digest", "--with-docs", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert_eq!(app_config.with_docs, Some(DocsTier::Full));
+        assert_eq!(app_config.directory, "/path/to/directory");
+
+        let args = ["code-digest", "--with-docs=outline", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().with_docs,
+            Some(DocsTier::Outline)
+        );
+    }
+
+    #[test]
+    fn test_parse_cli_args_index() {
+        let args = ["code-digest", "--index", "/tmp/index", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            AppConfig::new(&args).unwrap().index,
+            Some(PathBuf::from("/tmp/index"))
+        );
+
+        let args = ["code-digest", "search", "--index", "/tmp/index", "tokenize"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        match AppConfig::new(&args).unwrap().command {
+            Some(Command::Search(search_args)) => {
+                assert_eq!(search_args.query, "tokenize");
+                assert_eq!(search_args.index, PathBuf::from("/tmp/index"));
+                assert_eq!(search_args.limit, 10);
+            }
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_cli_args_daemon() {
+        let args = ["code-digest", "--daemon"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert!(app_config.daemon);
+
+        assert_eq!(app_config.parse_timeout, None);
+
+        let args = ["code-digest", "--daemon", "--parse-timeout", "5"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let app_config = AppConfig::new(&args).unwrap();
+        assert!(app_config.daemon);
+        assert_eq!(app_config.parse_timeout, Some(5));
+
+        let args = ["code-digest", "--daemon", "/path/to/directory"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(AppConfig::new(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_args_requires_directory() {
+        let args = ["code-digest", "--tree"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(matches!(
+            AppConfig::new(&args),
+            Err(ConfigError::CliError(_))
+        ));
     }
 }
@@ -0,0 +1,499 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use language_parsers::{parse, KeyContent, Language};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::file_processor::{estimate_tokens, format_key_contents, ParseConfigs, Symbol};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC 2.0 server over stdio for editor integrations. Messages use the same
+/// `Content-Length` framing as the Language Server Protocol, so existing editor JSON-RPC clients
+/// can talk to it directly.
+///
+/// Methods:
+///
+/// - `digestFile` `{path, text?}`: digest a file, using `text` as its contents if given (e.g. an
+///   unsaved buffer), otherwise reading it from disk.
+/// - `digestSelection` `{path, text?, startLine, endLine}`: as `digestFile`, but only the captured
+///   items that overlap the selected lines, which start from 1.
+/// - `tokensForBuffer` `{text, path?}`: estimated tokens for `text`, and for its digest if `path`
+///   has a supported language.
+/// - `shutdown`: stop the daemon.
+///
+/// Parsed files are cached by path and content, so repeated requests for an unchanged buffer do
+/// not parse it again.
+pub struct Daemon {
+    parse_configs: ParseConfigs,
+    cache: HashMap<PathBuf, CachedFile>,
+}
+
+/// What was parsed from a file, kept with the text it was parsed from so that a cached result is
+/// only used for exactly that text.
+struct CachedFile {
+    text: String,
+    key_contents: Vec<KeyContent>,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ResponseError>,
+}
+
+#[derive(Serialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DigestFileParams {
+    path: PathBuf,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DigestSelectionParams {
+    path: PathBuf,
+    text: Option<String>,
+    start_line: usize,
+    end_line: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokensForBufferParams {
+    text: String,
+    path: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DigestResult {
+    digest: String,
+    tokens: usize,
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TokensResult {
+    tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest_tokens: Option<usize>,
+}
+
+impl Default for Daemon {
+    fn default() -> Self {
+        Daemon::new()
+    }
+}
+
+impl Daemon {
+    pub fn new() -> Daemon {
+        Daemon {
+            parse_configs: ParseConfigs::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Use `parse_configs` instead of the default parse configs.
+    pub fn with_parse_configs(mut self, parse_configs: ParseConfigs) -> Self {
+        self.parse_configs = parse_configs;
+        self
+    }
+
+    /// Read requests from `input` and write responses to `output` until the input is closed or a
+    /// `shutdown` request is received.
+    pub fn run(&mut self, mut input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            let (response, shutdown) = self.handle_message(&message);
+            if let Some(response) = response {
+                write_message(&mut output, &serde_json::to_string(&response).unwrap())?;
+            }
+            if shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one message, returning the response (None for notifications) and whether the daemon
+    /// should shut down.
+    fn handle_message(&mut self, message: &str) -> (Option<Response>, bool) {
+        let request: Request = match serde_json::from_str::<Value>(message) {
+            Ok(value) => match serde_json::from_value(value) {
+                Ok(request) => request,
+                Err(e) => {
+                    let error = error(INVALID_REQUEST, format!("Invalid request: {}", e));
+                    return (Some(response(Value::Null, Err(error))), false);
+                }
+            },
+            Err(e) => {
+                let error = error(PARSE_ERROR, format!("Parse error: {}", e));
+                return (Some(response(Value::Null, Err(error))), false);
+            }
+        };
+
+        let shutdown = request.method == "shutdown";
+        let result = match request.method.as_str() {
+            "digestFile" => self.digest_file(request.params),
+            "digestSelection" => self.digest_selection(request.params),
+            "tokensForBuffer" => self.tokens_for_buffer(request.params),
+            "shutdown" => Ok(Value::Null),
+            method => Err(error(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        };
+        (request.id.map(|id| response(id, result)), shutdown)
+    }
+
+    fn digest_file(&mut self, params: Value) -> Result<Value, ResponseError> {
+        let params: DigestFileParams = parse_params(params)?;
+        let (language, key_contents) = self.key_contents(&params.path, params.text)?;
+        Ok(digest_result(&params.path, language, key_contents))
+    }
+
+    fn digest_selection(&mut self, params: Value) -> Result<Value, ResponseError> {
+        let params: DigestSelectionParams = parse_params(params)?;
+        let (language, key_contents) = self.key_contents(&params.path, params.text)?;
+        let key_contents = key_contents
+            .into_iter()
+            .filter(|key_content| {
+                key_content.start_line <= params.end_line
+                    && params.start_line <= key_content.end_line
+            })
+            .collect();
+        Ok(digest_result(&params.path, language, key_contents))
+    }
+
+    fn tokens_for_buffer(&mut self, params: Value) -> Result<Value, ResponseError> {
+        let params: TokensForBufferParams = parse_params(params)?;
+        let tokens = estimate_tokens(&params.text);
+        let digest_tokens = match &params.path {
+            Some(path) if self.parse_configs.for_path(path).is_some() => {
+                let (language, key_contents) = self.key_contents(path, Some(params.text))?;
+                Some(estimate_tokens(&format_key_contents(
+                    path,
+                    language,
+                    &key_contents,
+                    None,
+                    false,
+                )))
+            }
+            _ => None,
+        };
+        Ok(serde_json::to_value(TokensResult {
+            tokens,
+            digest_tokens,
+        })
+        .unwrap())
+    }
+
+    /// Parse a file, or return the cached result if its contents have not changed, along with the
+    /// language it was parsed as.
+    fn key_contents(
+        &mut self,
+        path: &Path,
+        text: Option<String>,
+    ) -> Result<(Language, Vec<KeyContent>), ResponseError> {
+        let parse_config = self.parse_configs.for_path(path).ok_or_else(|| {
+            error(
+                INVALID_PARAMS,
+                format!("Unsupported file kind: {}", path.display()),
+            )
+        })?;
+        let text = match text {
+            Some(text) => text,
+            None => std::fs::read_to_string(path)
+                .map_err(|e| error(INTERNAL_ERROR, format!("Error reading file: {}", e)))?,
+        };
+
+        if let Some(cached) = self.cache.get(path) {
+            if cached.text == text {
+                return Ok((parse_config.language(), cached.key_contents.clone()));
+            }
+        }
+
+        let key_contents = parse(&text, parse_config)
+            .map_err(|e| error(INTERNAL_ERROR, format!("Error parsing file: {}", e)))?;
+        self.cache.insert(
+            path.to_path_buf(),
+            CachedFile {
+                text,
+                key_contents: key_contents.clone(),
+            },
+        );
+        Ok((parse_config.language(), key_contents))
+    }
+}
+
+fn digest_result(path: &Path, language: Language, key_contents: Vec<KeyContent>) -> Value {
+    let digest = format_key_contents(path, language, &key_contents, None, false);
+    serde_json::to_value(DigestResult {
+        tokens: estimate_tokens(&digest),
+        digest,
+        symbols: key_contents.into_iter().map(Symbol::from).collect(),
+    })
+    .unwrap()
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, ResponseError> {
+    serde_json::from_value(params)
+        .map_err(|e| error(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn error(code: i64, message: String) -> ResponseError {
+    ResponseError { code, message }
+}
+
+fn response(id: Value, result: Result<Value, ResponseError>) -> Response {
+    match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        },
+    }
+}
+
+/// Read one `Content-Length` framed message, returning None at end of input.
+fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            let value = value
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            content_length = Some(value);
+        }
+    }
+
+    let mut body = vec![0; content_length.unwrap()];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, body: &str) -> std::io::Result<()> {
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(messages: &[Value]) -> Vec<u8> {
+        let mut input = vec![];
+        for message in messages {
+            write_message(&mut input, &message.to_string()).unwrap();
+        }
+        input
+    }
+
+    fn unframe(output: &[u8]) -> Vec<Value> {
+        let mut reader = std::io::BufReader::new(output);
+        let mut result = vec![];
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            result.push(serde_json::from_str(&message).unwrap());
+        }
+        result
+    }
+
+    fn run(daemon: &mut Daemon, messages: &[Value]) -> Vec<Value> {
+        let input = frame(messages);
+        let mut output = vec![];
+        daemon.run(input.as_slice(), &mut output).unwrap();
+        unframe(&output)
+    }
+
+    const RUST_SOURCE: &str = r#"use std::fmt;
+
+fn first() {
+    println!("first");
+}
+
+fn second() {
+    println!("second");
+}
+"#;
+
+    #[test]
+    fn test_digest_file_and_selection() {
+        let mut daemon = Daemon::new();
+        let responses = run(
+            &mut daemon,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "digestFile",
+                       "params": {"path": "lib.rs", "text": RUST_SOURCE}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "digestSelection",
+                       "params": {"path": "lib.rs", "text": RUST_SOURCE,
+                                  "startLine": 7, "endLine": 8}}),
+            ],
+        );
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(
+            responses[0]["result"]["digest"],
+            "`lib.rs`\n```rust\nuse std::fmt;\n\nfn first() {\n    // ...\n}\n\nfn second() {\n    // ...\n}\n```\n"
+        );
+        assert_eq!(
+            responses[0]["result"]["symbols"].as_array().unwrap().len(),
+            3
+        );
+        assert_eq!(responses[0]["result"]["symbols"][1]["start_line"], 3);
+
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(
+            responses[1]["result"]["digest"],
+            "`lib.rs`\n```rust\nfn second() {\n    // ...\n}\n```\n"
+        );
+        assert_eq!(daemon.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_is_invalidated_when_text_changes() {
+        let mut daemon = Daemon::new();
+        let responses = run(
+            &mut daemon,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "digestFile",
+                       "params": {"path": "lib.rs", "text": "fn a() {}"}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "digestFile",
+                       "params": {"path": "lib.rs", "text": "fn b() {}"}}),
+            ],
+        );
+        assert_eq!(
+            responses[0]["result"]["symbols"][0]["content"],
+            "fn a() {\n    // ...\n}"
+        );
+        assert_eq!(
+            responses[1]["result"]["symbols"][0]["content"],
+            "fn b() {\n    // ...\n}"
+        );
+    }
+
+    #[test]
+    fn test_cache_is_used_only_for_the_same_text() {
+        let mut daemon = Daemon::new();
+        daemon.cache.insert(
+            PathBuf::from("lib.rs"),
+            CachedFile {
+                text: "fn a() {}".to_string(),
+                key_contents: vec![],
+            },
+        );
+        let responses = run(
+            &mut daemon,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "digestFile",
+                       "params": {"path": "lib.rs", "text": "fn a() {}"}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "digestFile",
+                       "params": {"path": "lib.rs", "text": "fn a() {}\n"}}),
+            ],
+        );
+        assert_eq!(responses[0]["result"]["symbols"], json!([]));
+        assert_eq!(
+            responses[1]["result"]["symbols"][0]["content"],
+            "fn a() {\n    // ...\n}"
+        );
+    }
+
+    #[test]
+    fn test_tokens_for_buffer() {
+        let mut daemon = Daemon::new();
+        let responses = run(
+            &mut daemon,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "tokensForBuffer",
+                       "params": {"text": "12345678"}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tokensForBuffer",
+                       "params": {"text": RUST_SOURCE, "path": "lib.rs"}}),
+            ],
+        );
+        assert_eq!(responses[0]["result"], json!({"tokens": 2}));
+        assert!(responses[1]["result"]["digestTokens"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_errors_and_shutdown() {
+        let mut daemon = Daemon::new();
+        let responses = run(
+            &mut daemon,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "unknown"}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "digestFile", "params": {}}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "digestFile",
+                       "params": {"path": "notes.txt", "text": "Hello"}}),
+                json!({"jsonrpc": "2.0", "method": "tokensForBuffer", "params": {"text": "a"}}),
+                json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}),
+                json!({"jsonrpc": "2.0", "id": 5, "method": "tokensForBuffer",
+                       "params": {"text": "a"}}),
+            ],
+        );
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[1]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[2]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(responses[3]["result"], Value::Null);
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::file_processor::format_full_file;
+
+/// How much of each documentation file to include.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsTier {
+    /// The full text
+    Full,
+
+    /// Only the headings of Markdown files; other files are included in full
+    Outline,
+}
+
+/// File names, without extension and in lower case, that are documentation wherever they are.
+const DOC_FILE_STEMS: &[&str] = &["readme", "contributing", "architecture"];
+
+/// Directory names, in lower case, whose files are documentation. ADRs are usually kept in `adr`
+/// or `decisions`, often under `docs`.
+const DOC_DIRECTORIES: &[&str] = &["docs", "doc", "adr", "adrs", "decisions"];
+
+const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "rst", "txt", "adoc"];
+
+/// Picks out documentation such as READMEs, `docs/`, ADRs, and CONTRIBUTING, so that it can be
+/// included alongside the code digest without hand-written include globs.
+pub struct DocsSelector {
+    root: PathBuf,
+    tier: DocsTier,
+}
+
+impl DocsSelector {
+    /// `root` is the directory being digested; only directories beneath it are considered when
+    /// deciding if a file is in e.g. `docs/`.
+    pub fn new(root: PathBuf, tier: DocsTier) -> DocsSelector {
+        DocsSelector { root, tier }
+    }
+
+    pub fn is_documentation(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        let is_doc_extension = extension
+            .as_deref()
+            .is_none_or(|extension| DOC_EXTENSIONS.contains(&extension));
+        if !is_doc_extension {
+            return false;
+        }
+
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        if DOC_FILE_STEMS.contains(&stem.as_str()) {
+            return true;
+        }
+        // Only files with an extension count in documentation directories, to skip e.g. scripts.
+        let relative_path = path.strip_prefix(&self.root).unwrap_or(path);
+        extension.is_some()
+            && relative_path
+                .parent()
+                .into_iter()
+                .flat_map(|parent| parent.components())
+                .filter_map(|component| component.as_os_str().to_str())
+                .any(|component| DOC_DIRECTORIES.contains(&component.to_lowercase().as_str()))
+    }
+
+    /// Render a documentation file at this selector's tier.
+    pub fn format(&self, path: &Path, source: &str) -> String {
+        let is_markdown = matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("md") | Some("markdown")
+        );
+        if self.tier == DocsTier::Outline && is_markdown {
+            format!(
+                "`{}` (outline)\n```markdown\n{}```\n",
+                path.display(),
+                markdown_outline(source)
+            )
+        } else {
+            format_full_file(path, source)
+        }
+    }
+}
+
+/// The ATX headings of a Markdown document, one per line, skipping `#` lines in fenced code blocks.
+fn markdown_outline(source: &str) -> String {
+    let mut outline = String::new();
+    let mut in_code_block = false;
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if !in_code_block && trimmed.starts_with('#') {
+            outline.push_str(trimmed.trim_end());
+            outline.push('\n');
+        }
+    }
+    outline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_documentation() {
+        let selector = DocsSelector::new(PathBuf::from("/home/docs/project"), DocsTier::Full);
+        for path in [
+            "/home/docs/project/README.md",
+            "/home/docs/project/crate/readme.rst",
+            "/home/docs/project/CONTRIBUTING",
+            "/home/docs/project/docs/guide.md",
+            "/home/docs/project/architecture/decisions/0001-use-rust.md",
+        ] {
+            assert!(selector.is_documentation(Path::new(path)), "{}", path);
+        }
+        for path in [
+            "/home/docs/project/src/main.rs",
+            "/home/docs/project/notes.md",
+            "/home/docs/project/docs/build.sh",
+            "/home/docs/project/docs/Makefile",
+        ] {
+            assert!(!selector.is_documentation(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_format_outline() {
+        let selector = DocsSelector::new(PathBuf::from("."), DocsTier::Outline);
+        let source = "# Title\n\nSome text.\n\n```sh\n# not a heading\n```\n\n## Usage\n";
+        assert_eq!(
+            selector.format(Path::new("README.md"), source),
+            "`README.md` (outline)\n```markdown\n# Title\n## Usage\n```\n"
+        );
+        assert_eq!(
+            selector.format(Path::new("README.txt"), "Hello\n"),
+            "`README.txt`\n```\nHello\n\n```\n"
+        );
+    }
+}
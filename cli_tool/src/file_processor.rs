@@ -8,14 +8,37 @@
  * SPDX-License-Identifier: MPL-2.0
  */
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use file_system::GlobPatternMatcher;
-use language_parsers::{parse, ParseConfig};
+use file_system::{
+    get_files, File, FileBlame, FileKind, GlobPatternMatcher, SkipReason, WalkError, WalkOptions,
+};
+use language_parsers::{
+    default_parse_config_for_language, is_test_path, parse_config_from_toml,
+    parse_with_syntax_errors, Granularity, ItemKind, KeyContent, Language, LanguageRegistry,
+    ParseConfig, SyntaxError, TestCode,
+};
+use serde::Serialize;
+
+use crate::docs::DocsSelector;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FileSkipReason {
     FileExtension,
+    TestFile,
+    TooLarge,
+    Binary,
+}
+
+impl From<SkipReason> for FileSkipReason {
+    fn from(reason: SkipReason) -> Self {
+        match reason {
+            SkipReason::TooLarge => FileSkipReason::TooLarge,
+            SkipReason::Binary => FileSkipReason::Binary,
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -31,117 +54,634 @@ pub enum FileProcessorError {
 
     #[error("Unsupported file kind: {0:?}")]
     UnsupportedFileKind(String),
+
+    #[error("Error loading parse config {0}: {1}")]
+    ErrorLoadingParseConfig(PathBuf, language_parsers::ParseError),
+
+    #[error("Parse config for unknown language: {0}")]
+    UnknownParseConfigLanguage(PathBuf),
+
+    #[error("Invalid language mapping {0:?}, expected e.g. `.pyi=python` or `BUILD=python`")]
+    InvalidLanguageMapping(String),
+
+    #[error("Syntax errors in {0}: {}", describe_syntax_errors(.1))]
+    SyntaxErrors(PathBuf, Vec<SyntaxError>),
+}
+
+/// What to do with a file that has syntax errors, whose digest may be missing items or contain
+/// mangled ones.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyntaxErrorMode {
+    /// Digest the file as usual
+    Ignore,
+
+    /// Digest the file and print a warning with where the errors are
+    #[default]
+    Warn,
+
+    /// Skip the file with an error
+    Fail,
+}
+
+/// How much of each captured item to keep, from least to most.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GranularityMode {
+    /// Doc comments and signatures only
+    Signatures,
+
+    /// Signatures, with types kept whole so that their fields are shown
+    Types,
+
+    /// As `types`, also keeping small function bodies whole
+    SmallBodies,
+
+    /// The whole source of every file
+    Full,
+}
+
+impl From<GranularityMode> for Granularity {
+    fn from(mode: GranularityMode) -> Self {
+        match mode {
+            GranularityMode::Signatures => Granularity::Signatures,
+            GranularityMode::Types => Granularity::Types,
+            GranularityMode::SmallBodies => Granularity::SmallBodies,
+            GranularityMode::Full => Granularity::Full,
+        }
+    }
+}
+
+/// What to do with tests, which often take many tokens while saying little about how the code
+/// fits together.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TestCodeMode {
+    /// Digest tests as usual
+    #[default]
+    Keep,
+
+    /// Reduce tests to their signatures, and modules and classes of tests to how many they hold
+    Summarize,
+
+    /// Leave out files of tests, such as those under `tests/`, and tests in other files
+    Skip,
+}
+
+impl From<TestCodeMode> for TestCode {
+    fn from(mode: TestCodeMode) -> Self {
+        match mode {
+            TestCodeMode::Keep => TestCode::Keep,
+            TestCodeMode::Summarize => TestCode::Summarize,
+            TestCodeMode::Skip => TestCode::Skip,
+        }
+    }
 }
 
+fn describe_syntax_errors(syntax_errors: &[SyntaxError]) -> String {
+    syntax_errors
+        .iter()
+        .map(|syntax_error| syntax_error.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Process each file, reading its contents with `read_file`. Directories are skipped.
+///
+/// `read_file` is usually `std::fs::read_to_string`, but can read from elsewhere, e.g. a git
+/// revision. `blame_file` returns the git blame for a file, given the contents that were read, if
+/// captured items should be annotated with who last changed them and when. If `docs` is given then documentation files it selects are
+/// included at its tier.
 pub fn process_files<'a>(
     files: impl Iterator<Item = file_system::File> + 'a,
-    go_config: &'a ParseConfig,
-    rust_config: &'a ParseConfig,
+    read_file: impl Fn(&Path) -> std::io::Result<String> + 'a,
+    blame_file: impl Fn(&Path, &str) -> Option<FileBlame> + 'a,
+    parse_configs: &'a ParseConfigs,
     glob_matcher: &'a GlobPatternMatcher,
+    docs: Option<&'a DocsSelector>,
 ) -> impl Iterator<Item = Result<String, FileProcessorError>> + 'a {
     files.into_iter().filter_map(move |file| {
         if file.kind != file_system::FileKind::File {
             return None;
         }
+        if let Some(reason) = file.skipped {
+            return Some(Err(FileProcessorError::FileSkipped(reason.into())));
+        }
+        let source_code = match read_file(&file.path) {
+            Ok(source_code) => source_code,
+            Err(e) => return Some(Err(FileProcessorError::ErrorReadingFile(e))),
+        };
+        if let Some(docs) = docs {
+            if !glob_matcher.matches(&file.path) && docs.is_documentation(&file.path) {
+                return Some(Ok(docs.format(&file.path, &source_code)));
+            }
+        }
+        let blame = blame_file(&file.path, &source_code);
         Some(process_file(
             &file.path,
-            go_config,
-            rust_config,
+            &source_code,
+            blame.as_ref(),
+            parse_configs,
             glob_matcher,
         ))
     })
 }
 
+/// A file's digest and its estimated size in tokens.
+#[derive(Serialize)]
+pub struct DigestedFile {
+    pub path: String,
+    pub digest: String,
+    pub tokens: usize,
+}
+
+/// The digests of the files in a directory, limited to a token budget.
+#[derive(Serialize)]
+pub struct BudgetedDigest {
+    pub files: Vec<DigestedFile>,
+    pub tokens: usize,
+
+    /// Files that were left out because they did not fit in the token budget.
+    pub omitted: Vec<String>,
+}
+
+/// Digest every supported file under `directory` in order, leaving out any file whose digest would
+/// take the total over `token_budget`. Paths in digest headers, and the paths matched against
+/// `glob_matcher`, are relative to `directory`. Files that the walk with `walk_options` skips, such
+/// as binary files with `skip_binary`, are left out.
+pub fn digest_directory(
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    walk_options: &WalkOptions,
+    parse_configs: &ParseConfigs,
+    glob_matcher: &GlobPatternMatcher,
+    token_budget: Option<usize>,
+) -> BudgetedDigest {
+    let mut result = BudgetedDigest {
+        files: vec![],
+        tokens: 0,
+        omitted: vec![],
+    };
+    for file in skip_walk_errors(get_files(
+        directory.to_path_buf(),
+        ignore_dirs,
+        walk_options,
+    )) {
+        if file.kind != FileKind::File || file.skipped.is_some() {
+            continue;
+        }
+        let path = relative_path(directory, &file.path);
+        let digest = std::fs::read_to_string(&file.path)
+            .map_err(FileProcessorError::ErrorReadingFile)
+            .and_then(|source_code| {
+                process_file(
+                    Path::new(&path),
+                    &source_code,
+                    None,
+                    parse_configs,
+                    glob_matcher,
+                )
+            });
+        let digest = match digest {
+            Ok(digest) => digest,
+            Err(FileProcessorError::UnsupportedFileKind(_))
+            | Err(FileProcessorError::FileSkipped(_)) => continue,
+            Err(e) => {
+                eprintln!("Error processing file {}: {}", file.path.display(), e);
+                continue;
+            }
+        };
+
+        let tokens = estimate_tokens(&digest);
+        if let Some(token_budget) = token_budget {
+            if result.tokens + tokens > token_budget {
+                result.omitted.push(path);
+                continue;
+            }
+        }
+        result.tokens += tokens;
+        result.files.push(DigestedFile {
+            path,
+            digest,
+            tokens,
+        });
+    }
+    result
+}
+
+/// The files found by a walk, reporting any errors, such as a directory that could not be read,
+/// and carrying on past them.
+pub fn skip_walk_errors(
+    files: impl Iterator<Item = Result<File, WalkError>>,
+) -> impl Iterator<Item = File> {
+    files.filter_map(|file| {
+        file.map_err(|e| eprintln!("Error walking directory: {}", e))
+            .ok()
+    })
+}
+
+/// `path` relative to `directory` as a string, or all of `path` if it is not under `directory`.
+pub fn relative_path(directory: &Path, path: &Path) -> String {
+    path.strip_prefix(directory)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Each language that has a parse config, and the name of its config file and of the language in
+/// language mappings, e.g. `rust` for `rust.toml`.
+const CONFIG_LANGUAGES: &[(&str, Language)] = &[
+    ("bash", Language::Bash),
+    ("clojure", Language::Clojure),
+    ("cmake", Language::CMake),
+    ("cpp", Language::Cpp),
+    ("dart", Language::Dart),
+    ("dockerfile", Language::Dockerfile),
+    ("fsharp", Language::FSharp),
+    ("go", Language::Go),
+    ("hcl", Language::Hcl),
+    ("java", Language::Java),
+    ("json", Language::Json),
+    ("julia", Language::Julia),
+    ("jupyter", Language::Jupyter),
+    ("make", Language::Make),
+    ("markdown", Language::Markdown),
+    ("nix", Language::Nix),
+    ("php", Language::Php),
+    ("proto", Language::Proto),
+    ("python", Language::Python),
+    ("rust", Language::Rust),
+    ("sql", Language::Sql),
+    ("svelte", Language::Svelte),
+    ("toml", Language::Toml),
+    ("yaml", Language::Yaml),
+];
+
+/// The default parse config for each supported language.
+pub struct ParseConfigs {
+    configs: HashMap<Language, ParseConfig>,
+    unknown: ParseConfig,
+    registry: LanguageRegistry,
+    fallback: bool,
+    syntax_errors: SyntaxErrorMode,
+    tests: TestCodeMode,
+    line_numbers: bool,
+}
+
+impl ParseConfigs {
+    pub fn new() -> ParseConfigs {
+        ParseConfigs {
+            configs: CONFIG_LANGUAGES
+                .iter()
+                .map(|&(_, language)| (language, default_parse_config_for_language(language)))
+                .collect(),
+            unknown: default_parse_config_for_language(Language::Unknown),
+            registry: LanguageRegistry::new(),
+            fallback: false,
+            syntax_errors: SyntaxErrorMode::default(),
+            tests: TestCodeMode::default(),
+            line_numbers: false,
+        }
+    }
+
+    /// Replace the default parse config of each language that has a config file in `directory`,
+    /// named after the language, e.g. `rust.toml` or `cpp.toml`. A missing directory is ignored.
+    pub fn load_config_files(&mut self, directory: &Path) -> Result<(), FileProcessorError> {
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(FileProcessorError::ErrorReadingFile(e)),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("toml") {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            let (language, config) = match self.for_name(name) {
+                Some(language_config) => language_config,
+                None => return Err(FileProcessorError::UnknownParseConfigLanguage(path)),
+            };
+            let source = std::fs::read_to_string(&path)?;
+            *config = parse_config_from_toml(language, &source)
+                .map_err(|e| FileProcessorError::ErrorLoadingParseConfig(path.clone(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Include the doc comments of captured items for every language.
+    pub fn set_include_doc_comments(&mut self, include_doc_comments: bool) {
+        for config in self.configs_mut() {
+            config.set_include_doc_comments(include_doc_comments);
+        }
+    }
+
+    /// Remove comments from captured items for every language.
+    pub fn set_strip_comments(&mut self, strip_comments: bool) {
+        for config in self.configs_mut() {
+            config.set_strip_comments(strip_comments);
+        }
+    }
+
+    /// Reduce captured items to their doc comments and signatures for every language.
+    pub fn set_signatures_only(&mut self, signatures_only: bool) {
+        for config in self.configs_mut() {
+            config.set_signatures_only(signatures_only);
+        }
+    }
+
+    /// Keep as much of each captured item as `granularity` says for every language.
+    pub fn set_granularity(&mut self, granularity: GranularityMode) {
+        for config in self.configs_mut() {
+            config.set_granularity(granularity.into());
+        }
+    }
+
+    /// The parse config of each supported language.
+    fn configs_mut(&mut self) -> impl Iterator<Item = &mut ParseConfig> {
+        self.configs.values_mut()
+    }
+
+    /// Digest files in languages without a parse config by heuristics, their first lines and lines
+    /// that look like definitions, rather than skipping them.
+    pub fn set_fallback(&mut self, fallback: bool) {
+        self.fallback = fallback;
+    }
+
+    /// Treat more files as being in a language, from a mapping such as `.pyi=python` for an
+    /// extension or `BUILD=python` for a file name. Languages are named as for config files.
+    pub fn add_language_mapping(&mut self, mapping: &str) -> Result<(), FileProcessorError> {
+        let invalid = || FileProcessorError::InvalidLanguageMapping(mapping.to_string());
+        let (pattern, name) = mapping.split_once('=').ok_or_else(invalid)?;
+        let (language, _) = self.for_name(name).ok_or_else(invalid)?;
+        match pattern.strip_prefix('.') {
+            Some("") => return Err(invalid()),
+            Some(extension) => self.registry.register_extension(extension, language),
+            None if pattern.is_empty() => return Err(invalid()),
+            None => self.registry.register_file_name(pattern, language),
+        }
+        Ok(())
+    }
+
+    /// Annotate each captured item with the lines it spans in the source, such as `// lines
+    /// 120-164`, so that items can be found in the source from the digest.
+    pub fn set_line_numbers(&mut self, line_numbers: bool) {
+        self.line_numbers = line_numbers;
+    }
+
+    /// Give up on parsing a file after `timeout`, or never if it is None, which is the default.
+    pub fn set_parse_timeout(&mut self, timeout: Option<Duration>) {
+        for config in self.configs_mut() {
+            config.set_timeout(timeout);
+        }
+    }
+
+    /// Set what to do with tests, both whole files of them and tests in other files. The default is
+    /// to keep them.
+    pub fn set_test_code_mode(&mut self, tests: TestCodeMode) {
+        for config in self.configs_mut() {
+            config.set_test_code(tests.into());
+        }
+        self.tests = tests;
+    }
+
+    /// Set what to do with files that have syntax errors. The default is to warn.
+    pub fn set_syntax_error_mode(&mut self, syntax_errors: SyntaxErrorMode) {
+        self.syntax_errors = syntax_errors;
+    }
+
+    /// The language and parse config for a language's config file name, e.g. `rust`.
+    fn for_name(&mut self, name: &str) -> Option<(Language, &mut ParseConfig)> {
+        let &(_, language) = CONFIG_LANGUAGES
+            .iter()
+            .find(|(config_name, _)| *config_name == name)?;
+        Some((language, self.configs.get_mut(&language)?))
+    }
+
+    /// Returns the parse config for a file based on its name or extension. If the language is not
+    /// supported this is the fallback config if fallback is on, and otherwise None.
+    pub fn for_path(&self, file_path: &Path) -> Option<&ParseConfig> {
+        self.for_language_path(file_path)
+            .or_else(|| self.fallback.then_some(&self.unknown))
+    }
+
+    /// Returns the parse config for a file based on its name or extension, or failing that on its
+    /// contents, such as a `#!/usr/bin/env python` line. If the language is still unknown this is
+    /// the fallback config if fallback is on, and otherwise None.
+    pub fn for_file(&self, file_path: &Path, source_code: &str) -> Option<&ParseConfig> {
+        self.registry
+            .language_for_file(file_path, source_code)
+            .map(|language| self.for_language(language))
+            .or_else(|| self.fallback.then_some(&self.unknown))
+    }
+
+    fn for_language_path(&self, file_path: &Path) -> Option<&ParseConfig> {
+        let language = self.registry.language_for_path(file_path)?;
+        Some(self.for_language(language))
+    }
+
+    /// The parse config for a language.
+    fn for_language(&self, language: Language) -> &ParseConfig {
+        self.configs.get(&language).unwrap_or(&self.unknown)
+    }
+}
+
+/// The directory that language parse config files are loaded from, `code-digest/languages` in
+/// `$XDG_CONFIG_HOME` or `~/.config`.
+pub fn parse_config_directory() -> PathBuf {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.config").as_ref()));
+    config_home.join("code-digest").join("languages")
+}
+
+impl Default for ParseConfigs {
+    fn default() -> Self {
+        ParseConfigs::new()
+    }
+}
+
 pub fn process_file(
     file_path: &Path,
-    go_config: &ParseConfig,
-    rust_config: &ParseConfig,
+    source_code: &str,
+    blame: Option<&FileBlame>,
+    parse_configs: &ParseConfigs,
     glob_matcher: &GlobPatternMatcher,
 ) -> Result<String, FileProcessorError> {
-    let source_code =
-        std::fs::read_to_string(file_path).map_err(FileProcessorError::ErrorReadingFile)?;
-    let mut output = String::new();
-
+    let _span = tracing::debug_span!("file", path = %file_path.display()).entered();
     if glob_matcher.matches(file_path) {
-        output.push_str(&format!("`{}`", file_path.display()));
-        output.push_str(&format!("```\n{}\n```\n", source_code));
-        return Ok(output);
+        return Ok(format_full_file(file_path, source_code));
     }
-
-    let extension = file_path.extension();
-    if extension.is_none() {
-        return Err(FileProcessorError::FileSkipped(
-            FileSkipReason::FileExtension,
-        ));
+    if parse_configs.tests == TestCodeMode::Skip && is_test_path(file_path) {
+        return Err(FileProcessorError::FileSkipped(FileSkipReason::TestFile));
     }
-    let extension = extension.unwrap().to_str().unwrap();
-    let parse_config = match extension {
-        "go" => go_config,
-        "rs" => rust_config,
-        _ => {
+
+    let parse_config = match (
+        parse_configs.for_file(file_path, source_code),
+        file_path.extension(),
+    ) {
+        (Some(parse_config), _) => parse_config,
+        (None, None) => {
+            return Err(FileProcessorError::FileSkipped(
+                FileSkipReason::FileExtension,
+            ))
+        }
+        (None, Some(extension)) => {
             return Err(FileProcessorError::UnsupportedFileKind(
-                extension.to_string(),
+                extension.to_string_lossy().to_string(),
             ))
         }
     };
-    let parsed = parse(&source_code, parse_config);
+    let parsed = parse_with_syntax_errors(source_code, parse_config);
     if parsed.is_err() {
         return Err(FileProcessorError::ErrorParsingFile(parsed.err().unwrap()));
     }
     let parsed = parsed.unwrap();
+    if !parsed.syntax_errors.is_empty() {
+        match parse_configs.syntax_errors {
+            SyntaxErrorMode::Ignore => {}
+            SyntaxErrorMode::Warn => eprintln!(
+                "Warning: {} has syntax errors and may be only partially digested: {}",
+                file_path.display(),
+                describe_syntax_errors(&parsed.syntax_errors)
+            ),
+            SyntaxErrorMode::Fail => {
+                return Err(FileProcessorError::SyntaxErrors(
+                    file_path.to_path_buf(),
+                    parsed.syntax_errors,
+                ))
+            }
+        }
+    }
 
+    Ok(format_key_contents(
+        file_path,
+        parse_config.language(),
+        &parsed.key_contents,
+        blame,
+        parse_configs.line_numbers,
+    ))
+}
+
+/// Render the whole of a file as a fenced code block headed by the file path.
+pub fn format_full_file(file_path: &Path, source_code: &str) -> String {
+    format!("`{}`\n```\n{}\n```\n", file_path.display(), source_code)
+}
+
+/// Render captured items as a fenced code block in `language` headed by the file path, annotating each item with
+/// the lines it spans in the source if `line_numbers` is set, and its last change if `blame` is
+/// given.
+pub fn format_key_contents(
+    file_path: &Path,
+    language: Language,
+    key_contents: &[KeyContent],
+    blame: Option<&FileBlame>,
+    line_numbers: bool,
+) -> String {
+    let line_comment = language.line_comment();
+
+    let mut output = String::new();
     output.push_str(&format!("`{}`\n", file_path.display()));
-    match extension {
-        "go" => {
-            output.push_str("```go\n");
+    output.push_str(&format!("```{}\n", language.fence_name()));
+
+    for (i, key_content) in key_contents.iter().enumerate() {
+        if line_numbers {
+            output.push_str(&format!(
+                "{} {}\n",
+                line_comment,
+                format_line_range(key_content.start_line, key_content.end_line)
+            ));
         }
-        "rs" => {
-            output.push_str("```rust\n");
+        if let Some(change) =
+            blame.and_then(|blame| blame.last_change(key_content.start_line, key_content.end_line))
+        {
+            output.push_str(&format!(
+                "{} Last modified {} by {}\n",
+                line_comment,
+                format_date(change.time),
+                change.author
+            ));
         }
-        _ => unreachable!(),
-    }
-
-    for (i, key_content) in parsed.iter().enumerate() {
         output.push_str(&key_content.content.to_string());
         output.push('\n');
-        if i < parsed.len() - 1 {
+        if i < key_contents.len() - 1 {
             output.push('\n');
         }
     }
     output.push_str("```\n");
 
-    Ok(output)
+    output
+}
+
+/// A rough token count, assuming about four characters per token as is typical for code with
+/// common LLM tokenizers.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// A captured item and where it is in the source file, for JSON output.
+#[derive(Serialize)]
+pub struct Symbol {
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: ItemKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+impl From<KeyContent> for Symbol {
+    fn from(key_content: KeyContent) -> Symbol {
+        Symbol {
+            content: key_content.content,
+            start_line: key_content.start_line,
+            end_line: key_content.end_line,
+            kind: key_content.kind,
+            name: key_content.name,
+            start_byte: key_content.byte_range.start,
+            end_byte: key_content.byte_range.end,
+        }
+    }
+}
+
+/// Format seconds since the Unix epoch as a UTC date, e.g. `2023-05-26`.
+fn format_date(time: i64) -> String {
+    match chrono::DateTime::from_timestamp(time, 0) {
+        Some(date_time) => date_time.format("%Y-%m-%d").to_string(),
+        None => "unknown date".to_string(),
+    }
+}
+
+/// Lines as `line 12` or `lines 120-164`.
+fn format_line_range(start_line: usize, end_line: usize) -> String {
+    match start_line == end_line {
+        true => format!("line {}", start_line),
+        false => format!("lines {}-{}", start_line, end_line),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use file_system::{File, FileKind};
-    use language_parsers::{default_parse_config_for_language, Language};
-
     use crate::GlobPatternMatcher;
+    use file_system::{File, FileKind};
 
     use super::*;
 
     #[test]
     fn test_process_file_rust() {
-        let rust_config = default_parse_config_for_language(Language::Rust);
-        let go_config = default_parse_config_for_language(Language::Go);
+        let parse_configs = ParseConfigs::new();
         let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
 
-        // Create a temporary file with Rust code
-        let temp_dir = tempfile::tempdir().unwrap();
-        let file_path = temp_dir.path().join("test.rs");
-        std::fs::write(
-            &file_path,
-            r#"
+        let file_path = Path::new("test.rs");
+        let source_code = r#"
 fn main() {
     println!("Hello, world!");
 }
-"#,
-        )
-        .unwrap();
+"#;
 
-        let result = process_file(&file_path, &go_config, &rust_config, &glob_matcher);
+        let result = process_file(file_path, source_code, None, &parse_configs, &glob_matcher);
         assert!(result.is_ok());
         let actual_output = result.unwrap();
 
@@ -159,10 +699,244 @@ fn main() {{
         assert_eq!(actual_output, expected_output);
     }
 
+    #[test]
+    fn test_process_file_line_numbers() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let mut parse_configs = ParseConfigs::new();
+        parse_configs.set_line_numbers(true);
+        let source_code = "use std::fmt;\n\nfn main() {\n    run();\n}\n";
+        let result = process_file(
+            Path::new("main.rs"),
+            source_code,
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "`main.rs`\n```rust\n// line 1\nuse std::fmt;\n\n// lines 3-5\nfn main() {\n    // ...\n}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_process_file_syntax_errors() {
+        let mut parse_configs = ParseConfigs::new();
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let file_path = Path::new("test.rs");
+        let source_code = "fn main() {}\n\nfn broken( {\n}\n";
+
+        let result = process_file(file_path, source_code, None, &parse_configs, &glob_matcher);
+        assert!(result.unwrap().contains("fn main()"));
+
+        parse_configs.set_syntax_error_mode(SyntaxErrorMode::Fail);
+        let result = process_file(file_path, source_code, None, &parse_configs, &glob_matcher);
+        match result {
+            Err(e @ FileProcessorError::SyntaxErrors(..)) => {
+                assert_eq!(
+                    e.to_string(),
+                    "Syntax errors in test.rs: line 3: missing `)`"
+                )
+            }
+            _ => panic!("expected syntax errors, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_process_file_fallback() {
+        let mut parse_configs = ParseConfigs::new();
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let file_path = Path::new("greet.rb");
+        let source_code = "# Greets people.\nclass Greeter\nend\n";
+
+        let result = process_file(file_path, source_code, None, &parse_configs, &glob_matcher);
+        assert!(matches!(
+            result,
+            Err(FileProcessorError::UnsupportedFileKind(_))
+        ));
+
+        parse_configs.set_fallback(true);
+        let result = process_file(file_path, source_code, None, &parse_configs, &glob_matcher);
+        assert_eq!(
+            result.unwrap(),
+            "`greet.rb`\n```\n# Greets people.\nclass Greeter\nend\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_load_config_files() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join("rust.toml"),
+            "[selectors]\nfunction_item = \"capture_all\"\n",
+        )
+        .unwrap();
+
+        let mut parse_configs = ParseConfigs::new();
+        parse_configs.load_config_files(temp_dir.path()).unwrap();
+        let result = process_file(
+            Path::new("test.rs"),
+            "fn main() {\n    run();\n}\n",
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "`test.rs`\n```rust\nfn main() {\n    run();\n}\n```\n"
+        );
+
+        std::fs::write(temp_dir.path().join("cobol.toml"), "").unwrap();
+        assert!(matches!(
+            parse_configs.load_config_files(temp_dir.path()),
+            Err(FileProcessorError::UnknownParseConfigLanguage(_))
+        ));
+        assert!(parse_configs
+            .load_config_files(&temp_dir.path().join("missing"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_process_file_detected_language() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let mut parse_configs = ParseConfigs::new();
+        parse_configs.set_line_numbers(true);
+        let result = process_file(
+            Path::new("bin/tool"),
+            "#!/usr/bin/env python3\n\ndef main():\n    run()\n",
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "`bin/tool`\n```python\n# lines 3-4\ndef main():\n    ...\n```\n"
+        );
+
+        assert!(matches!(
+            process_file(
+                Path::new("LICENSE"),
+                "Permission is hereby granted\n",
+                None,
+                &parse_configs,
+                &glob_matcher,
+            ),
+            Err(FileProcessorError::FileSkipped(
+                FileSkipReason::FileExtension
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_process_file_tests() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let mut parse_configs = ParseConfigs::new();
+        let source_code = "def test_parse():\n    assert parse('') is None\n";
+        let result = process_file(
+            Path::new("tests/test_parser.py"),
+            source_code,
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert!(result.contains("def test_parse():\n    ..."));
+
+        parse_configs.set_test_code_mode(TestCodeMode::Summarize);
+        let result = process_file(
+            Path::new("tests/test_parser.py"),
+            source_code,
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert!(result.contains("def test_parse():"));
+        assert!(!result.contains("..."));
+
+        parse_configs.set_test_code_mode(TestCodeMode::Skip);
+        assert!(matches!(
+            process_file(
+                Path::new("tests/test_parser.py"),
+                source_code,
+                None,
+                &parse_configs,
+                &glob_matcher,
+            ),
+            Err(FileProcessorError::FileSkipped(FileSkipReason::TestFile))
+        ));
+    }
+
+    #[test]
+    fn test_process_file_jupyter() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let source_code = r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis"]},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["import numpy as np"]}
+ ]
+}"##;
+        let result = process_file(
+            Path::new("analysis.ipynb"),
+            source_code,
+            None,
+            &ParseConfigs::new(),
+            &glob_matcher,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "`analysis.ipynb`\n```python\n# Analysis\n\nimport numpy as np\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_config_languages() {
+        let mut parse_configs = ParseConfigs::new();
+        for &(name, language) in CONFIG_LANGUAGES {
+            assert_eq!(parse_configs.for_language(language).language(), language);
+            assert_eq!(parse_configs.for_name(name).unwrap().1.language(), language);
+        }
+        assert_eq!(
+            parse_configs.for_language(Language::Unknown).language(),
+            Language::Unknown
+        );
+        assert!(parse_configs.for_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_add_language_mapping() {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let mut parse_configs = ParseConfigs::new();
+        assert!(parse_configs.for_path(Path::new("typing.pyi")).is_none());
+
+        parse_configs.add_language_mapping(".pyi=python").unwrap();
+        parse_configs.add_language_mapping("BUILD=python").unwrap();
+        let result = process_file(
+            Path::new("pkg/BUILD"),
+            "def rule(name):\n    pass\n",
+            None,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert!(result.starts_with("`pkg/BUILD`\n```python\ndef rule(name):"));
+        assert!(parse_configs.for_path(Path::new("typing.pyi")).is_some());
+
+        for mapping in ["pyi", ".pyi=cobol", "=python", ".=python"] {
+            assert!(matches!(
+                parse_configs.add_language_mapping(mapping),
+                Err(FileProcessorError::InvalidLanguageMapping(_))
+            ));
+        }
+    }
+
     #[test]
     fn test_process_files() {
-        let rust_config = default_parse_config_for_language(Language::Rust);
-        let go_config = default_parse_config_for_language(Language::Go);
+        let parse_configs = ParseConfigs::new();
         let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
 
         // Create a temporary file with Rust code
@@ -198,16 +972,29 @@ func main() {
                 path: rust_file_path.clone(),
                 kind: FileKind::File,
                 depth: 0,
+                skipped: None,
+                size: 0,
+                modified: None,
             },
             File {
                 path: go_file_path.clone(),
                 kind: FileKind::File,
                 depth: 0,
+                skipped: None,
+                size: 0,
+                modified: None,
             },
         ];
 
-        let results: Vec<_> =
-            process_files(files.into_iter(), &go_config, &rust_config, &glob_matcher).collect();
+        let results: Vec<_> = process_files(
+            files.into_iter(),
+            |path| std::fs::read_to_string(path),
+            |_, _| None,
+            &parse_configs,
+            &glob_matcher,
+            None,
+        )
+        .collect();
 
         assert_eq!(results.len(), 2);
 
@@ -237,4 +1024,35 @@ func main() {{
         assert_eq!(results[0].as_ref().unwrap(), &rust_expected_output);
         assert_eq!(results[1].as_ref().unwrap(), &go_expected_output);
     }
+
+    #[test]
+    fn test_process_files_skipped() {
+        let parse_configs = ParseConfigs::new();
+        let glob_matcher = GlobPatternMatcher::new();
+        let files = vec![File {
+            path: PathBuf::from("data/huge.json"),
+            kind: FileKind::File,
+            depth: 1,
+            skipped: Some(SkipReason::TooLarge),
+            size: 0,
+            modified: None,
+        }];
+
+        let results: Vec<_> = process_files(
+            files.into_iter(),
+            |path| panic!("read {} although it was skipped", path.display()),
+            |_, _| None,
+            &parse_configs,
+            &glob_matcher,
+            None,
+        )
+        .collect();
+
+        assert!(matches!(
+            results[..],
+            [Err(FileProcessorError::FileSkipped(
+                FileSkipReason::TooLarge
+            ))]
+        ));
+    }
 }
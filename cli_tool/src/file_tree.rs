@@ -126,7 +126,12 @@ mod tests {
         File::create(file_a2).unwrap();
         File::create(file_b1).unwrap();
 
-        let files = file_system::get_files(temp_dir.into_path(), &[]);
+        let files = file_system::get_files(
+            temp_dir.path().to_path_buf(),
+            &[],
+            &file_system::WalkOptions::default(),
+        )
+        .map(Result::unwrap);
 
         let mut output = String::new();
 
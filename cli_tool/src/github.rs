@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug)]
+pub enum GithubError {
+    #[error("Invalid GitHub repository {0:?}, expected org/repo or org/repo@ref")]
+    InvalidRepository(String),
+
+    #[error("Error downloading repository: {0}")]
+    ErrorDownloading(Box<ureq::Error>),
+
+    #[error("Error extracting repository: {0}")]
+    ErrorExtracting(#[from] std::io::Error),
+}
+
+/// A GitHub repository and optionally a branch, tag, or commit, written as `org/repo[@ref]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GithubRepository {
+    pub owner: String,
+    pub repo: String,
+
+    /// Branch, tag, or commit to download, or None for the default branch.
+    pub reference: Option<String>,
+}
+
+impl FromStr for GithubRepository {
+    type Err = GithubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GithubError::InvalidRepository(s.to_string());
+        let (name, reference) = match s.split_once('@') {
+            Some((name, reference)) if !reference.is_empty() => (name, Some(reference.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (s, None),
+        };
+        let (owner, repo) = name.split_once('/').ok_or_else(invalid)?;
+        if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+            return Err(invalid());
+        }
+        Ok(GithubRepository {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            reference,
+        })
+    }
+}
+
+impl GithubRepository {
+    fn tarball_url(&self) -> String {
+        let mut url = format!(
+            "https://api.github.com/repos/{}/{}/tarball",
+            self.owner, self.repo
+        );
+        if let Some(reference) = &self.reference {
+            url.push('/');
+            url.push_str(reference);
+        }
+        url
+    }
+
+    /// Download the repository's files into `destination` using the tarball endpoint, so git does
+    /// not need to be installed. `token` is sent if given, for private repositories and higher
+    /// rate limits.
+    pub fn download(&self, token: Option<&str>, destination: &Path) -> Result<(), GithubError> {
+        let mut request = ureq::get(&self.tarball_url())
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "code-digest");
+        if let Some(token) = token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        let response = request
+            .call()
+            .map_err(|e| GithubError::ErrorDownloading(Box::new(e)))?;
+        extract_tarball(response.into_reader(), destination)
+    }
+}
+
+/// Extract a gzipped tarball into `destination`, dropping the single top-level directory that
+/// GitHub wraps the files in, e.g. `org-repo-0123abc/`.
+fn extract_tarball(tarball: impl Read, destination: &Path) -> Result<(), GithubError> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+        let mut components = path.components();
+        components.next();
+        let relative_path: PathBuf = components.collect();
+        let is_safe = relative_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if relative_path.as_os_str().is_empty() || !is_safe {
+            continue;
+        }
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            continue;
+        }
+        let target = destination.join(relative_path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_repository() {
+        assert_eq!(
+            "asimihsan/code-digest".parse::<GithubRepository>().unwrap(),
+            GithubRepository {
+                owner: "asimihsan".to_string(),
+                repo: "code-digest".to_string(),
+                reference: None,
+            }
+        );
+        assert_eq!(
+            "asimihsan/code-digest@v0.6.0"
+                .parse::<GithubRepository>()
+                .unwrap()
+                .tarball_url(),
+            "https://api.github.com/repos/asimihsan/code-digest/tarball/v0.6.0"
+        );
+        assert!("code-digest".parse::<GithubRepository>().is_err());
+        assert!("asimihsan/code-digest@"
+            .parse::<GithubRepository>()
+            .is_err());
+        assert!("a/b/c".parse::<GithubRepository>().is_err());
+    }
+
+    #[test]
+    fn test_extract_tarball() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            vec![],
+            flate2::Compression::default(),
+        ));
+        for (path, contents) in [
+            ("org-repo-0123abc/src/main.rs", "fn main() {}\n"),
+            ("org-repo-0123abc/README.md", "# Hello\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        extract_tarball(tarball.as_slice(), temp_dir.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("src").join("main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(temp_dir.path().join("README.md").is_file());
+    }
+}
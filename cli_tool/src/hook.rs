@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::path::{Path, PathBuf};
+
+use file_system::{FileKind, GitError, GitTree, GlobPatternMatcher, WalkOptions};
+
+use crate::file_processor::{process_file, relative_path, FileProcessorError, ParseConfigs};
+
+#[derive(thiserror::Error, Debug)]
+pub enum HookError {
+    #[error("Error reading staged files: {0}")]
+    GitError(#[from] GitError),
+
+    #[error("Error reading digest file {0}: {1}")]
+    ErrorReadingDigest(PathBuf, std::io::Error),
+
+    #[error("Error writing digest file {0}: {1}")]
+    ErrorWritingDigest(PathBuf, std::io::Error),
+}
+
+/// Digest the files staged in the git index under `directory`, i.e. as they are about to be
+/// committed. Paths are relative to `directory`, so the digest is the same wherever the repository
+/// is checked out. `digest_file` itself is left out so that it does not digest itself.
+pub fn staged_digest(
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    digest_file: &Path,
+    parse_configs: &ParseConfigs,
+    glob_matcher: &GlobPatternMatcher,
+) -> Result<String, HookError> {
+    let git_tree = GitTree::open_index(directory.to_path_buf())?;
+    let digest_file = digest_file.canonicalize().ok();
+
+    let mut digest = String::new();
+    let walk_options = WalkOptions {
+        skip_binary: true,
+        ..Default::default()
+    };
+    for file in git_tree.files(ignore_dirs, &walk_options)? {
+        if file.kind != FileKind::File || file.skipped.is_some() {
+            continue;
+        }
+        if digest_file.is_some() && file.path.canonicalize().ok() == digest_file {
+            continue;
+        }
+        let path = relative_path(directory, &file.path);
+        let result = git_tree
+            .read_to_string(&file.path)
+            .map_err(FileProcessorError::ErrorReadingFile)
+            .and_then(|source_code| {
+                process_file(
+                    Path::new(&path),
+                    &source_code,
+                    None,
+                    parse_configs,
+                    glob_matcher,
+                )
+            });
+        match result {
+            Ok(file_digest) => {
+                digest.push_str(&file_digest);
+                digest.push('\n');
+            }
+            Err(FileProcessorError::UnsupportedFileKind(_))
+            | Err(FileProcessorError::FileSkipped(_)) => {}
+            Err(e) => eprintln!("Error processing file {}: {}", path, e),
+        }
+    }
+    Ok(digest)
+}
+
+/// Returns true if `digest_file` exists and contains exactly `digest`.
+pub fn is_up_to_date(digest_file: &Path, digest: &str) -> Result<bool, HookError> {
+    match std::fs::read_to_string(digest_file) {
+        Ok(existing) => Ok(existing == digest),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(HookError::ErrorReadingDigest(digest_file.to_path_buf(), e)),
+    }
+}
+
+/// Write `digest` to `digest_file` if it is out of date. Returns true if the file was changed.
+pub fn write_if_changed(digest_file: &Path, digest: &str) -> Result<bool, HookError> {
+    if is_up_to_date(digest_file, digest)? {
+        return Ok(false);
+    }
+    std::fs::write(digest_file, digest)
+        .map_err(|e| HookError::ErrorWritingDigest(digest_file.to_path_buf(), e))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+
+    fn stage_all(repository: &Repository) {
+        let mut index = repository.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+    }
+
+    #[test]
+    fn test_staged_digest() {
+        let parse_configs = ParseConfigs::new();
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&["*.md".to_string()]).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let digest_file = temp_dir.path().join("ARCHITECTURE.digest.md");
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("data.rs"), b"\x7fELF\x02\x01\x01\0").unwrap();
+        std::fs::write(&digest_file, "stale").unwrap();
+        stage_all(&repository);
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn unstaged() {}\n").unwrap();
+
+        let digest = staged_digest(
+            temp_dir.path(),
+            &[],
+            &digest_file,
+            &parse_configs,
+            &glob_matcher,
+        )
+        .unwrap();
+        assert_eq!(
+            digest,
+            "`main.rs`\n```rust\nfn main() {\n    // ...\n}\n```\n\n"
+        );
+
+        assert!(!is_up_to_date(&digest_file, &digest).unwrap());
+        assert!(write_if_changed(&digest_file, &digest).unwrap());
+        assert!(is_up_to_date(&digest_file, &digest).unwrap());
+        assert!(!write_if_changed(&digest_file, &digest).unwrap());
+
+        assert!(!is_up_to_date(&temp_dir.path().join("missing.md"), &digest).unwrap());
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use crate::file_processor::BudgetedDigest;
+
+/// Reloads the page when the server sends `reload` over the WebSocket at `/live-reload`.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+new WebSocket(`ws://${location.host}/live-reload`).onmessage = (event) => {
+  if (event.data === "reload") location.reload();
+};
+</script>"#;
+
+/// Render a digest as a standalone HTML page, with a section per file. If `live_reload` is true
+/// then the page reloads itself when the server says that files have changed.
+pub fn render_digest_html(title: &str, digest: &BudgetedDigest, live_reload: bool) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str(
+        "<style>body { font-family: sans-serif; margin: 2em; } \
+         pre { background: #f6f8fa; padding: 1em; overflow-x: auto; }</style>\n",
+    );
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape(title)));
+    html.push_str(&format!(
+        "<p>{} files, about {} tokens</p>\n",
+        digest.files.len(),
+        digest.tokens
+    ));
+    for file in &digest.files {
+        html.push_str(&format!(
+            "<section>\n<h2><code>{}</code></h2>\n<pre><code>{}</code></pre>\n</section>\n",
+            escape(&file.path),
+            escape(code_block(&file.digest))
+        ));
+    }
+    if live_reload {
+        html.push_str(LIVE_RELOAD_SCRIPT);
+        html.push('\n');
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// The contents of the fenced code block in a file's digest, without the path heading and fences.
+fn code_block(digest: &str) -> &str {
+    let start = match digest.find("```") {
+        Some(start) => start,
+        None => return digest,
+    };
+    let contents_start = match digest[start..].find('\n') {
+        Some(newline) => start + newline + 1,
+        None => return digest,
+    };
+    let contents_end = digest.rfind("```").unwrap_or(digest.len());
+    if contents_end < contents_start {
+        return digest;
+    }
+    digest[contents_start..contents_end].trim_end_matches('\n')
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_processor::DigestedFile;
+
+    #[test]
+    fn test_render_digest_html() {
+        let digest = BudgetedDigest {
+            files: vec![DigestedFile {
+                path: "src/main.rs".to_string(),
+                digest:
+                    "`src/main.rs`\n```rust\nfn main() -> Result<(), Error> {\n    // ...\n}\n```\n"
+                        .to_string(),
+                tokens: 12,
+            }],
+            tokens: 12,
+            omitted: vec![],
+        };
+
+        let html = render_digest_html("project", &digest, false);
+        assert!(html.contains("<h2><code>src/main.rs</code></h2>"));
+        assert!(html.contains(
+            "<pre><code>fn main() -&gt; Result&lt;(), Error&gt; {\n    // ...\n}</code></pre>"
+        ));
+        assert!(!html.contains("WebSocket"));
+
+        let html = render_digest_html("project", &digest, true);
+        assert!(html.contains("/live-reload"));
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::path::Path;
+
+use file_system::{File, FileKind};
+use language_parsers::parse;
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, INDEXED, STORED, TEXT};
+use tantivy::{doc, Index, TantivyDocument};
+
+use crate::file_processor::ParseConfigs;
+
+#[derive(thiserror::Error, Debug)]
+pub enum IndexError {
+    #[error("Error creating index directory: {0}")]
+    ErrorCreatingDirectory(#[from] std::io::Error),
+
+    #[error("Error opening index directory: {0}")]
+    ErrorOpeningDirectory(#[from] tantivy::directory::error::OpenDirectoryError),
+
+    #[error("Index error: {0}")]
+    Tantivy(#[from] tantivy::TantivyError),
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(#[from] tantivy::query::QueryParserError),
+}
+
+/// A captured item that matched a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub path: String,
+    pub start_line: u64,
+    pub end_line: u64,
+    pub content: String,
+    pub score: f32,
+}
+
+struct Fields {
+    path: Field,
+    content: Field,
+    start_line: Field,
+    end_line: Field,
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        path: builder.add_text_field("path", TEXT | STORED),
+        content: builder.add_text_field("content", TEXT | STORED),
+        start_line: builder.add_u64_field("start_line", INDEXED | STORED),
+        end_line: builder.add_u64_field("end_line", INDEXED | STORED),
+    };
+    (builder.build(), fields)
+}
+
+/// Build a full-text search index in `index_directory` over the items captured from every file,
+/// replacing anything indexed there before. Returns the number of items indexed.
+pub fn build_index(
+    index_directory: &Path,
+    files: impl Iterator<Item = File>,
+    read_file: impl Fn(&Path) -> std::io::Result<String>,
+    parse_configs: &ParseConfigs,
+) -> Result<usize, IndexError> {
+    std::fs::create_dir_all(index_directory)?;
+    let (schema, fields) = schema();
+    let index = Index::open_or_create(MmapDirectory::open(index_directory)?, schema)?;
+    let mut writer = index.writer(50_000_000)?;
+    writer.delete_all_documents()?;
+
+    let mut count = 0;
+    for file in files {
+        if file.kind != FileKind::File || file.skipped.is_some() {
+            continue;
+        }
+        let parse_config = match parse_configs.for_path(&file.path) {
+            Some(parse_config) => parse_config,
+            None => continue,
+        };
+        let key_contents = match read_file(&file.path)
+            .map_err(|e| e.to_string())
+            .and_then(|source_code| parse(&source_code, parse_config).map_err(|e| e.to_string()))
+        {
+            Ok(key_contents) => key_contents,
+            Err(e) => {
+                eprintln!("Error indexing file {}: {}", file.path.display(), e);
+                continue;
+            }
+        };
+        for key_content in key_contents {
+            writer.add_document(doc!(
+                fields.path => file.path.display().to_string(),
+                fields.content => key_content.content,
+                fields.start_line => key_content.start_line as u64,
+                fields.end_line => key_content.end_line as u64,
+            ))?;
+            count += 1;
+        }
+    }
+    writer.commit()?;
+    Ok(count)
+}
+
+/// Search the index in `index_directory` for captured items matching `query`, best matches first.
+/// `query` uses tantivy's query syntax and searches both the item content and its file path.
+pub fn search(
+    index_directory: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>, IndexError> {
+    let index = Index::open(MmapDirectory::open(index_directory)?)?;
+    let (_, fields) = schema();
+    let searcher = index.reader()?.searcher();
+    let query =
+        QueryParser::for_index(&index, vec![fields.content, fields.path]).parse_query(query)?;
+
+    let mut hits = vec![];
+    for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit))? {
+        let document: TantivyDocument = searcher.doc(address)?;
+        let text = |field| {
+            document
+                .get_first(field)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let number = |field| {
+            document
+                .get_first(field)
+                .and_then(|value| value.as_u64())
+                .unwrap_or_default()
+        };
+        hits.push(SearchHit {
+            path: text(fields.path),
+            start_line: number(fields.start_line),
+            end_line: number(fields.end_line),
+            content: text(fields.content),
+            score,
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_index_and_search() {
+        let parse_configs = ParseConfigs::new();
+        let index_dir = tempfile::tempdir().unwrap();
+        let files = vec![
+            File {
+                path: "src/parser.rs".into(),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+            File {
+                path: "README.md".into(),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+        ];
+        let read_file = |_: &Path| {
+            Ok("struct Parser {\n    tokens: Vec<Token>,\n}\n\nfn tokenize(input: &str) -> Vec<Token> {\n    vec![]\n}\n".to_string())
+        };
+
+        let count = build_index(
+            index_dir.path(),
+            files.clone().into_iter(),
+            read_file,
+            &parse_configs,
+        )
+        .unwrap();
+        assert_eq!(count, 2);
+
+        let hits = search(index_dir.path(), "tokenize", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "src/parser.rs");
+        assert_eq!((hits[0].start_line, hits[0].end_line), (5, 7));
+        assert!(hits[0].content.starts_with("fn tokenize"));
+
+        assert_eq!(search(index_dir.path(), "parser", 10).unwrap().len(), 2);
+
+        // Rebuilding replaces the previous index rather than adding to it.
+        build_index(
+            index_dir.path(),
+            files.into_iter(),
+            read_file,
+            &parse_configs,
+        )
+        .unwrap();
+        assert_eq!(search(index_dir.path(), "tokenize", 10).unwrap().len(), 1);
+    }
+}
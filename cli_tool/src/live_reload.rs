@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::Engine;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use sha1::{Digest, Sha1};
+use tiny_http::{Header, ReadWrite, Request, Response};
+
+/// How long to wait for more changes before telling pages to reload, so that e.g. saving many files
+/// at once only reloads once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+type Clients = Arc<Mutex<Vec<Box<dyn ReadWrite + Send>>>>;
+
+/// Watches a directory and sends `reload` over every open WebSocket when files in it change.
+pub struct LiveReload {
+    clients: Clients,
+
+    // Watching stops when the watcher is dropped.
+    _watcher: RecommendedWatcher,
+}
+
+impl LiveReload {
+    /// Start watching `directory` recursively, ignoring changes under `.git` or any of
+    /// `ignore_dirs`.
+    pub fn watch(directory: &Path, ignore_dirs: &[PathBuf]) -> Result<LiveReload, notify::Error> {
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(directory, RecursiveMode::Recursive)?;
+
+        let clients: Clients = Arc::new(Mutex::new(vec![]));
+        let watched_clients = clients.clone();
+        let directory = directory.to_path_buf();
+        let ignore_dirs = ignore_dirs.to_vec();
+        std::thread::spawn(move || {
+            let is_relevant = |event: notify::Result<notify::Event>| {
+                event.is_ok_and(|event| {
+                    event
+                        .paths
+                        .iter()
+                        .any(|path| !is_ignored(&directory, &ignore_dirs, path))
+                })
+            };
+            while let Ok(event) = receiver.recv() {
+                if !is_relevant(event) {
+                    continue;
+                }
+                loop {
+                    match receiver.recv_timeout(DEBOUNCE) {
+                        Ok(_) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                let frame = text_frame("reload");
+                watched_clients
+                    .lock()
+                    .unwrap()
+                    .retain_mut(|client| client.write_all(&frame).and(client.flush()).is_ok());
+            }
+        });
+
+        Ok(LiveReload {
+            clients,
+            _watcher: watcher,
+        })
+    }
+
+    /// Complete the WebSocket handshake for `request` and keep the connection open to send reloads
+    /// to. Requests that are not WebSocket upgrades are answered with an error.
+    pub fn accept(&self, request: Request) {
+        let key = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+            .map(|header| header.value.to_string());
+        let key = match key {
+            Some(key) => key,
+            None => {
+                let response = Response::from_string("Expected a WebSocket upgrade request")
+                    .with_status_code(400);
+                if let Err(e) = request.respond(response) {
+                    eprintln!("Error responding to request: {}", e);
+                }
+                return;
+            }
+        };
+        let accept = Header::from_bytes("Sec-WebSocket-Accept", websocket_accept(&key)).unwrap();
+        let response = Response::empty(101).with_header(accept);
+        let stream = request.upgrade("websocket", response);
+        self.clients.lock().unwrap().push(stream);
+    }
+}
+
+fn is_ignored(directory: &Path, ignore_dirs: &[PathBuf], path: &Path) -> bool {
+    let relative_path = path.strip_prefix(directory).unwrap_or(path);
+    relative_path
+        .components()
+        .any(|component| component.as_os_str() == ".git")
+        || ignore_dirs
+            .iter()
+            .any(|ignore_dir| path.starts_with(ignore_dir) || relative_path.starts_with(ignore_dir))
+}
+
+/// The `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`, as defined in RFC 6455.
+fn websocket_accept(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.trim().as_bytes());
+    hasher.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// An unmasked WebSocket text frame, as sent by servers. Only short messages are supported.
+fn text_frame(text: &str) -> Vec<u8> {
+    assert!(text.len() < 126);
+    let mut frame = vec![0x81, text.len() as u8];
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_accept() {
+        // The example from RFC 6455 section 1.3.
+        assert_eq!(
+            websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_text_frame() {
+        assert_eq!(
+            text_frame("reload"),
+            vec![0x81, 6, b'r', b'e', b'l', b'o', b'a', b'd']
+        );
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let directory = Path::new("/project");
+        let ignore_dirs = vec![PathBuf::from("target")];
+        assert!(is_ignored(
+            directory,
+            &ignore_dirs,
+            Path::new("/project/.git/index")
+        ));
+        assert!(is_ignored(
+            directory,
+            &ignore_dirs,
+            Path::new("/project/target/debug/app")
+        ));
+        assert!(!is_ignored(
+            directory,
+            &ignore_dirs,
+            Path::new("/project/src/main.rs")
+        ));
+    }
+}
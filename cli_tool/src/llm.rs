@@ -0,0 +1,238 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::io::BufRead;
+
+use serde_json::{json, Value};
+
+#[derive(thiserror::Error, Debug)]
+pub enum LlmError {
+    #[error("Environment variable {0} must be set to the provider's API key")]
+    MissingApiKey(&'static str),
+
+    #[error("Error sending request: {0}")]
+    ErrorSendingRequest(Box<ureq::Error>),
+
+    #[error("Error reading response: {0}")]
+    ErrorReadingResponse(#[from] std::io::Error),
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+/// The API that a provider endpoint speaks.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// OpenAI-compatible chat completions, with the key in `OPENAI_API_KEY`
+    #[value(name = "openai")]
+    OpenAi,
+
+    /// Anthropic-compatible messages, with the key in `ANTHROPIC_API_KEY`
+    Anthropic,
+}
+
+impl Provider {
+    fn default_endpoint(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "https://api.openai.com/v1/chat/completions",
+            Provider::Anthropic => "https://api.anthropic.com/v1/messages",
+        }
+    }
+
+    fn api_key_variable(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "OPENAI_API_KEY",
+            Provider::Anthropic => "ANTHROPIC_API_KEY",
+        }
+    }
+}
+
+/// A piece of a streamed answer.
+#[derive(Debug, PartialEq, Eq)]
+enum StreamEvent {
+    Text(String),
+    Done,
+}
+
+/// Sends a prompt to an LLM provider and streams back the answer.
+pub struct LlmClient {
+    provider: Provider,
+    endpoint: String,
+    model: String,
+    api_key: String,
+}
+
+impl LlmClient {
+    /// Create a client for `provider`, reading the API key from the provider's environment
+    /// variable. `endpoint` overrides the provider's default URL, e.g. for a local proxy.
+    pub fn from_env(
+        provider: Provider,
+        model: String,
+        endpoint: Option<String>,
+    ) -> Result<LlmClient, LlmError> {
+        let api_key = std::env::var(provider.api_key_variable())
+            .map_err(|_| LlmError::MissingApiKey(provider.api_key_variable()))?;
+        Ok(LlmClient {
+            provider,
+            endpoint: endpoint.unwrap_or_else(|| provider.default_endpoint().to_string()),
+            model,
+            api_key,
+        })
+    }
+
+    /// Send `prompt` as a single user message, calling `on_text` with each piece of the answer as
+    /// it arrives.
+    pub fn ask(&self, prompt: &str, mut on_text: impl FnMut(&str)) -> Result<(), LlmError> {
+        let request = ureq::post(&self.endpoint).set("Content-Type", "application/json");
+        let (request, body) = match self.provider {
+            Provider::OpenAi => (
+                request.set("Authorization", &format!("Bearer {}", self.api_key)),
+                json!({
+                    "model": self.model,
+                    "stream": true,
+                    "messages": [{"role": "user", "content": prompt}],
+                }),
+            ),
+            Provider::Anthropic => (
+                request
+                    .set("x-api-key", &self.api_key)
+                    .set("anthropic-version", "2023-06-01"),
+                json!({
+                    "model": self.model,
+                    "stream": true,
+                    "max_tokens": 4096,
+                    "messages": [{"role": "user", "content": prompt}],
+                }),
+            ),
+        };
+        let response = request
+            .send_json(body)
+            .map_err(|e| LlmError::ErrorSendingRequest(Box::new(e)))?;
+
+        for line in std::io::BufReader::new(response.into_reader()).lines() {
+            let line = line?;
+            let data = match line.strip_prefix("data:") {
+                Some(data) => data.trim(),
+                None => continue,
+            };
+            match parse_event(self.provider, data)? {
+                Some(StreamEvent::Text(text)) => on_text(&text),
+                Some(StreamEvent::Done) => break,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the data of one server-sent event. Returns None for events that carry no text, e.g.
+/// pings or the start of a message.
+fn parse_event(provider: Provider, data: &str) -> Result<Option<StreamEvent>, LlmError> {
+    if provider == Provider::OpenAi && data == "[DONE]" {
+        return Ok(Some(StreamEvent::Done));
+    }
+    let event: Value =
+        serde_json::from_str(data).map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+    if let Some(error) = event.get("error") {
+        return Err(LlmError::InvalidResponse(error.to_string()));
+    }
+    let text = match provider {
+        Provider::OpenAi => &event["choices"][0]["delta"]["content"],
+        Provider::Anthropic => match event["type"].as_str() {
+            Some("content_block_delta") => &event["delta"]["text"],
+            Some("message_stop") => return Ok(Some(StreamEvent::Done)),
+            _ => return Ok(None),
+        },
+    };
+    Ok(text
+        .as_str()
+        .map(|text| StreamEvent::Text(text.to_string())))
+}
+
+/// Fill in the `{question}` and `{digest}` placeholders in `template`. Placeholders are only
+/// replaced in the template itself, not in the question or digest.
+pub fn build_prompt(template: &str, question: &str, digest: &str) -> String {
+    let mut prompt = String::with_capacity(template.len() + question.len() + digest.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        prompt.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("{question}") {
+            prompt.push_str(question);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{digest}") {
+            prompt.push_str(digest);
+            rest = after;
+        } else {
+            prompt.push('{');
+            rest = &rest[1..];
+        }
+    }
+    prompt.push_str(rest);
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_openai() {
+        let data = r#"{"choices":[{"index":0,"delta":{"content":"Hello"}}]}"#;
+        assert_eq!(
+            parse_event(Provider::OpenAi, data).unwrap(),
+            Some(StreamEvent::Text("Hello".to_string()))
+        );
+
+        let data = r#"{"choices":[{"index":0,"delta":{"role":"assistant"}}]}"#;
+        assert_eq!(parse_event(Provider::OpenAi, data).unwrap(), None);
+
+        assert_eq!(
+            parse_event(Provider::OpenAi, "[DONE]").unwrap(),
+            Some(StreamEvent::Done)
+        );
+
+        let data = r#"{"error":{"message":"Rate limit reached"}}"#;
+        assert!(parse_event(Provider::OpenAi, data).is_err());
+    }
+
+    #[test]
+    fn test_parse_event_anthropic() {
+        let data = r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#;
+        assert_eq!(
+            parse_event(Provider::Anthropic, data).unwrap(),
+            Some(StreamEvent::Text("Hello".to_string()))
+        );
+
+        let data = r#"{"type":"ping"}"#;
+        assert_eq!(parse_event(Provider::Anthropic, data).unwrap(), None);
+
+        let data = r#"{"type":"message_stop"}"#;
+        assert_eq!(
+            parse_event(Provider::Anthropic, data).unwrap(),
+            Some(StreamEvent::Done)
+        );
+
+        let data = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(parse_event(Provider::Anthropic, data).is_err());
+    }
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(
+            build_prompt(
+                "Code:\n{digest}\nQuestion: {question} {other}",
+                "What does {digest} do?",
+                "fn main() {\n    // ...\n}",
+            ),
+            "Code:\nfn main() {\n    // ...\n}\nQuestion: What does {digest} do? {other}"
+        );
+    }
+}
@@ -8,17 +8,49 @@
  * SPDX-License-Identifier: MPL-2.0
  */
 
-use std::path::PathBuf;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use file_system::{get_files, GlobPatternMatcher};
-use language_parsers::default_parse_config_for_language;
+use file_system::{
+    get_files, get_files_parallel, File, GitBlame, GitTree, GlobPatternMatcher, WalkOptions,
+};
 
-use crate::file_processor::{process_files, FileProcessorError};
+use crate::call_graph::CallGraph;
+use crate::config::{AskArgs, Command, HookArgs, SearchArgs, StatsArgs};
+use crate::daemon::Daemon;
+use crate::docs::DocsSelector;
+use crate::file_processor::{
+    digest_directory, parse_config_directory, process_files, skip_walk_errors, FileProcessorError,
+    ParseConfigs,
+};
 use crate::file_tree::{print_file_tree, CallbackArgs};
+use crate::github::GithubRepository;
+use crate::hook::{is_up_to_date, staged_digest, write_if_changed};
+use crate::index::{build_index, search};
+use crate::llm::{build_prompt, LlmClient};
+use crate::packages::{
+    cargo_workspace, dependency_order, discover_packages, nested_ignore_patterns, Package,
+};
+use crate::server::DigestServer;
+use crate::stats::CodeStats;
 
+mod call_graph;
 mod config;
+mod daemon;
+mod docs;
 mod file_processor;
 mod file_tree;
+mod github;
+mod hook;
+mod html;
+mod index;
+mod live_reload;
+mod llm;
+mod packages;
+mod server;
+mod stats;
 
 pub fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -30,79 +62,581 @@ pub fn main() {
         std::process::exit(1);
     });
 
-    let directory = shellexpand::full(config.directory.as_str())
-        .map_err(|e| {
-            eprintln!("Error expanding directory: {}", e);
+    if config.verbose {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    if config.daemon {
+        let mut parse_configs = load_parse_configs();
+        parse_configs.set_parse_timeout(parse_timeout(config.parse_timeout));
+        Daemon::new()
+            .with_parse_configs(parse_configs)
+            .run(std::io::stdin().lock(), std::io::stdout().lock())
+            .unwrap_or_else(|e| {
+                eprintln!("Error running daemon: {}", e);
+                std::process::exit(1);
+            });
+        return;
+    }
+
+    if let Some(Command::Serve(serve_args)) = &config.command {
+        let directory = expand_directory(&serve_args.directory);
+        let ignore_dirs = expand_ignore_dirs(&serve_args.ignore);
+        let addr = serve_args
+            .http
+            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 8080)));
+        let mut parse_configs = load_parse_configs();
+        parse_configs.set_parse_timeout(parse_timeout(serve_args.parse_timeout));
+        DigestServer::new(directory, ignore_dirs, serve_args.html)
+            .with_walk_options(WalkOptions {
+                max_file_size: serve_args.max_file_size,
+                skip_binary: true,
+                follow_symlinks: serve_args.follow_symlinks,
+                max_depth: serve_args.max_depth,
+                hidden: serve_args.hidden,
+                include_hidden: serve_args.include_hidden.clone(),
+            })
+            .with_parse_configs(parse_configs)
+            .serve(addr)
+            .unwrap_or_else(|e| {
+                eprintln!("Error serving HTTP: {}", e);
+                std::process::exit(1);
+            });
+        return;
+    }
+
+    if let Some(Command::Ask(ask_args)) = &config.command {
+        ask(ask_args);
+        return;
+    }
+
+    if let Some(Command::Hook(hook_args)) = &config.command {
+        hook(hook_args);
+        return;
+    }
+
+    if let Some(Command::Stats(stats_args)) = &config.command {
+        stats(stats_args);
+        return;
+    }
+
+    if let Some(Command::Search(search_args)) = &config.command {
+        search_index(search_args);
+        return;
+    }
+
+    // The downloaded repository is deleted when this is dropped, after the digest is printed.
+    let github_checkout = config.github.as_ref().map(download_github_repository);
+    let directory = match &github_checkout {
+        Some(checkout) => checkout.path().to_path_buf(),
+        None => expand_directory(&config.directory),
+    };
+    let ignore_dirs = &expand_ignore_dirs(&config.ignore);
+    let walk_options = &WalkOptions {
+        max_file_size: config.max_file_size,
+        skip_binary: true,
+        follow_symlinks: config.follow_symlinks,
+        max_depth: config.max_depth,
+        hidden: config.hidden,
+        include_hidden: config.include_hidden.clone(),
+    };
+    let glob_matcher = GlobPatternMatcher::new_from_strings(&unquote_include(&config.include))
+        .unwrap_or_else(|e| {
+            eprintln!("Error parsing include patterns: {}", e);
+            std::process::exit(1);
+        });
+
+    let git_tree = config.rev.as_ref().map(|rev| {
+        GitTree::open(directory.clone(), rev).unwrap_or_else(|e| {
+            eprintln!("Error opening git revision {}: {}", rev, e);
             std::process::exit(1);
         })
-        .unwrap();
-    let directory = PathBuf::from(directory.as_ref());
-    if !directory.is_dir() {
-        eprintln!("Not a directory: {}", &config.directory);
-        std::process::exit(1);
+    });
+    // Files in a downloaded repository are listed relative to it, so that digests show paths in the
+    // repository rather than in a temporary directory.
+    let checkout_path = github_checkout.as_ref().map(|checkout| checkout.path());
+    let read_file = |path: &Path| match (&git_tree, checkout_path) {
+        (Some(git_tree), _) => git_tree.read_to_string(path),
+        (None, Some(checkout_path)) => std::fs::read_to_string(checkout_path.join(path)),
+        (None, None) => std::fs::read_to_string(path),
+    };
+    let git_blame = match config.blame {
+        true => GitBlame::open(directory.clone(), config.rev.as_deref())
+            .map_err(|e| eprintln!("Error opening git repository for blame: {}", e))
+            .ok(),
+        false => None,
+    };
+    // Files are blamed with the contents that were digested, so that line numbers match.
+    let blame = |path: &Path, source_code: &str| {
+        git_blame.as_ref().and_then(|git_blame| {
+            git_blame
+                .blame(path, source_code)
+                .map_err(|e| eprintln!("Error running git blame on {}: {}", path.display(), e))
+                .ok()
+        })
+    };
+
+    let mut parse_configs = load_parse_configs();
+    if config.doc_comments {
+        parse_configs.set_include_doc_comments(true);
     }
+    if config.strip_comments {
+        parse_configs.set_strip_comments(true);
+    }
+    if let Some(granularity) = config.granularity {
+        parse_configs.set_granularity(granularity);
+    }
+    if config.signatures_only {
+        parse_configs.set_signatures_only(true);
+    }
+    for mapping in &config.language_map {
+        parse_configs
+            .add_language_mapping(mapping)
+            .unwrap_or_else(|e| {
+                eprintln!("Error adding language mapping: {}", e);
+                std::process::exit(1);
+            });
+    }
+    parse_configs.set_syntax_error_mode(config.syntax_errors);
+    parse_configs.set_test_code_mode(config.tests);
+    parse_configs.set_line_numbers(config.line_numbers);
+    parse_configs.set_parse_timeout(parse_timeout(config.parse_timeout));
+    parse_configs.set_fallback(config.fallback);
+    let docs = config
+        .with_docs
+        .map(|tier| DocsSelector::new(directory.clone(), tier));
 
-    let ignore_dirs: &Vec<PathBuf> = &config
-        .ignore
+    if let Some(index) = &config.index {
+        let count = build_index(
+            index,
+            list_files(
+                &directory,
+                ignore_dirs,
+                git_tree.as_ref(),
+                walk_options,
+                checkout_path,
+            ),
+            read_file,
+            &parse_configs,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("Error building index in {}: {}", index.display(), e);
+            std::process::exit(1);
+        });
+        eprintln!("Indexed {} items in {}", count, index.display());
+    }
+
+    if config.stats {
+        let stats = CodeStats::collect(
+            list_files(
+                &directory,
+                ignore_dirs,
+                git_tree.as_ref(),
+                walk_options,
+                checkout_path,
+            ),
+            read_file,
+            &parse_configs,
+        );
+        println!("{}", stats.to_markdown());
+    }
+
+    if config.call_graph {
+        let call_graph = CallGraph::collect(
+            list_files(
+                &directory,
+                ignore_dirs,
+                git_tree.as_ref(),
+                walk_options,
+                checkout_path,
+            ),
+            read_file,
+            &parse_configs,
+        );
+        println!("{}", call_graph.to_markdown());
+    }
+
+    let print_digest = |directory: &Path, ignore_dirs: &[PathBuf]| {
+        if config.tree {
+            print_file_tree(
+                list_files(
+                    directory,
+                    ignore_dirs,
+                    git_tree.as_ref(),
+                    walk_options,
+                    checkout_path,
+                ),
+                |CallbackArgs {
+                     output: s,
+                     linebreak,
+                 }| {
+                    print!("{}", s);
+                    if linebreak {
+                        println!();
+                    }
+                },
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("Error printing file tree: {}", e);
+                std::process::exit(1);
+            });
+        }
+
+        for file_result in process_files(
+            list_files(
+                directory,
+                ignore_dirs,
+                git_tree.as_ref(),
+                walk_options,
+                checkout_path,
+            ),
+            &read_file,
+            &blame,
+            &parse_configs,
+            &glob_matcher,
+            docs.as_ref(),
+        ) {
+            match file_result {
+                Ok(file) => {
+                    println!("{}", file);
+                }
+                Err(FileProcessorError::UnsupportedFileKind(_)) => {}
+                Err(FileProcessorError::FileSkipped(_)) => {}
+                _ => {
+                    eprintln!("Error processing file: {:?}\n", file_result);
+                }
+            }
+        }
+    };
+
+    if config.cargo {
+        let workspace = cargo_workspace(&directory).unwrap_or_else(|e| {
+            eprintln!("Error reading Cargo workspace: {}", e);
+            std::process::exit(1);
+        });
+        let target_directory = [workspace.target_directory];
+        for package in dependency_order(workspace.packages.clone()) {
+            let mut package_ignore_dirs = ignore_dirs.clone();
+            package_ignore_dirs.extend(nested_ignore_patterns(
+                &package,
+                &workspace.packages,
+                &target_directory,
+            ));
+            println!("# Crate `{}`\n", package.name);
+            print_digest(&package.directory, &package_ignore_dirs);
+        }
+    } else if config.packages {
+        let packages = discover_packages(&directory, ignore_dirs);
+        for package in dependency_order(packages.clone()) {
+            let mut package_ignore_dirs = ignore_dirs.clone();
+            package_ignore_dirs.extend(nested_ignore_patterns(&package, &packages, &[]));
+            println!("# Package `{}`\n", package.name);
+            print_digest(&package.directory, &package_ignore_dirs);
+        }
+
+        // Files outside every package, e.g. top-level docs, go last.
+        if !packages
+            .iter()
+            .any(|package| package.directory == directory)
+        {
+            let root = Package {
+                name: String::new(),
+                directory: directory.clone(),
+                dependencies: vec![],
+            };
+            let mut root_ignore_dirs = ignore_dirs.clone();
+            root_ignore_dirs.extend(nested_ignore_patterns(&root, &packages, &[]));
+            println!("# Other files\n");
+            print_digest(&directory, &root_ignore_dirs);
+        }
+    } else {
+        print_digest(&directory, ignore_dirs);
+    }
+}
+
+/// Send a budgeted digest of a directory to an LLM along with a question, and stream the answer to
+/// stdout.
+fn ask(ask_args: &AskArgs) {
+    let directory = expand_directory(&ask_args.directory);
+    let ignore_dirs = expand_ignore_dirs(&ask_args.ignore);
+    let glob_matcher = GlobPatternMatcher::new_from_strings(&unquote_include(&ask_args.include))
+        .unwrap_or_else(|e| {
+            eprintln!("Error parsing include patterns: {}", e);
+            std::process::exit(1);
+        });
+    let template = match &ask_args.prompt_template {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Error reading prompt template {}: {}", path.display(), e);
+            std::process::exit(1);
+        }),
+        None => include_str!("./ask_prompt.txt").to_string(),
+    };
+    let client = LlmClient::from_env(
+        ask_args.provider,
+        ask_args.model.clone(),
+        ask_args.endpoint.clone(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Error configuring LLM provider: {}", e);
+        std::process::exit(1);
+    });
+
+    let parse_configs = load_parse_configs();
+    let walk_options = WalkOptions {
+        skip_binary: true,
+        ..Default::default()
+    };
+    let digest = digest_directory(
+        &directory,
+        &ignore_dirs,
+        &walk_options,
+        &parse_configs,
+        &glob_matcher,
+        Some(ask_args.token_budget),
+    );
+    if !digest.omitted.is_empty() {
+        eprintln!(
+            "Left out {} files that did not fit in the token budget of {}",
+            digest.omitted.len(),
+            ask_args.token_budget
+        );
+    }
+    let digest_text = digest
+        .files
         .iter()
-        .map(|dir| shellexpand::full(dir.to_str().unwrap()).unwrap())
-        .map(|dir| PathBuf::from(dir.to_string()))
-        .collect::<Vec<PathBuf>>();
+        .map(|file| file.digest.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = build_prompt(&template, &ask_args.question, &digest_text);
+
+    let mut stdout = std::io::stdout().lock();
+    client
+        .ask(&prompt, |text| {
+            let _ = write!(stdout, "{}", text);
+            let _ = stdout.flush();
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("\nError asking LLM: {}", e);
+            std::process::exit(1);
+        });
+    let _ = writeln!(stdout);
+}
+
+/// Print a table of line and symbol counts per language.
+fn stats(stats_args: &StatsArgs) {
+    let directory = expand_directory(&stats_args.directory);
+    let ignore_dirs = expand_ignore_dirs(&stats_args.ignore);
+    let parse_configs = load_parse_configs();
+    // The statistics do not depend on the order files are found in.
+    let stats = CodeStats::collect(
+        skip_walk_errors(get_files_parallel(
+            directory,
+            &ignore_dirs,
+            &WalkOptions::default(),
+        )),
+        |path| std::fs::read_to_string(path),
+        &parse_configs,
+    );
+    print!("{}", stats.to_markdown());
+}
 
-    // cli.include comes from a shell and should not include single quotes around e.g. '*.md'. But
-    // if it does then we remove them here. Must be a matching pair of single quotes at the start
-    // and end of the string.
-    let cli_include = &config
-        .include
+/// Print the captured items in an index that match a query, best matches first.
+fn search_index(search_args: &SearchArgs) {
+    let hits =
+        search(&search_args.index, &search_args.query, search_args.limit).unwrap_or_else(|e| {
+            eprintln!("Error searching {}: {}", search_args.index.display(), e);
+            std::process::exit(1);
+        });
+    for hit in hits {
+        println!("`{}:{}-{}`", hit.path, hit.start_line, hit.end_line);
+        println!("```\n{}\n```\n", hit.content);
+    }
+}
+
+/// Compare or update a digest file against the digest of the staged files, exiting with an error
+/// if `--check` finds it out of date.
+fn hook(hook_args: &HookArgs) {
+    let directory = expand_directory(&hook_args.directory);
+    let ignore_dirs = expand_ignore_dirs(&hook_args.ignore);
+    let glob_matcher = GlobPatternMatcher::new_from_strings(&unquote_include(&hook_args.include))
+        .unwrap_or_else(|e| {
+            eprintln!("Error parsing include patterns: {}", e);
+            std::process::exit(1);
+        });
+    let digest_file = hook_args
+        .check
+        .as_ref()
+        .or(hook_args.write.as_ref())
+        .unwrap();
+
+    let parse_configs = load_parse_configs();
+    let result = staged_digest(
+        &directory,
+        &ignore_dirs,
+        digest_file,
+        &parse_configs,
+        &glob_matcher,
+    )
+    .and_then(|digest| {
+        if hook_args.check.is_some() {
+            is_up_to_date(digest_file, &digest)
+        } else {
+            write_if_changed(digest_file, &digest).map(|changed| {
+                if changed {
+                    eprintln!("Updated {}", digest_file.display());
+                }
+                true
+            })
+        }
+    });
+    match result {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!(
+                "{} is out of date, run `code-digest hook --write {}` to update it",
+                digest_file.display(),
+                digest_file.display()
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Include patterns come from a shell and should not include single quotes around e.g. '*.md'. But
+/// if they do then we remove them here. Must be a matching pair of single quotes at the start and
+/// end of the string.
+fn unquote_include(include: &[String]) -> Vec<String> {
+    include
         .iter()
         .map(|s| {
-            if s.starts_with('\'') && s.ends_with('\'') {
+            if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
                 s[1..s.len() - 1].to_string()
             } else {
                 s.to_string()
             }
         })
-        .collect::<Vec<String>>();
-
-    let glob_matcher = GlobPatternMatcher::new_from_strings(cli_include).unwrap();
-
-    let go_config = default_parse_config_for_language(language_parsers::Language::Go);
-    let rust_config = default_parse_config_for_language(language_parsers::Language::Rust);
-
-    if config.tree {
-        print_file_tree(
-            get_files(directory.clone(), ignore_dirs),
-            |CallbackArgs {
-                 output: s,
-                 linebreak,
-             }| {
-                print!("{}", s);
-                if linebreak {
-                    println!();
-                }
-            },
-        )
+        .collect()
+}
+
+/// The default parse configs, replaced by any language config files in the user's config directory.
+fn load_parse_configs() -> ParseConfigs {
+    let mut parse_configs = ParseConfigs::new();
+    parse_configs
+        .load_config_files(&parse_config_directory())
         .unwrap_or_else(|e| {
-            eprintln!("Error printing file tree: {}", e);
+            eprintln!("Error loading parse configs: {}", e);
             std::process::exit(1);
         });
+    parse_configs
+}
+
+/// How long to spend parsing a file given `--parse-timeout` in seconds, or None for no limit.
+fn parse_timeout(seconds: Option<u64>) -> Option<Duration> {
+    seconds.map(Duration::from_secs)
+}
+
+fn expand_directory(directory: &str) -> PathBuf {
+    let expanded = shellexpand::full(directory)
+        .map_err(|e| {
+            eprintln!("Error expanding directory: {}", e);
+            std::process::exit(1);
+        })
+        .unwrap();
+    let expanded = PathBuf::from(expanded.as_ref());
+    if !expanded.is_dir() {
+        eprintln!("Not a directory: {}", directory);
+        std::process::exit(1);
     }
+    expanded
+}
 
-    for file_result in process_files(
-        get_files(directory, ignore_dirs),
-        &go_config,
-        &rust_config,
-        &glob_matcher,
-    ) {
-        match file_result {
-            Ok(file) => {
-                println!("{}", file);
-            }
-            Err(FileProcessorError::UnsupportedFileKind(_)) => {}
-            Err(FileProcessorError::FileSkipped(_)) => {}
-            _ => {
-                eprintln!("Error processing file: {:?}\n", file_result);
-            }
+fn expand_ignore_dirs(ignore: &[PathBuf]) -> Vec<PathBuf> {
+    ignore
+        .iter()
+        .map(|dir| shellexpand::full(dir.to_str().unwrap()).unwrap())
+        .map(|dir| PathBuf::from(dir.to_string()))
+        .collect::<Vec<PathBuf>>()
+}
+
+/// Download a GitHub repository into a temporary directory, using the token in `GITHUB_TOKEN` if
+/// set.
+fn download_github_repository(repository: &GithubRepository) -> tempfile::TempDir {
+    let checkout = tempfile::tempdir().unwrap_or_else(|e| {
+        eprintln!("Error creating temporary directory: {}", e);
+        std::process::exit(1);
+    });
+    let token = std::env::var("GITHUB_TOKEN").ok();
+    repository
+        .download(token.as_deref(), checkout.path())
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "Error downloading {}/{}: {}",
+                repository.owner, repository.repo, e
+            );
+            std::process::exit(1);
+        });
+    checkout
+}
+
+/// List files from the git revision if one was given, otherwise from the working tree. If
+/// `relative_to` is given then paths are made relative to it.
+fn list_files(
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    git_tree: Option<&GitTree>,
+    walk_options: &WalkOptions,
+    relative_to: Option<&Path>,
+) -> Box<dyn Iterator<Item = File>> {
+    let files = list_files_in_tree(directory, ignore_dirs, git_tree, walk_options);
+    match relative_to {
+        Some(relative_to) => {
+            let relative_to = relative_to.to_path_buf();
+            Box::new(files.map(move |file| {
+                File {
+                    path: file
+                        .path
+                        .strip_prefix(&relative_to)
+                        .map(Path::to_path_buf)
+                        .unwrap_or(file.path),
+                    ..file
+                }
+            }))
+        }
+        None => files,
+    }
+}
+
+fn list_files_in_tree(
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    git_tree: Option<&GitTree>,
+    walk_options: &WalkOptions,
+) -> Box<dyn Iterator<Item = File>> {
+    match git_tree {
+        Some(git_tree) => {
+            let files = git_tree
+                .files(ignore_dirs, walk_options)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error listing files at git revision: {}", e);
+                    std::process::exit(1);
+                });
+            // Files at a revision are listed all at once, so the depth limit is applied here.
+            let max_depth = walk_options.max_depth;
+            Box::new(files.into_iter().filter(move |file| {
+                max_depth.is_none_or(|max_depth| file.depth <= max_depth as isize)
+            }))
         }
+        None => Box::new(skip_walk_errors(get_files(
+            directory.to_path_buf(),
+            ignore_dirs,
+            walk_options,
+        ))),
     }
 }
@@ -0,0 +1,532 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use file_system::{get_files, FileKind, WalkOptions};
+use serde::Deserialize;
+
+use crate::file_processor::skip_walk_errors;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PackageError {
+    #[error("Error running cargo metadata: {0}")]
+    ErrorRunningCargo(#[from] std::io::Error),
+
+    #[error("cargo metadata failed: {0}")]
+    CargoMetadataFailed(String),
+
+    #[error("Error parsing cargo metadata: {0}")]
+    ErrorParsingCargoMetadata(#[from] serde_json::Error),
+
+    #[error("Error reading {0}: {1}")]
+    ErrorReadingManifest(PathBuf, std::io::Error),
+
+    #[error("Error parsing {0}: {1}")]
+    ErrorParsingManifest(PathBuf, String),
+}
+
+/// A package inside the repository, e.g. a crate in a Cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+
+    /// The directory containing the package's manifest.
+    pub directory: PathBuf,
+
+    /// Names of other packages in the repository that this package depends on.
+    pub dependencies: Vec<String>,
+}
+
+/// The packages in a Cargo workspace and where it puts build output.
+#[derive(Debug)]
+pub struct CargoWorkspace {
+    pub packages: Vec<Package>,
+    pub target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadata {
+    packages: Vec<CargoPackage>,
+    workspace_members: Vec<String>,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage {
+    name: String,
+    id: String,
+    manifest_path: PathBuf,
+    dependencies: Vec<CargoDependency>,
+}
+
+#[derive(Deserialize)]
+struct CargoDependency {
+    name: String,
+    kind: Option<String>,
+}
+
+/// Read the Cargo workspace containing `directory` using `cargo metadata`, which resolves member
+/// globs and the target directory the same way cargo itself does.
+pub fn cargo_workspace(directory: &Path) -> Result<CargoWorkspace, PackageError> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(directory)
+        .output()?;
+    if !output.status.success() {
+        return Err(PackageError::CargoMetadataFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    parse_cargo_metadata(&output.stdout)
+}
+
+fn parse_cargo_metadata(metadata: &[u8]) -> Result<CargoWorkspace, PackageError> {
+    let metadata: CargoMetadata = serde_json::from_slice(metadata)?;
+    let members: BTreeSet<_> = metadata.workspace_members.iter().collect();
+    let packages = metadata
+        .packages
+        .iter()
+        .filter(|package| members.contains(&package.id))
+        .map(|package| Package {
+            name: package.name.clone(),
+            directory: package
+                .manifest_path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .to_path_buf(),
+            // Dev-dependencies are left out because they often point back up the dependency
+            // graph, e.g. to test utilities, and would make the order cyclic.
+            dependencies: package
+                .dependencies
+                .iter()
+                .filter(|dependency| dependency.kind.as_deref() != Some("dev"))
+                .map(|dependency| dependency.name.clone())
+                .collect(),
+        })
+        .collect();
+    Ok(CargoWorkspace {
+        packages,
+        target_directory: metadata.target_directory,
+    })
+}
+
+/// A package's name and the names of its dependencies, as read from its manifest.
+type ParsedManifest = (String, Vec<String>);
+
+/// Find the packages under `directory` from their `go.mod`, `package.json`, and `pyproject.toml`
+/// manifests, including members of npm workspaces. Dependencies on packages outside the repository
+/// are kept, but only matter to `dependency_order` if a package with that name is found. Manifests
+/// that cannot be read or parsed are reported and skipped.
+pub fn discover_packages(directory: &Path, ignore_dirs: &[PathBuf]) -> Vec<Package> {
+    let mut packages = vec![];
+    for file in skip_walk_errors(get_files(
+        directory.to_path_buf(),
+        ignore_dirs,
+        &WalkOptions::default(),
+    )) {
+        if file.kind != FileKind::File
+            || file
+                .path
+                .components()
+                .any(|component| component.as_os_str() == "node_modules")
+        {
+            continue;
+        }
+        let parse: fn(&str) -> Result<Option<ParsedManifest>, String> =
+            match file.path.file_name().and_then(|name| name.to_str()) {
+                Some("go.mod") => |manifest| Ok(Some(parse_go_mod(manifest))),
+                Some("package.json") => parse_package_json,
+                Some("pyproject.toml") => parse_pyproject_toml,
+                _ => continue,
+            };
+        let result = std::fs::read_to_string(&file.path)
+            .map_err(|e| PackageError::ErrorReadingManifest(file.path.clone(), e))
+            .and_then(|manifest| {
+                parse(&manifest)
+                    .map_err(|e| PackageError::ErrorParsingManifest(file.path.clone(), e))
+            });
+        match result {
+            Ok(Some((name, dependencies))) => packages.push(Package {
+                name,
+                directory: file.path.parent().unwrap_or(directory).to_path_buf(),
+                dependencies,
+            }),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    packages
+}
+
+/// Returns the module path and required modules from a `go.mod` file.
+fn parse_go_mod(manifest: &str) -> ParsedManifest {
+    let mut name = String::new();
+    let mut dependencies = vec![];
+    let mut in_require_block = false;
+    for line in manifest.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_require_block {
+            if line == ")" {
+                in_require_block = false;
+            } else if let Some(module) = line.split_whitespace().next() {
+                dependencies.push(module.to_string());
+            }
+        } else if let Some(module) = line.strip_prefix("module ") {
+            name = module.trim().trim_matches('"').to_string();
+        } else if line == "require (" {
+            in_require_block = true;
+        } else if let Some(require) = line.strip_prefix("require ") {
+            if let Some(module) = require.split_whitespace().next() {
+                dependencies.push(module.to_string());
+            }
+        }
+    }
+    (name, dependencies)
+}
+
+/// Returns the name and dependencies from a `package.json` file, or None if it has no name.
+/// `devDependencies` are left out, as for Cargo.
+fn parse_package_json(manifest: &str) -> Result<Option<ParsedManifest>, String> {
+    let manifest: serde_json::Value = serde_json::from_str(manifest).map_err(|e| e.to_string())?;
+    let name = match manifest["name"].as_str() {
+        Some(name) => name.to_string(),
+        None => return Ok(None),
+    };
+    let dependencies = ["dependencies", "peerDependencies", "optionalDependencies"]
+        .iter()
+        .filter_map(|key| manifest[key].as_object())
+        .flat_map(|dependencies| dependencies.keys().cloned())
+        .collect();
+    Ok(Some((name, dependencies)))
+}
+
+/// Returns the name and dependencies from a `pyproject.toml` file, from either the standard
+/// `[project]` table or `[tool.poetry]`, or None if it has neither. Names are normalized as in
+/// PEP 503 so that e.g. `My_Package` depends on `my-package`.
+fn parse_pyproject_toml(manifest: &str) -> Result<Option<ParsedManifest>, String> {
+    let manifest: toml::Value = toml::from_str(manifest).map_err(|e| e.to_string())?;
+    if let Some(project) = manifest.get("project") {
+        if let Some(name) = project.get("name").and_then(|name| name.as_str()) {
+            let dependencies = project
+                .get("dependencies")
+                .and_then(|dependencies| dependencies.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|requirement| requirement.as_str())
+                .map(|requirement| {
+                    let end = requirement
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c)))
+                        .unwrap_or(requirement.len());
+                    normalize_python_name(&requirement[..end])
+                })
+                .collect();
+            return Ok(Some((normalize_python_name(name), dependencies)));
+        }
+    }
+    if let Some(poetry) = manifest.get("tool").and_then(|tool| tool.get("poetry")) {
+        if let Some(name) = poetry.get("name").and_then(|name| name.as_str()) {
+            let dependencies = poetry
+                .get("dependencies")
+                .and_then(|dependencies| dependencies.as_table())
+                .into_iter()
+                .flat_map(|dependencies| dependencies.keys())
+                .filter(|dependency| *dependency != "python")
+                .map(|dependency| normalize_python_name(dependency))
+                .collect();
+            return Ok(Some((normalize_python_name(name), dependencies)));
+        }
+    }
+    Ok(None)
+}
+
+fn normalize_python_name(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Sort packages so that every package comes after the packages it depends on, with the
+/// least-dependent packages first. Packages that are otherwise unordered are sorted by name, and
+/// any caught in a dependency cycle go at the end.
+pub fn dependency_order(packages: Vec<Package>) -> Vec<Package> {
+    let names: BTreeSet<String> = packages.iter().map(|p| p.name.clone()).collect();
+    let mut remaining: HashMap<String, BTreeSet<String>> = packages
+        .iter()
+        .map(|package| {
+            let dependencies = package
+                .dependencies
+                .iter()
+                .filter(|dependency| names.contains(*dependency) && **dependency != package.name)
+                .cloned()
+                .collect();
+            (package.name.clone(), dependencies)
+        })
+        .collect();
+    let mut by_name: HashMap<String, Package> = packages
+        .into_iter()
+        .map(|package| (package.name.clone(), package))
+        .collect();
+
+    let mut result = vec![];
+    loop {
+        let ready: BTreeSet<String> = remaining
+            .iter()
+            .filter(|(_, dependencies)| dependencies.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+        let next = match ready.into_iter().next() {
+            Some(next) => next,
+            None => break,
+        };
+        remaining.remove(&next);
+        for dependencies in remaining.values_mut() {
+            dependencies.remove(&next);
+        }
+        result.extend(by_name.remove(&next));
+    }
+
+    let mut cyclic: Vec<Package> = by_name.into_values().collect();
+    cyclic.sort_by(|a, b| a.name.cmp(&b.name));
+    result.extend(cyclic);
+    result
+}
+
+/// Returns gitignore-style patterns, anchored at `package.directory`, for the other package
+/// directories and `extra_directories` nested inside it, so that walking a package does not also
+/// walk packages or build output beneath it.
+pub fn nested_ignore_patterns(
+    package: &Package,
+    packages: &[Package],
+    extra_directories: &[PathBuf],
+) -> Vec<PathBuf> {
+    packages
+        .iter()
+        .map(|other| &other.directory)
+        .chain(extra_directories)
+        .filter(|directory| **directory != package.directory)
+        .filter_map(|directory| directory.strip_prefix(&package.directory).ok())
+        .map(|relative| Path::new("/").join(relative))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, dependencies: &[&str]) -> Package {
+        Package {
+            name: name.to_string(),
+            directory: PathBuf::from("/workspace").join(name),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_cargo_metadata() {
+        let metadata = br#"{
+            "packages": [
+                {
+                    "name": "cli",
+                    "id": "path+file:///workspace/cli#0.1.0",
+                    "manifest_path": "/workspace/cli/Cargo.toml",
+                    "dependencies": [
+                        {"name": "parser", "kind": null},
+                        {"name": "clap", "kind": null},
+                        {"name": "test_utils", "kind": "dev"}
+                    ]
+                },
+                {
+                    "name": "parser",
+                    "id": "path+file:///workspace/parser#0.1.0",
+                    "manifest_path": "/workspace/parser/Cargo.toml",
+                    "dependencies": []
+                }
+            ],
+            "workspace_members": [
+                "path+file:///workspace/cli#0.1.0",
+                "path+file:///workspace/parser#0.1.0"
+            ],
+            "target_directory": "/workspace/target"
+        }"#;
+        let workspace = parse_cargo_metadata(metadata).unwrap();
+        assert_eq!(
+            workspace.target_directory,
+            PathBuf::from("/workspace/target")
+        );
+        assert_eq!(
+            workspace.packages,
+            vec![package("cli", &["parser", "clap"]), package("parser", &[])]
+        );
+    }
+
+    #[test]
+    fn test_parse_go_mod() {
+        let manifest = r#"
+module example.com/app
+
+go 1.20
+
+require example.com/lib v0.0.0 // indirect
+
+require (
+	github.com/spf13/cobra v1.7.0
+	example.com/util v0.0.0
+)
+
+replace example.com/lib => ../lib
+"#;
+        assert_eq!(
+            parse_go_mod(manifest),
+            (
+                "example.com/app".to_string(),
+                vec![
+                    "example.com/lib".to_string(),
+                    "github.com/spf13/cobra".to_string(),
+                    "example.com/util".to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let manifest = r#"{
+            "name": "@acme/web",
+            "dependencies": {"@acme/ui": "workspace:*", "react": "^18.0.0"},
+            "devDependencies": {"@acme/test-utils": "workspace:*"}
+        }"#;
+        assert_eq!(
+            parse_package_json(manifest).unwrap(),
+            Some((
+                "@acme/web".to_string(),
+                vec!["@acme/ui".to_string(), "react".to_string()]
+            ))
+        );
+        assert_eq!(
+            parse_package_json(r#"{"private": true, "workspaces": ["packages/*"]}"#).unwrap(),
+            None
+        );
+        assert!(parse_package_json("{").is_err());
+    }
+
+    #[test]
+    fn test_parse_pyproject_toml() {
+        let manifest = r#"
+[project]
+name = "Acme_API"
+dependencies = ["acme.core>=1.0", "requests[socks] ~= 2.31", "attrs"]
+"#;
+        assert_eq!(
+            parse_pyproject_toml(manifest).unwrap(),
+            Some((
+                "acme-api".to_string(),
+                vec![
+                    "acme-core".to_string(),
+                    "requests".to_string(),
+                    "attrs".to_string(),
+                ]
+            ))
+        );
+
+        let manifest = r#"
+[tool.poetry]
+name = "acme-cli"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+acme-api = { path = "../api", develop = true }
+"#;
+        assert_eq!(
+            parse_pyproject_toml(manifest).unwrap(),
+            Some(("acme-cli".to_string(), vec!["acme-api".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_discover_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        for directory in ["web", "ui", "node_modules/react"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"private": true, "workspaces": ["web", "ui"]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("web").join("package.json"),
+            r#"{"name": "web", "dependencies": {"ui": "*"}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("ui").join("package.json"), r#"{"name": "ui"}"#).unwrap();
+        std::fs::write(
+            root.join("node_modules").join("react").join("package.json"),
+            r#"{"name": "react"}"#,
+        )
+        .unwrap();
+
+        let packages = dependency_order(discover_packages(root, &[]));
+        assert_eq!(
+            packages,
+            vec![
+                Package {
+                    name: "ui".to_string(),
+                    directory: root.join("ui"),
+                    dependencies: vec![],
+                },
+                Package {
+                    name: "web".to_string(),
+                    directory: root.join("web"),
+                    dependencies: vec!["ui".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let packages = vec![
+            package("cli", &["parser", "files", "clap"]),
+            package("parser", &["files"]),
+            package("files", &[]),
+            package("config", &[]),
+            package("a", &["b"]),
+            package("b", &["a"]),
+        ];
+        let names: Vec<_> = dependency_order(packages)
+            .into_iter()
+            .map(|package| package.name)
+            .collect();
+        assert_eq!(names, vec!["config", "files", "parser", "cli", "a", "b"]);
+    }
+
+    #[test]
+    fn test_nested_ignore_patterns() {
+        let root = Package {
+            name: "root".to_string(),
+            directory: PathBuf::from("/workspace"),
+            dependencies: vec![],
+        };
+        let packages = vec![root.clone(), package("cli", &[])];
+        assert_eq!(
+            nested_ignore_patterns(&root, &packages, &[PathBuf::from("/workspace/target")]),
+            vec![PathBuf::from("/cli"), PathBuf::from("/target")]
+        );
+        assert!(nested_ignore_patterns(
+            &packages[1],
+            &packages,
+            &[PathBuf::from("/workspace/target")]
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use file_system::{get_file, get_files, FileKind, GlobPatternMatcher, WalkOptions};
+use language_parsers::parse;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Response};
+
+use crate::file_processor::{
+    digest_directory, relative_path, skip_walk_errors, ParseConfigs, Symbol,
+};
+use crate::html::render_digest_html;
+use crate::live_reload::LiveReload;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ServerError {
+    #[error("Error starting HTTP server: {0}")]
+    ErrorStartingServer(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Error watching files: {0}")]
+    ErrorWatchingFiles(#[from] notify::Error),
+}
+
+/// Serves digests of a single directory over HTTP, so that tools can query a long-running process
+/// instead of running the CLI for every request. Responses are JSON.
+///
+/// - `GET /tree` lists files and directories, with the size of each file.
+/// - `POST /digest` returns the digest of every supported file. The optional JSON body can contain
+///   `include`, glob patterns for files to include in full, and `token_budget`, the maximum
+///   estimated number of tokens to return.
+/// - `GET /file/{path}/symbols` returns the captured items in one file, with their line ranges.
+///   Only files that `/tree` lists can be read.
+///
+/// If `html` is true then `GET /` also returns the digest as an HTML page, which reloads itself
+/// through a WebSocket at `/live-reload` whenever files in the directory change.
+pub struct DigestServer {
+    directory: PathBuf,
+    ignore_dirs: Vec<PathBuf>,
+    walk_options: WalkOptions,
+    html: bool,
+    parse_configs: ParseConfigs,
+}
+
+struct HttpResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+#[derive(Serialize)]
+struct TreeEntry {
+    path: String,
+    kind: &'static str,
+    depth: isize,
+
+    /// The size of a file in bytes, left out for directories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct TreeResponse {
+    files: Vec<TreeEntry>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct DigestRequest {
+    include: Vec<String>,
+    token_budget: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SymbolsResponse {
+    path: String,
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl DigestServer {
+    pub fn new(directory: PathBuf, ignore_dirs: Vec<PathBuf>, html: bool) -> DigestServer {
+        DigestServer {
+            directory,
+            ignore_dirs,
+            walk_options: WalkOptions {
+                skip_binary: true,
+                ..Default::default()
+            },
+            html,
+            parse_configs: ParseConfigs::new(),
+        }
+    }
+
+    /// Walk the directory with `walk_options`, such as a limit on the size of files to read,
+    /// instead of the defaults, which skip binary files. Every endpoint lists the same files.
+    pub fn with_walk_options(mut self, walk_options: WalkOptions) -> Self {
+        self.walk_options = walk_options;
+        self
+    }
+
+    /// Use `parse_configs` instead of the default parse configs.
+    pub fn with_parse_configs(mut self, parse_configs: ParseConfigs) -> Self {
+        self.parse_configs = parse_configs;
+        self
+    }
+
+    /// Listen on `addr` and handle requests one at a time until the process is stopped.
+    pub fn serve(&self, addr: SocketAddr) -> Result<(), ServerError> {
+        let live_reload = if self.html {
+            Some(LiveReload::watch(&self.directory, &self.ignore_dirs)?)
+        } else {
+            None
+        };
+        let server = tiny_http::Server::http(addr).map_err(ServerError::ErrorStartingServer)?;
+        eprintln!("Listening on http://{}", addr);
+
+        for mut request in server.incoming_requests() {
+            if let Some(live_reload) = &live_reload {
+                if request.url() == "/live-reload" {
+                    live_reload.accept(request);
+                    continue;
+                }
+            }
+
+            let mut body = String::new();
+            let response = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self.handle(request.method(), request.url(), &body),
+                Err(e) => error_response(400, format!("Error reading request body: {}", e)),
+            };
+            let content_type = Header::from_bytes("Content-Type", response.content_type).unwrap();
+            let response = Response::from_string(response.body)
+                .with_status_code(response.status)
+                .with_header(content_type);
+            if let Err(e) = request.respond(response) {
+                eprintln!("Error responding to request: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&self, method: &Method, url: &str, body: &str) -> HttpResponse {
+        let path = url.split('?').next().unwrap_or_default();
+        match (method, path) {
+            (Method::Get, "/") if self.html => self.html_digest(),
+            (Method::Get, "/tree") => self.tree(),
+            (Method::Post, "/digest") => self.digest(body),
+            (Method::Get, path) if path.starts_with("/file/") && path.ends_with("/symbols") => {
+                let file_path = &path["/file/".len()..path.len() - "/symbols".len()];
+                self.symbols(&percent_decode_str(file_path).decode_utf8_lossy())
+            }
+            _ => error_response(404, format!("Not found: {} {}", method, path)),
+        }
+    }
+
+    fn tree(&self) -> HttpResponse {
+        let files = skip_walk_errors(get_files(
+            self.directory.clone(),
+            &self.ignore_dirs,
+            &self.walk_options,
+        ))
+        .filter(|file| file.depth > 0)
+        .map(|file| TreeEntry {
+            path: self.relative_path(&file.path),
+            kind: match file.kind {
+                FileKind::File => "file",
+                FileKind::Directory => "directory",
+            },
+            depth: file.depth,
+            size: (file.kind == FileKind::File).then_some(file.size),
+        })
+        .collect();
+        json_response(&TreeResponse { files })
+    }
+
+    fn digest(&self, body: &str) -> HttpResponse {
+        let request: DigestRequest = if body.trim().is_empty() {
+            DigestRequest::default()
+        } else {
+            match serde_json::from_str(body) {
+                Ok(request) => request,
+                Err(e) => return error_response(400, format!("Invalid request body: {}", e)),
+            }
+        };
+        let glob_matcher = match GlobPatternMatcher::new_from_strings(&request.include) {
+            Ok(glob_matcher) => glob_matcher,
+            Err(e) => return error_response(400, format!("Invalid include pattern: {}", e)),
+        };
+
+        let response = digest_directory(
+            &self.directory,
+            &self.ignore_dirs,
+            &self.walk_options,
+            &self.parse_configs,
+            &glob_matcher,
+            request.token_budget,
+        );
+        json_response(&response)
+    }
+
+    fn html_digest(&self) -> HttpResponse {
+        let glob_matcher = GlobPatternMatcher::new_from_strings(&[]).unwrap();
+        let digest = digest_directory(
+            &self.directory,
+            &self.ignore_dirs,
+            &self.walk_options,
+            &self.parse_configs,
+            &glob_matcher,
+            None,
+        );
+        HttpResponse {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: render_digest_html(&self.directory.display().to_string(), &digest, true),
+        }
+    }
+
+    fn symbols(&self, relative_path: &str) -> HttpResponse {
+        let file_path = match self.resolve(relative_path) {
+            Some(file_path) => file_path,
+            None => return error_response(404, format!("File not found: {}", relative_path)),
+        };
+        let parse_config = match self.parse_configs.for_path(&file_path) {
+            Some(parse_config) => parse_config,
+            None => {
+                return error_response(400, format!("Unsupported file kind: {}", relative_path))
+            }
+        };
+        let source_code = match std::fs::read_to_string(&file_path) {
+            Ok(source_code) => source_code,
+            Err(e) => return error_response(500, format!("Error reading file: {}", e)),
+        };
+        let symbols = match parse(&source_code, parse_config) {
+            Ok(key_contents) => key_contents.into_iter().map(Symbol::from).collect(),
+            Err(e) => return error_response(500, format!("Error parsing file: {}", e)),
+        };
+        json_response(&SymbolsResponse {
+            path: relative_path.to_string(),
+            symbols,
+        })
+    }
+
+    /// Resolve a path relative to the served directory, refusing anything that escapes it or that
+    /// the walk for `/tree` leaves out or lists as skipped, such as ignored or too large files.
+    fn resolve(&self, relative_path: &str) -> Option<PathBuf> {
+        let directory = self.directory.canonicalize().ok()?;
+        let file_path = directory.join(relative_path).canonicalize().ok()?;
+        let file = get_file(directory, &file_path, &self.ignore_dirs, &self.walk_options)?;
+        (file.kind == FileKind::File && file.skipped.is_none()).then_some(file.path)
+    }
+
+    fn relative_path(&self, path: &Path) -> String {
+        relative_path(&self.directory, path)
+    }
+}
+
+fn json_response(value: &impl Serialize) -> HttpResponse {
+    HttpResponse {
+        status: 200,
+        content_type: "application/json",
+        body: serde_json::to_string(value).unwrap(),
+    }
+}
+
+fn error_response(status: u16, error: String) -> HttpResponse {
+    HttpResponse {
+        status,
+        content_type: "application/json",
+        body: serde_json::to_string(&ErrorResponse { error }).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn server_with_files() -> (tempfile::TempDir, DigestServer) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("src").join("main.rs"),
+            r#"
+fn main() {
+    println!("Hello, world!");
+}
+"#,
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("README.txt"), "# Hello\n").unwrap();
+        let server = DigestServer::new(temp_dir.path().to_path_buf(), vec![], true);
+        (temp_dir, server)
+    }
+
+    fn handle(server: &DigestServer, method: Method, url: &str, body: &str) -> (u16, Value) {
+        let response = server.handle(&method, url, body);
+        (
+            response.status,
+            serde_json::from_str(&response.body).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_tree() {
+        let (_temp_dir, server) = server_with_files();
+        let (status, body) = handle(&server, Method::Get, "/tree", "");
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "files": [
+                    {"path": "README.txt", "kind": "file", "depth": 1, "size": 8},
+                    {"path": "src", "kind": "directory", "depth": 1},
+                    {"path": "src/main.rs", "kind": "file", "depth": 2, "size": 46},
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_digest() {
+        let (_temp_dir, server) = server_with_files();
+
+        let (status, body) = handle(&server, Method::Post, "/digest", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["files"].as_array().unwrap().len(), 1);
+        assert_eq!(body["files"][0]["path"], "src/main.rs");
+        assert!(body["files"][0]["digest"]
+            .as_str()
+            .unwrap()
+            .contains("fn main() {\n    // ...\n}"));
+
+        let (status, body) = handle(
+            &server,
+            Method::Post,
+            "/digest",
+            r#"{"include": ["*.txt"]}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["files"].as_array().unwrap().len(), 2);
+        assert_eq!(body["files"][0]["path"], "README.txt");
+        assert_eq!(
+            body["tokens"].as_u64().unwrap(),
+            body["files"][0]["tokens"].as_u64().unwrap()
+                + body["files"][1]["tokens"].as_u64().unwrap()
+        );
+
+        let (status, body) = handle(
+            &server,
+            Method::Post,
+            "/digest",
+            r#"{"include": ["*.txt"], "token_budget": 10}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["files"].as_array().unwrap().len(), 1);
+        assert_eq!(body["files"][0]["path"], "README.txt");
+        assert_eq!(body["omitted"], serde_json::json!(["src/main.rs"]));
+
+        let (status, _) = handle(&server, Method::Post, "/digest", "not json");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let (_temp_dir, server) = server_with_files();
+
+        let (status, body) = handle(&server, Method::Get, "/file/src%2Fmain.rs/symbols", "");
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "path": "src/main.rs",
+                "symbols": [
+                    {
+                        "content": "fn main() {\n    // ...\n}",
+                        "start_line": 2,
+                        "end_line": 4,
+                        "kind": "function",
+                        "name": "main",
+                        "start_byte": 1,
+                        "end_byte": 45,
+                    },
+                ]
+            })
+        );
+
+        let (status, _) = handle(&server, Method::Get, "/file/README.txt/symbols", "");
+        assert_eq!(status, 400);
+
+        let (status, _) = handle(
+            &server,
+            Method::Get,
+            "/file/..%2F..%2Fetc%2Fpasswd/symbols",
+            "",
+        );
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_symbols_of_files_left_out_of_tree() {
+        let (temp_dir, _) = server_with_files();
+        std::fs::create_dir(temp_dir.path().join("target")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("target").join("out.rs"),
+            "fn out() {}\n",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join(".codedigestignore"), "*.gen.rs\n").unwrap();
+        std::fs::write(temp_dir.path().join("api.gen.rs"), "fn api() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("big.rs"), "fn big() {}\n".repeat(10)).unwrap();
+        let server = DigestServer::new(
+            temp_dir.path().to_path_buf(),
+            vec![PathBuf::from("target")],
+            false,
+        )
+        .with_walk_options(WalkOptions {
+            max_file_size: Some(100),
+            hidden: true,
+            ..Default::default()
+        });
+        std::fs::create_dir(temp_dir.path().join(".config")).unwrap();
+        std::fs::write(
+            temp_dir.path().join(".config").join("hidden.rs"),
+            "fn hidden() {}\n",
+        )
+        .unwrap();
+
+        let (status, body) = handle(&server, Method::Get, "/tree", "");
+        assert_eq!(status, 200);
+        let paths: Vec<_> = body["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["path"].as_str().unwrap())
+            .collect();
+        assert!(!paths.contains(&"target/out.rs"));
+        assert!(!paths.contains(&"api.gen.rs"));
+
+        for path in ["target%2Fout.rs", "api.gen.rs", "big.rs"] {
+            let (status, _) = handle(&server, Method::Get, &format!("/file/{}/symbols", path), "");
+            assert_eq!(status, 404, "{}", path);
+        }
+        let (status, _) = handle(&server, Method::Get, "/file/src%2Fmain.rs/symbols", "");
+        assert_eq!(status, 200);
+
+        // The digest walks the directory the same way as the tree.
+        let (status, body) = handle(&server, Method::Post, "/digest", "");
+        assert_eq!(status, 200);
+        let digested: Vec<_> = body["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(digested, [".config/hidden.rs", "src/main.rs"]);
+        assert!(paths.contains(&".config/hidden.rs"));
+    }
+
+    #[test]
+    fn test_html() {
+        let (temp_dir, server) = server_with_files();
+        let response = server.handle(&Method::Get, "/", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "text/html; charset=utf-8");
+        assert!(response.body.contains("<h2><code>src/main.rs</code></h2>"));
+        assert!(response.body.contains("/live-reload"));
+
+        let server = DigestServer::new(temp_dir.path().to_path_buf(), vec![], false);
+        let (status, _) = handle(&server, Method::Get, "/", "");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_not_found() {
+        let (_temp_dir, server) = server_with_files();
+        let (status, _) = handle(&server, Method::Get, "/digest", "");
+        assert_eq!(status, 404);
+    }
+}
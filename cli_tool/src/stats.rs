@@ -0,0 +1,506 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use file_system::{File, FileKind};
+use language_parsers::{metrics, parse, FileMetrics};
+
+use crate::file_processor::ParseConfigs;
+
+/// How comments are written in a language, for telling comment lines from code.
+struct CommentSyntax {
+    language: &'static str,
+    extensions: &'static [&'static str],
+
+    /// Files recognized by their whole name rather than their extension, e.g. `CMakeLists.txt`.
+    file_names: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+}
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+
+const LANGUAGES: &[CommentSyntax] = &[
+    CommentSyntax {
+        language: "Bash",
+        extensions: &["sh", "bash"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "C",
+        extensions: &["c", "h"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "C++",
+        extensions: &["cc", "cpp", "cxx", "hh", "hpp"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Clojure",
+        extensions: &["clj", "cljs", "cljc"],
+        file_names: &[],
+        line_comments: &[";"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "CMake",
+        extensions: &["cmake"],
+        file_names: &["CMakeLists.txt"],
+        line_comments: &["#"],
+        block_comments: &[("#[[", "]]")],
+    },
+    CommentSyntax {
+        language: "Dart",
+        extensions: &["dart"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Dockerfile",
+        extensions: &["dockerfile"],
+        file_names: &["Dockerfile"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "F#",
+        extensions: &["fs", "fsx"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: &[("(*", "*)")],
+    },
+    CommentSyntax {
+        language: "Go",
+        extensions: &["go"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "HCL",
+        extensions: &["hcl", "tf"],
+        file_names: &[],
+        line_comments: &["#", "//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Java",
+        extensions: &["java"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "JavaScript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "JSON",
+        extensions: &["json"],
+        file_names: &[],
+        line_comments: &[],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "Julia",
+        extensions: &["jl"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[("#=", "=#")],
+    },
+    CommentSyntax {
+        language: "Markdown",
+        extensions: &["md", "markdown"],
+        file_names: &[],
+        line_comments: &[],
+        block_comments: &[("<!--", "-->")],
+    },
+    CommentSyntax {
+        language: "Makefile",
+        extensions: &["mk"],
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "Nix",
+        extensions: &["nix"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Protocol Buffers",
+        extensions: &["proto"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Python",
+        extensions: &["py"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "Ruby",
+        extensions: &["rb"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[("=begin", "=end")],
+    },
+    CommentSyntax {
+        language: "Rust",
+        extensions: &["rs"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "SQL",
+        extensions: &["sql"],
+        file_names: &[],
+        line_comments: &["--"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "Svelte",
+        extensions: &["svelte"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/"), ("<!--", "-->")],
+    },
+    CommentSyntax {
+        language: "TOML",
+        extensions: &["toml"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    CommentSyntax {
+        language: "TypeScript",
+        extensions: &["ts", "tsx"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    CommentSyntax {
+        language: "YAML",
+        extensions: &["yaml", "yml"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+];
+
+/// Line and symbol counts for one language.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+
+    /// Captured items, or None if the language is not parsed.
+    pub symbols: Option<usize>,
+}
+
+/// The number of files listed in the table of the most complex files.
+const MOST_COMPLEX_FILES: usize = 10;
+
+/// Counts of lines of code, comments, and blanks per language, in the style of `tokei`, and the
+/// metrics of each file with a tree-sitter grammar.
+#[derive(Debug, Default)]
+pub struct CodeStats {
+    languages: BTreeMap<&'static str, LanguageStats>,
+    files: Vec<(String, FileMetrics)>,
+}
+
+impl CodeStats {
+    /// Count the lines and captured items in every file. Directories and files in languages
+    /// without a known comment syntax are skipped.
+    pub fn collect(
+        files: impl Iterator<Item = File>,
+        read_file: impl Fn(&Path) -> std::io::Result<String>,
+        parse_configs: &ParseConfigs,
+    ) -> CodeStats {
+        let mut stats = CodeStats::default();
+        for file in files {
+            if file.kind != FileKind::File || file.skipped.is_some() {
+                continue;
+            }
+            let syntax = match comment_syntax(&file.path) {
+                Some(syntax) => syntax,
+                None => continue,
+            };
+            let source_code = match read_file(&file.path) {
+                Ok(source_code) => source_code,
+                Err(e) => {
+                    eprintln!("Error reading file {}: {}", file.path.display(), e);
+                    continue;
+                }
+            };
+            let parse_config = parse_configs.for_path(&file.path);
+            let symbols = parse_config
+                .and_then(|parse_config| parse(&source_code, parse_config).ok())
+                .map(|key_contents| key_contents.len());
+            stats.add(syntax, &source_code, symbols);
+            if let Some(file_metrics) =
+                parse_config.and_then(|parse_config| metrics(&source_code, parse_config))
+            {
+                stats
+                    .files
+                    .push((file.path.display().to_string(), file_metrics));
+            }
+        }
+        stats
+    }
+
+    fn add(&mut self, syntax: &CommentSyntax, source_code: &str, symbols: Option<usize>) {
+        let (code, comments, blanks) = count_lines(syntax, source_code);
+        let language = self.languages.entry(syntax.language).or_default();
+        language.files += 1;
+        language.code += code;
+        language.comments += comments;
+        language.blanks += blanks;
+        if let Some(symbols) = symbols {
+            *language.symbols.get_or_insert(0) += symbols;
+        }
+    }
+
+    /// The files with the highest complexity first, as a rough ranking of where the most important
+    /// logic is.
+    pub fn most_complex_files(&self) -> Vec<&(String, FileMetrics)> {
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        files.sort_by(|(a_path, a), (b_path, b)| {
+            b.complexity
+                .cmp(&a.complexity)
+                .then_with(|| a_path.cmp(b_path))
+        });
+        files
+    }
+
+    /// Render the counts as a Markdown table with a row per language and a total row, followed by
+    /// a table of the most complex files if any were parsed with a tree-sitter grammar.
+    pub fn to_markdown(&self) -> String {
+        let mut output = String::new();
+        output.push_str("| Language | Files | Code | Comments | Blanks | Symbols |\n");
+        output.push_str("| --- | ---: | ---: | ---: | ---: | ---: |\n");
+        let mut total = LanguageStats::default();
+        for (language, stats) in &self.languages {
+            output.push_str(&format_row(language, stats));
+            total.files += stats.files;
+            total.code += stats.code;
+            total.comments += stats.comments;
+            total.blanks += stats.blanks;
+            if let Some(symbols) = stats.symbols {
+                *total.symbols.get_or_insert(0) += symbols;
+            }
+        }
+        output.push_str(&format_row("Total", &total));
+
+        if !self.files.is_empty() {
+            output.push_str("\n| File | Code | Functions | Types | Complexity |\n");
+            output.push_str("| --- | ---: | ---: | ---: | ---: |\n");
+            for (path, file_metrics) in self
+                .most_complex_files()
+                .into_iter()
+                .take(MOST_COMPLEX_FILES)
+            {
+                output.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    path,
+                    file_metrics.lines_of_code,
+                    file_metrics.functions,
+                    file_metrics.types,
+                    file_metrics.complexity
+                ));
+            }
+        }
+        output
+    }
+}
+
+fn format_row(language: &str, stats: &LanguageStats) -> String {
+    format!(
+        "| {} | {} | {} | {} | {} | {} |\n",
+        language,
+        stats.files,
+        stats.code,
+        stats.comments,
+        stats.blanks,
+        stats
+            .symbols
+            .map_or_else(|| "-".to_string(), |symbols| symbols.to_string())
+    )
+}
+
+fn comment_syntax(path: &Path) -> Option<&'static CommentSyntax> {
+    let file_name = path.file_name()?.to_str()?;
+    if let Some(syntax) = LANGUAGES
+        .iter()
+        .find(|syntax| syntax.file_names.contains(&file_name))
+    {
+        return Some(syntax);
+    }
+    let extension = path.extension()?.to_str()?;
+    LANGUAGES
+        .iter()
+        .find(|syntax| syntax.extensions.contains(&extension))
+}
+
+/// Returns the number of code, comment, and blank lines. A line with both code and a comment counts
+/// as code. Comment markers inside strings are not recognized, as in most line counters.
+fn count_lines(syntax: &CommentSyntax, source_code: &str) -> (usize, usize, usize) {
+    let (mut code, mut comments, mut blanks) = (0, 0, 0);
+    // The end marker of the block comment that the current line starts inside of, if any.
+    let mut block_end: Option<&str> = None;
+    for line in source_code.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() && block_end.is_none() {
+            blanks += 1;
+            continue;
+        }
+
+        let mut has_code = false;
+        loop {
+            if let Some(end) = block_end {
+                match rest.find(end) {
+                    Some(i) => {
+                        rest = rest[i + end.len()..].trim_start();
+                        block_end = None;
+                    }
+                    None => break,
+                }
+            }
+            if rest.is_empty()
+                || syntax
+                    .line_comments
+                    .iter()
+                    .any(|prefix| rest.starts_with(prefix))
+            {
+                break;
+            }
+            match syntax
+                .block_comments
+                .iter()
+                .filter_map(|(start, end)| rest.find(start).map(|i| (i, *start, *end)))
+                .min_by_key(|(i, _, _)| *i)
+            {
+                Some((i, start, end)) => {
+                    has_code |= !rest[..i].trim().is_empty();
+                    rest = &rest[i + start.len()..];
+                    block_end = Some(end);
+                }
+                None => {
+                    has_code = true;
+                    break;
+                }
+            }
+        }
+
+        if has_code {
+            code += 1;
+        } else {
+            comments += 1;
+        }
+    }
+    (code, comments, blanks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_lines() {
+        let rust = comment_syntax(Path::new("main.rs")).unwrap();
+        let source_code = r#"
+// A line comment.
+/* A block comment
+   over two lines. */
+fn main() { /* inline */
+    println!("Hello, world!"); // trailing
+
+    /* one line */
+}
+"#;
+        assert_eq!(count_lines(rust, source_code), (3, 4, 2));
+
+        let python = comment_syntax(Path::new("main.py")).unwrap();
+        assert_eq!(count_lines(python, "# comment\nx = 1\n\n"), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_code_stats() {
+        let parse_configs = ParseConfigs::new();
+        let files = vec![
+            File {
+                path: "main.rs".into(),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+            File {
+                path: "build.sh".into(),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+            File {
+                path: "data.bin".into(),
+                kind: FileKind::File,
+                depth: 1,
+                skipped: None,
+                size: 0,
+                modified: None,
+            },
+        ];
+        let read_file = |path: &Path| match path.to_str() {
+            Some("main.rs") => Ok("// Entry point.\nfn main() {}\n\nfn other() {}\n".to_string()),
+            Some("build.sh") => Ok("#!/bin/sh\ncargo build\n".to_string()),
+            _ => Ok(String::new()),
+        };
+
+        let stats = CodeStats::collect(files.into_iter(), read_file, &parse_configs);
+        assert_eq!(
+            stats.to_markdown(),
+            "| Language | Files | Code | Comments | Blanks | Symbols |
+| --- | ---: | ---: | ---: | ---: | ---: |
+| Bash | 1 | 1 | 1 | 0 | 1 |
+| Rust | 1 | 2 | 1 | 1 | 2 |
+| Total | 2 | 3 | 2 | 1 | 3 |
+
+| File | Code | Functions | Types | Complexity |
+| --- | ---: | ---: | ---: | ---: |
+| main.rs | 2 | 2 | 0 | 2 |
+| build.sh | 1 | 0 | 0 | 1 |
+"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Images, compiled artifacts, archives and other binary files are recognized by sniffing the start
+// of their contents, as git does, rather than by extension, so that binaries with unusual names or
+// none are caught too and text files with unusual extensions are not.
+
+use std::io::Read;
+use std::path::Path;
+
+/// How many bytes at the start of a file are sniffed, as in git.
+const SNIFF_LENGTH: u64 = 8000;
+
+/// Whether `bytes`, the start of a file, look like binary rather than text: they hold a NUL byte,
+/// are not UTF-8, or more than a tenth of them are control characters other than whitespace.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => {}
+        // A character cut off by the end of the sniffed bytes is fine.
+        Err(error) if error.error_len().is_none() => {}
+        Err(_) => return true,
+    }
+    let control = bytes
+        .iter()
+        .filter(|&&byte| byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control * 10 > bytes.len()
+}
+
+/// Whether the file at `path` looks binary. Files that cannot be read are left for callers to
+/// report when they read them.
+pub(crate) fn is_binary_file(path: &Path) -> bool {
+    let mut bytes = vec![];
+    match std::fs::File::open(path) {
+        Ok(file) => file.take(SNIFF_LENGTH).read_to_end(&mut bytes).is_ok() && looks_binary(&bytes),
+        Err(_) => false,
+    }
+}
+
+/// Whether `contents`, a whole blob read from git, look binary. Only the start is sniffed, as for
+/// files on disk.
+pub(crate) fn is_binary_blob(contents: &[u8]) -> bool {
+    looks_binary(&contents[..contents.len().min(SNIFF_LENGTH as usize)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(!looks_binary(b""));
+        assert!(!looks_binary(b"fn main() {\n\tprintln!(\"hello\");\r\n}\n"));
+        assert!(!looks_binary("// Grüße, 世界\n".as_bytes()));
+        // The last character is cut off after its first byte.
+        assert!(!looks_binary(&"text 世".as_bytes()[..6]));
+        assert!(!looks_binary(b"\x1b[31mred\x1b[0m\n"));
+
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(looks_binary(b"\x7fELF\x02\x01\x01"));
+        assert!(looks_binary(b"caf\xe9 latin-1 text"));
+        assert!(looks_binary(b"\x01\x02\x03\x04 text"));
+    }
+}
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::path::{Path, PathBuf};
+
+use git2::{BlameOptions, Oid, Repository};
+
+use crate::git_tree::workdir_prefix;
+use crate::GitError;
+
+/// The commit that last changed a range of lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChange {
+    pub author: String,
+
+    /// Commit time in seconds since the Unix epoch.
+    pub time: i64,
+
+    pub commit_id: String,
+}
+
+struct BlameHunk {
+    start_line: usize,
+    end_line: usize,
+    change: LineChange,
+}
+
+/// Line-by-line ownership of a file, as reported by `git blame`.
+pub struct FileBlame {
+    hunks: Vec<BlameHunk>,
+}
+
+impl FileBlame {
+    /// Returns the most recent change to any line between `start_line` and `end_line` inclusive,
+    /// where lines start from 1. Returns None if none of the lines are committed.
+    pub fn last_change(&self, start_line: usize, end_line: usize) -> Option<&LineChange> {
+        self.hunks
+            .iter()
+            .filter(|hunk| hunk.start_line <= end_line && start_line <= hunk.end_line)
+            .map(|hunk| &hunk.change)
+            .max_by_key(|change| change.time)
+    }
+}
+
+/// The git repository containing a directory, opened once to blame many of the files in it as of a
+/// revision.
+pub struct GitBlame {
+    repository: Repository,
+    directory: PathBuf,
+
+    /// The path of `directory` relative to the root of the working tree.
+    prefix: PathBuf,
+    newest_commit: Option<Oid>,
+}
+
+impl GitBlame {
+    /// Open the git repository containing `directory` to blame files as of `rev`, or as of `HEAD`
+    /// if no revision is given.
+    pub fn open(directory: PathBuf, rev: Option<&str>) -> Result<GitBlame, GitError> {
+        let repository = Repository::discover(&directory)?;
+        let prefix = workdir_prefix(&repository, &directory)?;
+        let newest_commit = match rev {
+            Some(rev) => Some(repository.revparse_single(rev)?.peel_to_commit()?.id()),
+            None => None,
+        };
+        Ok(GitBlame {
+            repository,
+            directory,
+            prefix,
+            newest_commit,
+        })
+    }
+
+    /// Blame the file at `path`, one of the paths under the directory the repository was opened
+    /// with, given the `contents` that were digested. Lines of `contents` that are not in the file
+    /// at the revision, such as uncommitted changes, are not attributed to any commit. The file
+    /// does not need to exist in the working tree.
+    pub fn blame(&self, path: &Path, contents: &str) -> Result<FileBlame, GitError> {
+        let relative_path = path
+            .strip_prefix(&self.directory)
+            .map(|path| self.prefix.join(path))
+            .map_err(|_| GitError::OutsideWorkingTree(path.to_path_buf()))?;
+        let mut options = BlameOptions::new();
+        if let Some(newest_commit) = self.newest_commit {
+            options.newest_commit(newest_commit);
+        }
+        let blame = self
+            .repository
+            .blame_file(&relative_path, Some(&mut options))?;
+        let buffer_blame = blame.blame_buffer(contents.as_bytes())?;
+        Ok(file_blame(&buffer_blame))
+    }
+}
+
+fn file_blame(blame: &git2::Blame) -> FileBlame {
+    let hunks = blame
+        .iter()
+        // Lines that are only in the buffer have no commit.
+        .filter(|hunk| !hunk.final_commit_id().is_zero())
+        .map(|hunk| {
+            let signature = hunk.final_signature();
+            BlameHunk {
+                start_line: hunk.final_start_line(),
+                end_line: hunk.final_start_line() + hunk.lines_in_hunk() - 1,
+                change: LineChange {
+                    author: signature.name().unwrap_or_default().to_string(),
+                    time: signature.when().seconds(),
+                    commit_id: hunk.final_commit_id().to_string(),
+                },
+            }
+        })
+        .collect();
+    FileBlame { hunks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn commit_as(repository: &Repository, author: &str, time: i64) {
+        let mut index = repository.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            git2::Signature::new(author, "test@example.com", &git2::Time::new(time, 0)).unwrap();
+        let parent = repository
+            .head()
+            .ok()
+            .map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "commit",
+                &tree,
+                &parents,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_blame_file() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let file = root.join("file.txt");
+
+        std::fs::write(&file, "one\ntwo\nthree\n").unwrap();
+        commit_as(&repository, "alice", 1_000);
+        std::fs::write(&file, "one\nTWO\nthree\n").unwrap();
+        commit_as(&repository, "bob", 2_000);
+
+        let git_blame = GitBlame::open(root.clone(), None).unwrap();
+        let blame = git_blame.blame(&file, "one\nTWO\nthree\n").unwrap();
+        assert_eq!(blame.last_change(1, 1).unwrap().author, "alice");
+        assert_eq!(blame.last_change(3, 3).unwrap().author, "alice");
+        assert_eq!(blame.last_change(2, 2).unwrap().author, "bob");
+        assert_eq!(blame.last_change(1, 3).unwrap().author, "bob");
+        assert_eq!(blame.last_change(1, 3).unwrap().time, 2_000);
+        assert!(blame.last_change(10, 12).is_none());
+
+        // Lines are those of the contents that were digested, which may have uncommitted changes.
+        let blame = git_blame.blame(&file, "zero\none\nTWO\nthree\n").unwrap();
+        assert!(blame.last_change(1, 1).is_none());
+        assert_eq!(blame.last_change(2, 2).unwrap().author, "alice");
+        assert_eq!(blame.last_change(3, 3).unwrap().author, "bob");
+
+        // A file at a revision does not need to be in the working tree.
+        std::fs::remove_file(&file).unwrap();
+        let git_blame = GitBlame::open(root, Some("HEAD~1")).unwrap();
+        let blame = git_blame.blame(&file, "one\ntwo\nthree\n").unwrap();
+        assert_eq!(blame.last_change(1, 3).unwrap().author, "alice");
+    }
+}
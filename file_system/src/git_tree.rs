@@ -0,0 +1,710 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::path::{Path, PathBuf};
+
+use git2::{ObjectType, Repository};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::Override;
+
+use crate::binary::is_binary_blob;
+use crate::{
+    ignore_overrides, is_hidden_path_included, File, FileKind, SkipReason, WalkOptions,
+    IGNORE_FILE_NAME,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum GitError {
+    #[error("git error: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("repository has no working tree")]
+    BareRepository,
+
+    #[error("directory is not inside the repository working tree: {0}")]
+    OutsideWorkingTree(PathBuf),
+
+    #[error("error resolving directory: {0}")]
+    ErrorResolvingDirectory(#[from] std::io::Error),
+
+    #[error("invalid directory to ignore: {0}")]
+    InvalidIgnoreDir(#[from] ignore::Error),
+}
+
+/// A read-only view of a directory as it existed at a git commit, tag, or branch.
+///
+/// Files are read straight from the object database, so the working tree is never touched. Paths
+/// passed in and returned are rooted at the directory the tree was opened with, the same as the
+/// paths returned by `get_files`, so callers can swap one for the other.
+pub struct GitTree {
+    repository: Repository,
+    tree_id: git2::Oid,
+    directory: PathBuf,
+
+    /// The tree of the whole repository, for the ignore files in the directories above `directory`.
+    root_tree_id: git2::Oid,
+
+    /// The path of `directory` relative to the root of the repository.
+    prefix: PathBuf,
+}
+
+/// The ignore files in one directory of a tree.
+struct DirectoryIgnores {
+    custom: Gitignore,
+    ignore: Gitignore,
+    git: Gitignore,
+}
+
+impl GitTree {
+    /// Open the git repository containing `directory` and resolve `rev` (anything `git rev-parse`
+    /// accepts) to the tree for `directory` at that revision.
+    pub fn open(directory: PathBuf, rev: &str) -> Result<GitTree, GitError> {
+        let repository = Repository::discover(&directory)?;
+        let prefix = workdir_prefix(&repository, &directory)?;
+        let (root_tree_id, tree_id) = {
+            let tree = repository.revparse_single(rev)?.peel_to_tree()?;
+            (tree.id(), subtree_id(&repository, &tree, &prefix)?)
+        };
+
+        Ok(GitTree {
+            repository,
+            tree_id,
+            directory,
+            root_tree_id,
+            prefix,
+        })
+    }
+
+    /// Open the git repository containing `directory` and read `directory` as it is in the index,
+    /// i.e. as it would be committed, ignoring unstaged changes.
+    pub fn open_index(directory: PathBuf) -> Result<GitTree, GitError> {
+        let repository = Repository::discover(&directory)?;
+        let prefix = workdir_prefix(&repository, &directory)?;
+        let (root_tree_id, tree_id) = {
+            // Writing the index as a tree only adds objects to the object database; the index,
+            // refs, and working tree are unchanged.
+            let index_tree_id = repository.index()?.write_tree()?;
+            let tree = repository.find_tree(index_tree_id)?;
+            (tree.id(), subtree_id(&repository, &tree, &prefix)?)
+        };
+
+        Ok(GitTree {
+            repository,
+            tree_id,
+            directory,
+            root_tree_id,
+            prefix,
+        })
+    }
+
+    /// Returns every file and directory in the tree, in the same order and with the same depths as
+    /// `get_files`. Submodules are skipped, and so is anything under one of `ignore_dirs` or
+    /// excluded by the `.gitignore`, `.ignore` and `.codedigestignore` files in the tree, including
+    /// those in the directories above it, which are all matched the same way as by `get_files`.
+    /// Hidden files are left out, and files listed as skipped, as by `get_files` with `options`.
+    pub fn files(
+        &self,
+        ignore_dirs: &[PathBuf],
+        options: &WalkOptions,
+    ) -> Result<Vec<File>, GitError> {
+        let overrides = ignore_overrides(&self.directory, ignore_dirs)?;
+        let root_tree = self.repository.find_tree(self.root_tree_id)?;
+        let mut ignores = vec![];
+        // The ignore files in the directories above this one apply too, the outermost first.
+        let mut ancestors: Vec<_> = self.prefix.ancestors().skip(1).collect();
+        ancestors.reverse();
+        for ancestor in ancestors {
+            let tree_id = subtree_id(&self.repository, &root_tree, ancestor)?;
+            let tree = self.repository.find_tree(tree_id)?;
+            ignores.push(self.directory_ignores(&tree, ancestor)?);
+        }
+
+        let mut result = vec![File {
+            path: self.directory.clone(),
+            kind: FileKind::Directory,
+            depth: 0,
+            skipped: None,
+            size: 0,
+            modified: None,
+        }];
+        let tree = self.repository.find_tree(self.tree_id)?;
+        let parent = TreeDirectory {
+            path: &self.directory,
+            relative_path: &self.prefix,
+            depth: 1,
+        };
+        self.collect_files(
+            &tree,
+            parent,
+            &overrides,
+            &mut ignores,
+            options,
+            &mut result,
+        )?;
+        Ok(result)
+    }
+
+    fn collect_files(
+        &self,
+        tree: &git2::Tree,
+        parent: TreeDirectory,
+        overrides: &Override,
+        ignores: &mut Vec<DirectoryIgnores>,
+        options: &WalkOptions,
+        result: &mut Vec<File>,
+    ) -> Result<(), GitError> {
+        let mut entries: Vec<_> = tree.iter().collect();
+        entries.sort_by(|a, b| a.name_bytes().cmp(b.name_bytes()));
+        ignores.push(self.directory_ignores(tree, parent.relative_path)?);
+        let depth = parent.depth;
+
+        for entry in entries {
+            let name = match entry.name() {
+                Some(name) => name,
+                None => continue,
+            };
+            let path = parent.path.join(name);
+            let relative_path = parent.relative_path.join(name);
+            let is_dir = entry.kind() == Some(ObjectType::Tree);
+            let hidden = !options.hidden
+                && !is_hidden_path_included(
+                    path.strip_prefix(&self.directory).unwrap_or(&path),
+                    &options.include_hidden,
+                );
+            if hidden
+                || overrides.matched(&path, is_dir).is_ignore()
+                || is_ignored(ignores, &relative_path, is_dir)
+            {
+                continue;
+            }
+
+            match entry.kind() {
+                Some(ObjectType::Tree) => {
+                    result.push(File {
+                        path: path.clone(),
+                        kind: FileKind::Directory,
+                        depth,
+                        skipped: None,
+                        size: 0,
+                        modified: None,
+                    });
+                    let subtree = self.repository.find_tree(entry.id())?;
+                    let directory = TreeDirectory {
+                        path: &path,
+                        relative_path: &relative_path,
+                        depth: depth + 1,
+                    };
+                    self.collect_files(&subtree, directory, overrides, ignores, options, result)?;
+                }
+                Some(ObjectType::Blob) => {
+                    // The header holds the size without reading the whole blob.
+                    let (size, _) = self.repository.odb()?.read_header(entry.id())?;
+                    let size = size as u64;
+                    let too_large = options
+                        .max_file_size
+                        .is_some_and(|max_file_size| size > max_file_size);
+                    let skipped = if too_large {
+                        Some(SkipReason::TooLarge)
+                    } else if options.skip_binary
+                        && is_binary_blob(self.repository.find_blob(entry.id())?.content())
+                    {
+                        Some(SkipReason::Binary)
+                    } else {
+                        None
+                    };
+                    result.push(File {
+                        path,
+                        kind: FileKind::File,
+                        depth,
+                        skipped,
+                        size,
+                        modified: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+        ignores.pop();
+        Ok(())
+    }
+
+    /// The ignore files in `tree`, the directory at `relative_path` in the repository. Lines that
+    /// are not valid globs are skipped, as they are by a walk.
+    fn directory_ignores(
+        &self,
+        tree: &git2::Tree,
+        relative_path: &Path,
+    ) -> Result<DirectoryIgnores, GitError> {
+        let matcher = |file_name: &str| -> Result<Gitignore, GitError> {
+            let mut builder = GitignoreBuilder::new(relative_path);
+            if let Some(entry) = tree.get_name(file_name) {
+                if entry.kind() == Some(ObjectType::Blob) {
+                    let blob = self.repository.find_blob(entry.id())?;
+                    let from = relative_path.join(file_name);
+                    for line in String::from_utf8_lossy(blob.content()).lines() {
+                        let _ = builder.add_line(Some(from.clone()), line);
+                    }
+                }
+            }
+            Ok(builder.build().unwrap_or_else(|_| Gitignore::empty()))
+        };
+        Ok(DirectoryIgnores {
+            custom: matcher(IGNORE_FILE_NAME)?,
+            ignore: matcher(".ignore")?,
+            git: matcher(".gitignore")?,
+        })
+    }
+
+    /// Read the contents of a file in the tree. `path` must be one of the paths returned by
+    /// `files`. Errors are reported as `std::io::Error` so that callers can treat this exactly like
+    /// `std::fs::read_to_string`.
+    pub fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        let relative_path = path.strip_prefix(&self.directory).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is outside {}", path.display(), self.directory.display()),
+            )
+        })?;
+        let blob = self
+            .repository
+            .find_tree(self.tree_id)
+            .and_then(|tree| tree.get_path(relative_path))
+            .and_then(|entry| self.repository.find_blob(entry.id()))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+        String::from_utf8(blob.content().to_vec())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// A directory of the tree being listed.
+struct TreeDirectory<'a> {
+    /// The path rooted at the directory the tree was opened with.
+    path: &'a Path,
+
+    /// The path relative to the root of the repository, which ignore files are matched against.
+    relative_path: &'a Path,
+
+    /// The depth of the entries in the directory.
+    depth: isize,
+}
+
+/// Whether the ignore files of `path`'s directory and those above it, the innermost first, leave
+/// `path` out. As in a walk, `.codedigestignore` files take precedence over `.ignore` files, which
+/// take precedence over `.gitignore` files.
+fn is_ignored(ignores: &[DirectoryIgnores], path: &Path, is_dir: bool) -> bool {
+    let matched = |matcher: fn(&DirectoryIgnores) -> &Gitignore| {
+        ignores
+            .iter()
+            .rev()
+            .map(|directory| matcher(directory).matched(path, is_dir))
+            .find(|matched| !matched.is_none())
+    };
+    matched(|directory| &directory.custom)
+        .or_else(|| matched(|directory| &directory.ignore))
+        .or_else(|| matched(|directory| &directory.git))
+        .is_some_and(|matched| matched.is_ignore())
+}
+
+/// The path of `directory` relative to the root of the repository's working tree.
+pub(crate) fn workdir_prefix(
+    repository: &Repository,
+    directory: &Path,
+) -> Result<PathBuf, GitError> {
+    let workdir = repository
+        .workdir()
+        .ok_or(GitError::BareRepository)?
+        .canonicalize()?;
+    Ok(directory
+        .canonicalize()?
+        .strip_prefix(&workdir)
+        .map_err(|_| GitError::OutsideWorkingTree(directory.to_path_buf()))?
+        .to_path_buf())
+}
+
+fn subtree_id(
+    repository: &Repository,
+    tree: &git2::Tree,
+    prefix: &Path,
+) -> Result<git2::Oid, GitError> {
+    if prefix.as_os_str().is_empty() {
+        return Ok(tree.id());
+    }
+    Ok(tree
+        .get_path(prefix)?
+        .to_object(repository)?
+        .peel_to_tree()?
+        .id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WalkOptions;
+    use tempfile::tempdir;
+
+    fn commit_all(repository: &Repository, message: &str) {
+        let mut index = repository.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let parent = repository
+            .head()
+            .ok()
+            .map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_git_tree_reads_committed_contents() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let dir_a = temp_dir.path().join("a");
+        let file_a1 = dir_a.join("file_a1.txt");
+        let file_b = temp_dir.path().join("file_b.txt");
+
+        std::fs::create_dir(&dir_a).unwrap();
+        std::fs::write(&file_a1, "first").unwrap();
+        commit_all(&repository, "first");
+        repository
+            .tag_lightweight("v1", &repository.revparse_single("HEAD").unwrap(), false)
+            .unwrap();
+
+        std::fs::write(&file_a1, "second").unwrap();
+        std::fs::write(&file_b, "new").unwrap();
+        commit_all(&repository, "second");
+        std::fs::write(&file_a1, "uncommitted").unwrap();
+
+        let root = temp_dir.path().to_path_buf();
+
+        let head = GitTree::open(root.clone(), "HEAD").unwrap();
+        let files = head.files(&[], &WalkOptions::default()).unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].path, root);
+        assert_eq!(files[0].depth, 0);
+        assert_eq!(files[1].path, dir_a);
+        assert_eq!(files[1].kind, FileKind::Directory);
+        assert_eq!(files[1].depth, 1);
+        assert_eq!(files[2].path, file_a1);
+        assert_eq!(files[2].kind, FileKind::File);
+        assert_eq!(files[2].depth, 2);
+        assert_eq!(files[2].size, "second".len() as u64);
+        assert_eq!(files[2].modified, None);
+        assert_eq!(files[3].path, file_b);
+        assert_eq!(files[3].depth, 1);
+        assert_eq!(head.read_to_string(&file_a1).unwrap(), "second");
+
+        let tagged = GitTree::open(root, "v1").unwrap();
+        assert_eq!(tagged.files(&[], &WalkOptions::default()).unwrap().len(), 3);
+        assert_eq!(tagged.read_to_string(&file_a1).unwrap(), "first");
+        assert!(tagged.read_to_string(&file_b).is_err());
+    }
+
+    #[test]
+    fn test_git_tree_subdirectory() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let dir_a = temp_dir.path().join("a");
+        let file_a1 = dir_a.join("file_a1.txt");
+        let file_b = temp_dir.path().join("file_b.txt");
+
+        std::fs::create_dir(&dir_a).unwrap();
+        std::fs::write(&file_a1, "a1").unwrap();
+        std::fs::write(&file_b, "b").unwrap();
+        commit_all(&repository, "first");
+
+        let git_tree = GitTree::open(dir_a.clone(), "HEAD").unwrap();
+        let files = git_tree.files(&[], &WalkOptions::default()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, dir_a);
+        assert_eq!(files[1].path, file_a1);
+        assert_eq!(files[1].depth, 1);
+        assert_eq!(git_tree.read_to_string(&file_a1).unwrap(), "a1");
+    }
+
+    #[test]
+    fn test_git_tree_ignore_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for directory in ["src", "target", "crates/cli/target"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "src/debug.log",
+            "target/out.rs",
+            "crates/cli/main.rs",
+            "crates/cli/target/out.rs",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        commit_all(&repository, "first");
+
+        // The tree at a revision leaves out the same files as a walk of the working tree.
+        let ignore_dirs = [PathBuf::from("target"), PathBuf::from("*.log")];
+        let paths = |files: Vec<File>| {
+            files
+                .into_iter()
+                .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        let git_tree = GitTree::open(root.clone(), "HEAD").unwrap();
+        let files = paths(
+            git_tree
+                .files(&ignore_dirs, &WalkOptions::default())
+                .unwrap(),
+        );
+        assert_eq!(
+            files,
+            [
+                "",
+                "crates",
+                "crates/cli",
+                "crates/cli/main.rs",
+                "src",
+                "src/lib.rs"
+            ]
+            .map(PathBuf::from)
+        );
+        let walked = crate::get_files(root.clone(), &ignore_dirs, &Default::default())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(files, paths(walked));
+    }
+
+    #[test]
+    fn test_git_tree_ignore_files() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for directory in ["fixtures", "src/fixtures", "src/generated"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        for (file, contents) in [
+            (".codedigestignore", "fixtures/\n!src/generated/keep.rs\n"),
+            ("fixtures/gen.rs", ""),
+            ("src/.ignore", "generated/*\n"),
+            ("src/lib.rs", ""),
+            ("src/fixtures/gen.rs", ""),
+            ("src/generated/api.rs", ""),
+            ("src/generated/keep.rs", ""),
+        ] {
+            std::fs::write(root.join(file), contents).unwrap();
+        }
+        commit_all(&repository, "first");
+
+        // The tree at a revision and in the index leave out the same files as a walk of the
+        // working tree, including from a subdirectory, where the ignore files above it apply.
+        let options = WalkOptions {
+            hidden: true,
+            ..Default::default()
+        };
+        let ignore_dirs = [PathBuf::from(".git")];
+        for directory in [root.clone(), root.join("src")] {
+            let paths = |files: Vec<File>| {
+                files
+                    .into_iter()
+                    .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                    .collect::<Vec<_>>()
+            };
+            let walked = paths(
+                crate::get_files(directory.clone(), &ignore_dirs, &options)
+                    .map(Result::unwrap)
+                    .collect(),
+            );
+            let head = GitTree::open(directory.clone(), "HEAD").unwrap();
+            assert_eq!(paths(head.files(&ignore_dirs, &options).unwrap()), walked);
+            let index = GitTree::open_index(directory).unwrap();
+            assert_eq!(paths(index.files(&ignore_dirs, &options).unwrap()), walked);
+        }
+
+        let head = GitTree::open(root.clone(), "HEAD").unwrap();
+        let files: Vec<_> = head
+            .files(&[], &options)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                "",
+                ".codedigestignore",
+                "src",
+                "src/.ignore",
+                "src/generated",
+                "src/generated/keep.rs",
+                "src/lib.rs",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_git_tree_hidden() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let repository = Repository::init(&root).unwrap();
+        for directory in [".github/workflows", ".idea", "src"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        for file in [
+            ".github/workflows/ci.yml",
+            ".github/dependabot.yml",
+            ".idea/workspace.xml",
+            ".env",
+            "src/lib.rs",
+        ] {
+            std::fs::write(root.join(file), "a").unwrap();
+        }
+        commit_all(&repository, "first");
+
+        let ignore_dirs = [PathBuf::from(".git")];
+        let head = GitTree::open(root.clone(), "HEAD").unwrap();
+        let paths = |files: Vec<File>| {
+            files
+                .into_iter()
+                .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        for options in [
+            WalkOptions::default(),
+            WalkOptions {
+                include_hidden: vec![PathBuf::from(".github/workflows")],
+                ..Default::default()
+            },
+            WalkOptions {
+                hidden: true,
+                ..Default::default()
+            },
+        ] {
+            let walked: Vec<_> = crate::get_files(root.clone(), &ignore_dirs, &options)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(
+                paths(head.files(&ignore_dirs, &options).unwrap()),
+                paths(walked)
+            );
+        }
+        assert_eq!(
+            paths(head.files(&ignore_dirs, &WalkOptions::default()).unwrap()),
+            ["", "src", "src/lib.rs"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_git_tree_max_file_size() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        std::fs::write(temp_dir.path().join("small.txt"), "abc").unwrap();
+        std::fs::write(temp_dir.path().join("large.txt"), "abcd").unwrap();
+        commit_all(&repository, "first");
+
+        let options = WalkOptions {
+            max_file_size: Some(3),
+            ..Default::default()
+        };
+        let head = GitTree::open(temp_dir.path().to_path_buf(), "HEAD").unwrap();
+        let index = GitTree::open_index(temp_dir.path().to_path_buf()).unwrap();
+        for git_tree in [head, index] {
+            let skipped: Vec<_> = git_tree
+                .files(&[], &options)
+                .unwrap()
+                .into_iter()
+                .map(|file| (file.path.file_name().map(PathBuf::from), file.skipped))
+                .collect();
+            assert_eq!(
+                skipped,
+                [
+                    (temp_dir.path().file_name().map(PathBuf::from), None),
+                    (Some(PathBuf::from("large.txt")), Some(SkipReason::TooLarge)),
+                    (Some(PathBuf::from("small.txt")), None),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_tree_skip_binary() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        std::fs::write(temp_dir.path().join("image.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        commit_all(&repository, "first");
+
+        let options = WalkOptions {
+            skip_binary: true,
+            ..Default::default()
+        };
+        let head = GitTree::open(temp_dir.path().to_path_buf(), "HEAD").unwrap();
+        let index = GitTree::open_index(temp_dir.path().to_path_buf()).unwrap();
+        for git_tree in [head, index] {
+            let skipped: Vec<_> = git_tree
+                .files(&[], &options)
+                .unwrap()
+                .into_iter()
+                .filter(|file| file.kind == FileKind::File)
+                .map(|file| (file.path.file_name().map(PathBuf::from), file.skipped))
+                .collect();
+            assert_eq!(
+                skipped,
+                [
+                    (Some(PathBuf::from("image.png")), Some(SkipReason::Binary)),
+                    (Some(PathBuf::from("main.rs")), None),
+                ]
+            );
+        }
+        let walked: Vec<_> = crate::get_files(temp_dir.path().to_path_buf(), &[], &options)
+            .map(|file| file.unwrap().skipped)
+            .collect();
+        assert_eq!(walked, [None, Some(SkipReason::Binary), None]);
+    }
+
+    #[test]
+    fn test_git_tree_index() {
+        let temp_dir = tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let file_a = temp_dir.path().join("file_a.txt");
+        let file_b = temp_dir.path().join("file_b.txt");
+
+        std::fs::write(&file_a, "committed").unwrap();
+        commit_all(&repository, "first");
+        std::fs::write(&file_a, "staged").unwrap();
+        std::fs::write(&file_b, "staged").unwrap();
+        let mut index = repository.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        std::fs::write(&file_a, "unstaged").unwrap();
+
+        let git_tree = GitTree::open_index(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            git_tree.files(&[], &WalkOptions::default()).unwrap().len(),
+            3
+        );
+        assert_eq!(git_tree.read_to_string(&file_a).unwrap(), "staged");
+        assert_eq!(git_tree.read_to_string(&file_b).unwrap(), "staged");
+    }
+}
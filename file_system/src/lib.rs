@@ -9,9 +9,23 @@
  */
 
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::SystemTime;
 
-use ignore::overrides::OverrideBuilder;
-use ignore::WalkBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
+
+use crate::binary::is_binary_file;
+use crate::symlink::{is_loop, symlink_filter};
+
+pub use crate::binary::looks_binary;
+pub use crate::git_blame::{FileBlame, GitBlame, LineChange};
+pub use crate::git_tree::{GitError, GitTree};
+
+mod binary;
+mod git_blame;
+mod git_tree;
+mod symlink;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FileKind {
@@ -19,11 +33,70 @@ pub enum FileKind {
     Directory,
 }
 
+/// An error met while walking a directory, such as a directory that could not be read. The walk
+/// carries on past it.
+#[derive(thiserror::Error, Debug)]
+pub enum WalkError {
+    #[error(transparent)]
+    Walk(#[from] ignore::Error),
+
+    #[error("{0} is not inside the directory being walked")]
+    OutsideRoot(PathBuf),
+}
+
+/// Why a walk listed a file without it being worth reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file is larger than `WalkOptions::max_file_size`
+    TooLarge,
+
+    /// The file looks binary rather than text, such as an image or a compiled artifact
+    Binary,
+}
+
 #[derive(Debug, Clone)]
 pub struct File {
     pub path: PathBuf,
     pub kind: FileKind,
     pub depth: isize,
+
+    /// Why callers should not read the file, or None if they can.
+    pub skipped: Option<SkipReason>,
+
+    /// The size of the file in bytes when it was walked, or 0 for a directory in a git revision.
+    pub size: u64,
+
+    /// When the file was last modified, or None if that is not known, as for files in a git
+    /// revision.
+    pub modified: Option<SystemTime>,
+}
+
+/// Options for walking a directory, beyond which directories to ignore.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Files larger than this many bytes are listed as skipped, so that callers do not read huge
+    /// generated or data files into memory. None means no limit.
+    pub max_file_size: Option<u64>,
+
+    /// Files that look binary are listed as skipped. This reads the start of every file, so is off
+    /// unless the files will be read anyway.
+    pub skip_binary: bool,
+
+    /// Walk into symlinked directories outside the root, each once, as if they were in the root.
+    /// Symlinks to directories are otherwise listed but not walked into.
+    pub follow_symlinks: bool,
+
+    /// Files and directories deeper than this below the root are left out, so 1 lists only what is
+    /// directly in the root. None means no limit.
+    pub max_depth: Option<usize>,
+
+    /// Walk hidden files and directories, whose names start with `.`, which are otherwise left out
+    /// unless they are in `include_hidden`.
+    pub hidden: bool,
+
+    /// Hidden paths relative to the root to walk even if `hidden` is not set, such as
+    /// `.github/workflows`. Everything inside them is walked too.
+    pub include_hidden: Vec<PathBuf>,
 }
 
 impl PartialEq for File {
@@ -36,7 +109,7 @@ impl Eq for File {}
 
 impl PartialOrd for File {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.path.partial_cmp(&other.path)
+        Some(self.cmp(other))
     }
 }
 
@@ -49,60 +122,256 @@ impl Ord for File {
 pub struct FileIterator {
     walker: ignore::Walk,
     path: PathBuf,
+    options: WalkOptions,
 }
 
 impl Iterator for FileIterator {
-    type Item = File;
+    type Item = Result<File, WalkError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match self.walker.next() {
-                Some(Ok(entry)) => {
-                    let subpath = entry.path();
-                    let relative_path = subpath.strip_prefix(&self.path).unwrap();
-                    let depth = relative_path.components().count() as isize;
-                    let file = File {
-                        path: subpath.to_path_buf(),
-                        kind: if subpath.is_dir() {
-                            FileKind::Directory
-                        } else {
-                            FileKind::File
-                        },
-                        depth,
-                    };
-                    return Some(file);
-                }
-                Some(Err(err)) => {
-                    eprintln!("Error: {}", err);
-                    std::process::exit(1);
+            match self.walker.next()? {
+                Err(err) if is_loop(&err) => continue,
+                Err(err) => return Some(Err(err.into())),
+                Ok(entry) => return Some(to_file(&entry, &self.path, &self.options)),
+            }
+        }
+    }
+}
+
+/// Files found by walking a directory on several threads, in no particular order.
+pub struct ParallelFileIterator {
+    receiver: Receiver<Result<File, WalkError>>,
+}
+
+impl Iterator for ParallelFileIterator {
+    type Item = Result<File, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The channel closes once every thread of the walk has finished.
+        self.receiver.recv().ok()
+    }
+}
+
+fn to_file(
+    entry: &ignore::DirEntry,
+    root: &Path,
+    options: &WalkOptions,
+) -> Result<File, WalkError> {
+    let subpath = entry.path();
+    let relative_path = subpath
+        .strip_prefix(root)
+        .map_err(|_| WalkError::OutsideRoot(subpath.to_path_buf()))?;
+    let depth = relative_path.components().count() as isize;
+    let kind = if subpath.is_dir() {
+        FileKind::Directory
+    } else {
+        FileKind::File
+    };
+    let metadata = entry.metadata().ok();
+    let size = metadata.as_ref().map_or(0, |metadata| metadata.len());
+    let skipped = match kind {
+        FileKind::File => skip_reason(entry, size, options),
+        FileKind::Directory => None,
+    };
+    Ok(File {
+        path: subpath.to_path_buf(),
+        kind,
+        depth,
+        skipped,
+        size,
+        modified: metadata.and_then(|metadata| metadata.modified().ok()),
+    })
+}
+
+fn skip_reason(entry: &ignore::DirEntry, size: u64, options: &WalkOptions) -> Option<SkipReason> {
+    let too_large = options
+        .max_file_size
+        .is_some_and(|max_file_size| size > max_file_size);
+    if too_large {
+        Some(SkipReason::TooLarge)
+    } else if options.skip_binary && is_binary_file(entry.path()) {
+        Some(SkipReason::Binary)
+    } else {
+        None
+    }
+}
+
+pub fn get_files(path: PathBuf, ignore_dirs: &[PathBuf], options: &WalkOptions) -> FileIterator {
+    let walker = walk_builder(&path, &path, ignore_dirs, options).build();
+    FileIterator {
+        walker,
+        path,
+        options: options.clone(),
+    }
+}
+
+/// Returns the same files as `get_files`, but walks the directory on a thread per CPU and yields
+/// files as they are found, in no particular order. This is much quicker for huge repositories, so
+/// callers that do not need the files sorted, or sort them themselves, should prefer it.
+pub fn get_files_parallel(
+    path: PathBuf,
+    ignore_dirs: &[PathBuf],
+    options: &WalkOptions,
+) -> ParallelFileIterator {
+    let walker = walk_builder(&path, &path, ignore_dirs, options).build_parallel();
+    let options = options.clone();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        walker.run(|| {
+            let sender = sender.clone();
+            let (path, options) = (&path, &options);
+            Box::new(move |entry| {
+                if entry.as_ref().is_err_and(is_loop) {
+                    return WalkState::Continue;
                 }
-                None => {
-                    return None;
+                let file = match entry {
+                    Ok(entry) => to_file(&entry, path, options),
+                    Err(err) => Err(err.into()),
+                };
+                // The receiver is gone if the caller stopped iterating, so there is no need to
+                // carry on.
+                match sender.send(file) {
+                    Ok(()) => WalkState::Continue,
+                    Err(_) => WalkState::Quit,
                 }
-            }
+            })
+        });
+    });
+    ParallelFileIterator { receiver }
+}
+
+/// Returns the immediate children of `directory`, which must be `root` or a directory beneath it.
+///
+/// This is the incremental counterpart to `get_files`: rather than walking the whole tree up
+/// front, callers can list one directory at a time as it is expanded. The same ignore rules apply,
+/// including `.gitignore` files in parent directories of `directory`, and depths are relative to
+/// `root` so they line up with the ones `get_files` would have returned. `directory` itself is not
+/// included in the result.
+pub fn get_children(
+    root: PathBuf,
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    options: &WalkOptions,
+) -> impl Iterator<Item = Result<File, WalkError>> {
+    let mut builder = walk_builder(&root, directory, ignore_dirs, options);
+    builder.max_depth(Some(1));
+    let walker = builder.build();
+    // The limit is on depth below `root` rather than below `directory`.
+    let max_depth = options.max_depth;
+    let directory = directory.to_path_buf();
+    FileIterator {
+        walker,
+        path: root,
+        options: options.clone(),
+    }
+    .filter(move |file| match file {
+        Ok(file) => {
+            file.path != directory
+                && max_depth.is_none_or(|max_depth| file.depth <= max_depth as isize)
         }
+        Err(_) => true,
+    })
+}
+
+/// Returns `path`, which must be under `root`, as `get_files` would list it, or None if the walk
+/// would leave it out, such as a file in an ignored directory or matched by a `.gitignore`. Only
+/// the directories leading to `path` are listed, so this is much quicker than walking all of
+/// `root`.
+pub fn get_file(
+    root: PathBuf,
+    path: &Path,
+    ignore_dirs: &[PathBuf],
+    options: &WalkOptions,
+) -> Option<File> {
+    let relative_path = path.strip_prefix(&root).ok()?;
+    let mut directory = root.clone();
+    let mut file = None;
+    for component in relative_path.components() {
+        let child = directory.join(component);
+        // Errors are for other entries in the directory, which do not matter here.
+        file = get_children(root.clone(), &directory, ignore_dirs, options)
+            .flatten()
+            .find(|file| file.path == child);
+        directory = file.as_ref()?.path.clone();
+    }
+    file
+}
+
+/// Files in `.gitignore` syntax that leave files out of digests without changing what git ignores,
+/// at any level of the directory tree.
+pub(crate) const IGNORE_FILE_NAME: &str = ".codedigestignore";
+
+/// Globs that leave `ignore_dirs`, and `.gitkeep` files, out of a walk of `root`. Each is matched
+/// like a line of a `.gitignore` file in `root`, so `target` leaves out a `target` directory at any
+/// depth.
+pub(crate) fn ignore_overrides(
+    root: &Path,
+    ignore_dirs: &[PathBuf],
+) -> Result<Override, ignore::Error> {
+    let mut override_builder = OverrideBuilder::new(root);
+    for ignore_dir in ignore_dirs {
+        override_builder.add(&format!("!{}", ignore_dir.to_string_lossy()))?;
     }
+    override_builder.add("!.gitkeep")?;
+    override_builder.build()
 }
 
-pub fn get_files(path: PathBuf, ignore_dirs: &[PathBuf]) -> FileIterator {
-    let mut builder = WalkBuilder::new(path.clone());
+/// Whether to walk an entry, and for a directory what is in it.
+type EntryFilter = Box<dyn Fn(&ignore::DirEntry) -> bool + Send + Sync>;
+
+fn walk_builder(
+    root: &Path,
+    directory: &Path,
+    ignore_dirs: &[PathBuf],
+    options: &WalkOptions,
+) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(directory);
     builder
         .git_ignore(true)
         .git_global(false)
         .git_exclude(false)
+        .ignore(true)
+        .add_custom_ignore_filename(IGNORE_FILE_NAME)
+        .max_depth(options.max_depth)
         .sort_by_file_path(|a, b| a.cmp(b));
 
-    let mut override_builder = OverrideBuilder::new(path.clone());
-    for ignore_dir in ignore_dirs {
-        override_builder
-            .add(&format!("!{}", ignore_dir.to_str().unwrap()))
-            .unwrap();
+    builder.overrides(ignore_overrides(root, ignore_dirs).unwrap());
+
+    // A walk takes only one filter, so the checks are combined. Hidden entries are checked first so
+    // that symlinks are only recorded as visited if they are walked.
+    let mut filters: Vec<EntryFilter> = vec![];
+    builder.hidden(!options.hidden && options.include_hidden.is_empty());
+    if !options.hidden && !options.include_hidden.is_empty() {
+        let root = root.to_path_buf();
+        let include_hidden = options.include_hidden.clone();
+        filters.push(Box::new(move |entry| {
+            entry
+                .path()
+                .strip_prefix(&root)
+                .map_or(true, |path| is_hidden_path_included(path, &include_hidden))
+        }));
+    }
+    if options.follow_symlinks {
+        builder.follow_links(true);
+        filters.push(Box::new(symlink_filter(root)));
+    }
+    if !filters.is_empty() {
+        builder.filter_entry(move |entry| filters.iter().all(|filter| filter(entry)));
     }
-    override_builder.add("!.gitkeep").unwrap();
-    builder.overrides(override_builder.build().unwrap());
+    builder
+}
 
-    let walker = builder.build();
-    FileIterator { walker, path }
+/// Whether `path`, relative to the root, is not hidden or is one of `include_hidden`, inside one,
+/// or a directory leading to one.
+pub(crate) fn is_hidden_path_included(path: &Path, include_hidden: &[PathBuf]) -> bool {
+    let hidden = path
+        .components()
+        .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+    !hidden
+        || include_hidden
+            .iter()
+            .any(|include| path.starts_with(include) || include.starts_with(path))
 }
 
 pub struct GlobPatternMatcher {
@@ -207,8 +476,12 @@ mod tests {
 
         let ignore_dirs = Vec::new();
 
-        let files = get_files(temp_dir.path().to_path_buf(), &ignore_dirs);
-        let files: Vec<_> = files.collect();
+        let files = get_files(
+            temp_dir.path().to_path_buf(),
+            &ignore_dirs,
+            &WalkOptions::default(),
+        );
+        let files: Vec<_> = files.map(Result::unwrap).collect();
 
         assert_eq!(files.len(), 6);
         assert_eq!(files[0].path, temp_dir.path().to_path_buf());
@@ -230,4 +503,406 @@ mod tests {
         assert_eq!(files[5].kind, FileKind::File);
         assert_eq!(files[5].depth, 2);
     }
+
+    #[test]
+    fn test_get_files_parallel() {
+        let temp_dir = tempdir().unwrap();
+        for directory in ["a", "a/nested", "b", "target"] {
+            std::fs::create_dir(temp_dir.path().join(directory)).unwrap();
+        }
+        for i in 0..20 {
+            for directory in ["a", "a/nested", "b", "target"] {
+                let file = temp_dir.path().join(directory).join(format!("{}.txt", i));
+                std::fs::File::create(file).unwrap();
+            }
+        }
+
+        let root = temp_dir.path().to_path_buf();
+        let ignore_dirs = vec![PathBuf::from("target")];
+        let mut files: Vec<_> =
+            get_files_parallel(root.clone(), &ignore_dirs, &WalkOptions::default())
+                .map(Result::unwrap)
+                .collect();
+        files.sort();
+        let expected: Vec<_> = get_files(root, &ignore_dirs, &WalkOptions::default())
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(files.len(), 64);
+        assert_eq!(files, expected);
+        for (file, expected) in files.iter().zip(&expected) {
+            assert_eq!(file.kind, expected.kind);
+            assert_eq!(file.depth, expected.depth);
+        }
+    }
+
+    #[test]
+    fn test_get_files_ignore_files() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for directory in ["fixtures", "src", "src/snapshots"] {
+            std::fs::create_dir(root.join(directory)).unwrap();
+        }
+        for file in [
+            "fixtures/input.json",
+            "src/lib.rs",
+            "src/generated.rs",
+            "src/snapshots/lib.snap",
+            "notes.txt",
+        ] {
+            std::fs::File::create(root.join(file)).unwrap();
+        }
+        std::fs::write(root.join(".ignore"), "fixtures/\n").unwrap();
+        std::fs::write(
+            root.join("src/.codedigestignore"),
+            "generated.rs\nsnapshots/\n",
+        )
+        .unwrap();
+
+        let paths = |files: Vec<File>| {
+            files
+                .into_iter()
+                .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(
+                get_files(root.clone(), &[], &WalkOptions::default())
+                    .map(Result::unwrap)
+                    .collect()
+            ),
+            ["", "notes.txt", "src", "src/lib.rs"].map(PathBuf::from)
+        );
+        // The ignore file in `src` applies when listing it on its own.
+        assert_eq!(
+            paths(
+                get_children(
+                    root.clone(),
+                    &root.join("src"),
+                    &[],
+                    &WalkOptions::default()
+                )
+                .map(Result::unwrap)
+                .collect()
+            ),
+            [PathBuf::from("src/lib.rs")]
+        );
+    }
+
+    #[test]
+    fn test_get_files_max_file_size() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("large.json"), "x".repeat(101)).unwrap();
+        std::fs::write(root.join("small.rs"), "x".repeat(100)).unwrap();
+
+        let options = WalkOptions {
+            max_file_size: Some(100),
+            ..Default::default()
+        };
+        let files: Vec<_> = get_files(root.clone(), &[], &options)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].skipped, None);
+        assert_eq!(files[1].path, root.join("large.json"));
+        assert_eq!(files[1].skipped, Some(SkipReason::TooLarge));
+        assert_eq!(files[1].size, 101);
+        assert_eq!(files[2].path, root.join("small.rs"));
+        assert_eq!(files[2].skipped, None);
+        assert_eq!(files[2].size, 100);
+        assert_eq!(
+            files[2].modified,
+            Some(
+                std::fs::metadata(root.join("small.rs"))
+                    .unwrap()
+                    .modified()
+                    .unwrap()
+            )
+        );
+
+        let files: Vec<_> = get_files(root, &[], &WalkOptions::default())
+            .map(Result::unwrap)
+            .collect();
+        assert!(files.iter().all(|file| file.skipped.is_none()));
+    }
+
+    #[test]
+    fn test_get_files_skip_binary() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("tool"), b"\x7fELF\x02\x01\x01\0\0").unwrap();
+
+        let options = WalkOptions {
+            skip_binary: true,
+            ..Default::default()
+        };
+        let skipped = get_files(root, &[], &options)
+            .map(Result::unwrap)
+            .map(|file| (file.path.file_name().unwrap().to_owned(), file.skipped))
+            .skip(1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped,
+            [
+                ("logo.png".into(), Some(SkipReason::Binary)),
+                ("main.rs".into(), None),
+                ("tool".into(), Some(SkipReason::Binary)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_files_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir().unwrap();
+        let outside_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let shared = outside_dir.path().join("shared");
+        std::fs::create_dir(root.join("app")).unwrap();
+        std::fs::create_dir(&shared).unwrap();
+        std::fs::File::create(root.join("app/main.rs")).unwrap();
+        std::fs::File::create(shared.join("lib.rs")).unwrap();
+        symlink(&shared, root.join("app/shared")).unwrap();
+        symlink(&shared, root.join("app/shared_again")).unwrap();
+        symlink(&root, root.join("app/root")).unwrap();
+        symlink(&shared, shared.join("loop")).unwrap();
+
+        let paths = |options: &WalkOptions| {
+            get_files(root.clone(), &[], options)
+                .map(Result::unwrap)
+                .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(&WalkOptions::default()),
+            [
+                "",
+                "app",
+                "app/main.rs",
+                "app/root",
+                "app/shared",
+                "app/shared_again"
+            ]
+            .map(PathBuf::from)
+        );
+        // The shared package is walked once, and the links back to the root and to the package
+        // itself are skipped.
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&options),
+            ["", "app", "app/main.rs", "app/shared", "app/shared/lib.rs"].map(PathBuf::from)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_files_walk_errors() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::File::create(root.join("main.rs")).unwrap();
+        symlink(root.join("missing"), root.join("dangling")).unwrap();
+
+        // The link that cannot be followed is reported, and the walk carries on past it.
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let (files, errors): (Vec<_>, Vec<_>) =
+            get_files(root.clone(), &[], &options).partition(Result::is_ok);
+        assert_eq!(
+            files
+                .into_iter()
+                .map(|file| file.unwrap().path)
+                .collect::<Vec<_>>(),
+            [root.clone(), root.join("main.rs")]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], Err(WalkError::Walk(_))));
+
+        let outside_dir = tempdir().unwrap();
+        std::fs::File::create(outside_dir.path().join("lib.rs")).unwrap();
+        let children: Vec<_> =
+            get_children(root, outside_dir.path(), &[], &WalkOptions::default()).collect();
+        assert!(!children.is_empty());
+        assert!(children
+            .iter()
+            .all(|child| matches!(child, Err(WalkError::OutsideRoot(_)))));
+    }
+
+    #[test]
+    fn test_get_files_max_depth() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("a/b/c")).unwrap();
+        for file in ["top.rs", "a/one.rs", "a/b/two.rs", "a/b/c/three.rs"] {
+            std::fs::File::create(root.join(file)).unwrap();
+        }
+
+        let options = WalkOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let paths = get_files(root.clone(), &[], &options)
+            .map(Result::unwrap)
+            .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["", "a", "a/b", "a/one.rs", "top.rs"].map(PathBuf::from)
+        );
+        assert_eq!(
+            get_children(root.clone(), &root.join("a"), &[], &options).count(),
+            2
+        );
+        assert_eq!(
+            get_children(root.clone(), &root.join("a/b"), &[], &options).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_get_files_hidden() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for directory in [
+            ".github/workflows",
+            ".github/ISSUE_TEMPLATE",
+            ".idea",
+            "src",
+        ] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        for file in [
+            ".github/workflows/ci.yml",
+            ".github/ISSUE_TEMPLATE/bug.md",
+            ".idea/workspace.xml",
+            ".env",
+            "src/lib.rs",
+        ] {
+            std::fs::File::create(root.join(file)).unwrap();
+        }
+
+        let paths = |options: &WalkOptions| {
+            get_files(root.clone(), &[], options)
+                .map(Result::unwrap)
+                .map(|file| file.path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(&WalkOptions::default()),
+            ["", "src", "src/lib.rs"].map(PathBuf::from)
+        );
+        let options = WalkOptions {
+            include_hidden: vec![PathBuf::from(".github/workflows")],
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&options),
+            [
+                "",
+                ".github",
+                ".github/workflows",
+                ".github/workflows/ci.yml",
+                "src",
+                "src/lib.rs"
+            ]
+            .map(PathBuf::from)
+        );
+        let options = WalkOptions {
+            hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(paths(&options).len(), 11);
+    }
+
+    #[test]
+    fn test_get_children() {
+        let temp_dir = tempdir().unwrap();
+        let dir_a = temp_dir.path().join("a");
+        let dir_b = temp_dir.path().join("b");
+        let file_a1 = dir_a.join("file_a1.txt");
+        let file_a2 = dir_a.join("file_a2.txt");
+        let file_b1 = dir_b.join("file_b1.txt");
+
+        std::fs::create_dir(&dir_a).unwrap();
+        std::fs::create_dir(&dir_b).unwrap();
+        std::fs::File::create(file_a1.clone()).unwrap();
+        std::fs::File::create(file_a2.clone()).unwrap();
+        std::fs::File::create(file_b1).unwrap();
+
+        let root = temp_dir.path().to_path_buf();
+
+        let top_level: Vec<_> = get_children(root.clone(), &root, &[], &WalkOptions::default())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(top_level.len(), 2);
+        assert_eq!(top_level[0].path, dir_a);
+        assert_eq!(top_level[0].kind, FileKind::Directory);
+        assert_eq!(top_level[0].depth, 1);
+        assert_eq!(top_level[1].path, dir_b);
+        assert_eq!(top_level[1].kind, FileKind::Directory);
+        assert_eq!(top_level[1].depth, 1);
+
+        let children_of_a: Vec<_> = get_children(root, &dir_a, &[], &WalkOptions::default())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(children_of_a.len(), 2);
+        assert_eq!(children_of_a[0].path, file_a1);
+        assert_eq!(children_of_a[0].kind, FileKind::File);
+        assert_eq!(children_of_a[0].depth, 2);
+        assert_eq!(children_of_a[1].path, file_a2);
+        assert_eq!(children_of_a[1].kind, FileKind::File);
+        assert_eq!(children_of_a[1].depth, 2);
+    }
+
+    #[test]
+    fn test_get_file() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::create_dir_all(root.join("generated")).unwrap();
+        std::fs::write(root.join(IGNORE_FILE_NAME), "generated/\n*.log\n").unwrap();
+        std::fs::write(root.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("target").join("build.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("generated").join("api.rs"), "fn api() {}\n").unwrap();
+        std::fs::write(root.join("debug.log"), "started\n").unwrap();
+        let ignore_dirs = [PathBuf::from("target")];
+        let options = WalkOptions {
+            max_file_size: Some(5),
+            ..Default::default()
+        };
+
+        let file = get_file(
+            root.clone(),
+            &root.join("src").join("main.rs"),
+            &ignore_dirs,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(file.kind, FileKind::File);
+        assert_eq!(file.depth, 2);
+        assert_eq!(file.skipped, Some(SkipReason::TooLarge));
+
+        for path in [
+            root.join("target").join("build.rs"),
+            root.join("generated").join("api.rs"),
+            root.join("debug.log"),
+            root.join("src").join("missing.rs"),
+            root.join("src").join("..").join("debug.log"),
+            temp_dir.path().parent().unwrap().to_path_buf(),
+        ] {
+            assert!(get_file(root.clone(), &path, &ignore_dirs, &options).is_none());
+        }
+    }
 }
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Monorepos often link shared packages into the packages that use them, so following symlinks to
+// directories keeps those in the digest. Each directory is walked once however many links lead to
+// it, tracked by its device and inode, and a link back to a directory it is inside is skipped, so
+// the walk always ends. A link to a directory inside the walked root is skipped too, since that
+// directory is walked where it is.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use ignore::DirEntry;
+
+/// Whether to walk an entry while walking `root` and following symlinks.
+pub(crate) fn symlink_filter(root: &Path) -> impl Fn(&DirEntry) -> bool + Send + Sync + 'static {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let visited = Mutex::new(HashSet::new());
+    move |entry| {
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_dir())
+        {
+            return true;
+        }
+        if entry.path_is_symlink()
+            && entry
+                .path()
+                .canonicalize()
+                .is_ok_and(|target| target.starts_with(&root))
+        {
+            return false;
+        }
+        match directory_id(entry.path()) {
+            Some(id) => visited
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(id),
+            None => true,
+        }
+    }
+}
+
+/// Whether `err` is about a symlink back to a directory it is inside, which is skipped rather than
+/// reported.
+pub(crate) fn is_loop(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_loop(err),
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn directory_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// Without inodes, a directory is identified by its path with every symlink resolved.
+#[cfg(not(unix))]
+fn directory_id(path: &Path) -> Option<std::path::PathBuf> {
+    path.canonicalize().ok()
+}
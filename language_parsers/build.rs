@@ -80,21 +80,31 @@ impl TreeSitterParser {
 
 fn main() {
     let parsers = vec![
+        TreeSitterParser {
+            name: "tree-sitter-bash",
+            src_dir: Path::new("../vendor/tree-sitter-bash/src"),
+            extra_files: vec!["scanner.c"],
+        },
+        TreeSitterParser {
+            name: "tree-sitter-cpp",
+            src_dir: Path::new("../vendor/tree-sitter-cpp/src"),
+            extra_files: vec!["scanner.c"],
+        },
         TreeSitterParser {
             name: "tree-sitter-go",
             src_dir: Path::new("../vendor/tree-sitter-go/src"),
             extra_files: vec![],
         },
-        TreeSitterParser {
-            name: "tree-sitter-hcl",
-            src_dir: Path::new("../vendor/tree-sitter-hcl/src"),
-            extra_files: vec!["scanner.cc"],
-        },
         TreeSitterParser {
             name: "tree-sitter-java",
             src_dir: Path::new("../vendor/tree-sitter-java/src"),
             extra_files: vec![],
         },
+        TreeSitterParser {
+            name: "tree-sitter-php",
+            src_dir: Path::new("../vendor/tree-sitter-php/php/src"),
+            extra_files: vec!["scanner.c"],
+        },
         TreeSitterParser {
             name: "tree-sitter-python",
             src_dir: Path::new("../vendor/tree-sitter-python/src"),
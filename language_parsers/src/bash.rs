@@ -0,0 +1,35 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+use crate::elide::NestedDefinitions;
+
+const NESTED_DEFINITIONS: NestedDefinitions = NestedDefinitions {
+    functions: &["function_definition"],
+    containers: &[],
+    line_comment: "#",
+};
+
+/// The source of a function definition with its body elided. Bodies written as a subshell or test
+/// command are elided as a `{ ... }` group too.
+pub(crate) fn elide_function_body(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    crate::elide::elide_function_bodies(node, source_code, indent, &NESTED_DEFINITIONS)
+}
+
+/// The text of `node` if it is the script's shebang line, otherwise an empty string.
+pub(crate) fn shebang(node: &ts::Node, source_code: &str) -> String {
+    let text = &source_code[node.byte_range()];
+    if node.start_byte() == 0 && text.starts_with("#!") {
+        text.trim().to_string()
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Calls are found by name alone: a call to `self.area()`, `shapes::area()`, or `area()` is a call to
+// `area`, whichever `area` that is. This is approximate, but needs no type information, and is
+// enough to tell which functions in a repository use which.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use tree_sitter as ts;
+
+use crate::item::{definition, ItemKind};
+
+/// Node kinds of calls, across languages. Bash calls functions as commands.
+pub(crate) const CALL_KINDS: &[&str] = &[
+    "call_expression",
+    "call",
+    "method_invocation",
+    "function_call_expression",
+    "member_call_expression",
+    "scoped_call_expression",
+    "command",
+];
+
+/// A function and the names of the functions it calls.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FunctionCalls {
+    pub name: String,
+
+    /// The first line of the function in the source code, starting from 1.
+    pub start_line: usize,
+
+    /// The last line of the function in the source code, starting from 1.
+    pub end_line: usize,
+
+    /// The names of the functions called, in source order with a call before the calls in its
+    /// arguments, without duplicates. Calls in a nested function belong to the nested function.
+    pub calls: Vec<String>,
+}
+
+pub(crate) fn function_calls(root_node: &ts::Node, source_code: &str) -> Vec<FunctionCalls> {
+    let mut result = vec![];
+    collect(
+        *root_node,
+        source_code,
+        None,
+        &mut HashSet::new(),
+        &mut result,
+    );
+    result
+}
+
+/// `function` is the index in `result` of the function that `node` is in, if any.
+fn collect(
+    node: ts::Node,
+    source_code: &str,
+    mut function: Option<usize>,
+    names: &mut HashSet<usize>,
+    result: &mut Vec<FunctionCalls>,
+) {
+    if let Some((_, name)) = definition(node, &[ItemKind::Function], names) {
+        result.push(FunctionCalls {
+            name: source_code[name.byte_range()].to_string(),
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            calls: vec![],
+        });
+        function = Some(result.len() - 1);
+    } else if let Some(function) = function {
+        let callee = CALL_KINDS
+            .contains(&node.kind())
+            .then(|| callee_name(node, source_code))
+            .flatten();
+        if let Some(callee) = callee {
+            let calls = &mut result[function].calls;
+            if !calls.contains(&callee) {
+                calls.push(callee);
+            }
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect(child, source_code, function, names, result);
+    }
+}
+
+/// The name of the function that a call calls, the last identifier in a path such as `a.b.c` or
+/// `a::b::c`.
+fn callee_name(call: ts::Node, source_code: &str) -> Option<String> {
+    let mut callee = call
+        .child_by_field_name("function")
+        .or_else(|| call.child_by_field_name("name"))?;
+    // Follow the part of a path that names the function rather than the object or module it is
+    // in, or the type arguments of a generic function such as `parse::<T>`.
+    loop {
+        let next = ["function", "name", "field", "attribute"]
+            .iter()
+            .find_map(|field| callee.child_by_field_name(field))
+            .or_else(|| callee.named_child(callee.named_child_count().checked_sub(1)?));
+        match next {
+            Some(next) => callee = next,
+            None => break,
+        }
+    }
+    let name = &source_code[callee.byte_range()];
+    (!name.is_empty()).then(|| name.to_string())
+}
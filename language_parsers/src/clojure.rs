@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Clojure is read as top-level forms. `ns` forms, protocols and multimethod declarations are kept
+// whole, and definitions are kept as their names, docstrings and argument vectors, with their
+// bodies elided. Other top-level forms, such as `(comment ...)`, are skipped.
+
+use crate::scan::{key_content, text, Spanned, Token};
+use crate::KeyContent;
+
+/// Characters that prefix a form, as in `'(a b)`, `#{1 2}`, `^:private` or `@state`.
+const PREFIXES: &[char] = &['\'', '`', '~', '@', '^', '#'];
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let tokens = read(source_code);
+    elements(&tokens)
+        .into_iter()
+        .filter_map(|form| {
+            let content = form_digest(form, source_code, indent)?;
+            Some(key_content(content, form, source_code))
+        })
+        .collect()
+}
+
+fn form_digest(form: &[Spanned], source_code: &str, indent: &str) -> Option<String> {
+    if form.len() < 2 || !form[0].is_punctuation('(') {
+        return None;
+    }
+    let inner = elements(&form[1..form.len() - 1]);
+    let head = text(inner.first()?, source_code);
+    // Skip metadata such as `^:private` or `^{:added "1.0"}` before the name.
+    let mut rest = inner[1..]
+        .iter()
+        .skip_while(|element| text(element, source_code).starts_with('^'));
+    let name = text(rest.next()?, source_code);
+    let rest = rest.collect::<Vec<_>>();
+
+    let docstring = match rest.first() {
+        Some(first) if matches!(first[0].token, Token::String) && rest.len() > 1 => {
+            Some(text(first, source_code))
+        }
+        _ => None,
+    };
+    let after_docstring = &rest[docstring.map_or(0, |_| 1)..];
+
+    match head {
+        "ns" | "defprotocol" | "defmulti" => Some(text(form, source_code).to_string()),
+        "def" | "defonce" => {
+            let mut parts = vec![format!("({} {}", head, name)];
+            parts.extend(docstring.map(str::to_string));
+            Some(format!("{} ...)", parts.join(" ")))
+        }
+        "defn" | "defn-" | "defmacro" => {
+            // An optional attribute map, then either one argument vector or one list per arity.
+            let mut signatures = after_docstring
+                .iter()
+                .skip_while(|element| element[0].is_punctuation('{'))
+                .peekable();
+            let single_arity = signatures
+                .peek()
+                .is_some_and(|element| element[0].is_punctuation('['));
+            let arities = if single_arity {
+                signatures
+                    .take(1)
+                    .map(|arguments| format!("{} ...", text(arguments, source_code)))
+                    .collect::<Vec<_>>()
+            } else {
+                signatures
+                    .map_while(|element| {
+                        if !element[0].is_punctuation('(') {
+                            return None;
+                        }
+                        let arguments = elements(&element[1..element.len() - 1]);
+                        let arguments = arguments.first()?;
+                        arguments[0]
+                            .is_punctuation('[')
+                            .then(|| format!("({} ...)", text(arguments, source_code)))
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let mut parts = vec![format!("({} {}", head, name)];
+            parts.extend(docstring.map(str::to_string));
+            parts.extend(arities);
+            let separator = if docstring.is_none() && single_arity {
+                " ".to_string()
+            } else {
+                format!("\n{}", indent)
+            };
+            Some(format!("{})", parts.join(&separator)))
+        }
+        "defrecord" | "deftype" => {
+            let fields = after_docstring
+                .first()
+                .map_or("", |fields| text(fields, source_code));
+            Some(format!("({} {} {} ...)", head, name, fields))
+        }
+        "defmethod" => {
+            let dispatch_value = text(rest.first()?, source_code);
+            let arguments = text(rest.get(1)?, source_code);
+            Some(format!(
+                "(defmethod {} {} {} ...)",
+                name, dispatch_value, arguments
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Split tokens into elements, each a single token or a bracketed form, together with any prefix
+/// such as `#` or `^`.
+fn elements(tokens: &[Spanned]) -> Vec<&[Spanned]> {
+    let mut elements = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match &spanned.token {
+            Token::Punctuation('(' | '[' | '{') => depth += 1,
+            Token::Punctuation(')' | ']' | '}') => depth -= 1,
+            Token::Word(word) if depth == 0 && word.chars().all(|c| PREFIXES.contains(&c)) => {
+                continue
+            }
+            _ => {}
+        }
+        if depth <= 0 {
+            elements.push(&tokens[start..=i]);
+            start = i + 1;
+            depth = 0;
+        }
+    }
+    elements
+}
+
+/// Read Clojure source code into brackets, strings and other atoms, skipping comments.
+fn read(source_code: &str) -> Vec<Spanned> {
+    let mut tokens = vec![];
+    let mut chars = source_code.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() || c == ',' => continue,
+            ';' => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                continue;
+            }
+            '(' | ')' | '[' | ']' | '{' | '}' => Token::Punctuation(c),
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                Token::String
+            }
+            _ => {
+                // A character literal such as `\(` includes the character after the backslash.
+                if c == '\\' {
+                    chars.next();
+                }
+                while chars
+                    .next_if(|&(_, c)| {
+                        !c.is_whitespace()
+                            && !matches!(c, ',' | ';' | '"' | '(' | ')' | '[' | ']' | '{' | '}')
+                    })
+                    .is_some()
+                {}
+                let end = chars.peek().map_or(source_code.len(), |&(end, _)| end);
+                Token::Word(source_code[start..end].to_string())
+            }
+        };
+        let end = chars.peek().map_or(source_code.len(), |&(end, _)| end);
+        tokens.push(Spanned {
+            token,
+            range: start..end,
+        });
+    }
+    tokens
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// CMake files are a sequence of commands, `name(arguments)`, so the scanner only has to match
+// parentheses. Commands that describe the project and its targets are kept whole, and custom
+// commands and targets are kept up to their first COMMAND, leaving out what they run.
+
+use crate::scan::{key_content, text, tokenize, Lexicon, Spanned, Token};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["#"],
+    block_comments: &[("#[[", "]]")],
+    quotes: &["\""],
+    word_characters: &[],
+};
+
+/// Commands kept whole. Command names are case-insensitive.
+const KEPT_COMMANDS: &[&str] = &[
+    "project",
+    "option",
+    "add_library",
+    "add_executable",
+    "target_link_libraries",
+];
+
+/// Commands kept up to their first COMMAND argument.
+const CUSTOM_COMMANDS: &[&str] = &["add_custom_command", "add_custom_target"];
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    let mut result = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let name = match &tokens[i].token {
+            Token::Word(name) if tokens.get(i + 1).is_some_and(|t| t.is_punctuation('(')) => {
+                name.to_lowercase()
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let end = closing_paren(&tokens, i + 1).unwrap_or(tokens.len() - 1);
+        let command = &tokens[i..=end];
+        if KEPT_COMMANDS.contains(&name.as_str()) {
+            result.push(key_content(
+                text(command, source_code).to_string(),
+                command,
+                source_code,
+            ));
+        } else if CUSTOM_COMMANDS.contains(&name.as_str()) {
+            result.push(key_content(
+                custom_command_outline(command, source_code),
+                command,
+                source_code,
+            ));
+        }
+        i = end + 1;
+    }
+    result
+}
+
+fn closing_paren(tokens: &[Spanned], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate().skip(open) {
+        if spanned.is_punctuation('(') {
+            depth += 1;
+        } else if spanned.is_punctuation(')') {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+fn custom_command_outline(command: &[Spanned], source_code: &str) -> String {
+    match command
+        .iter()
+        .position(|spanned| spanned.is_word("COMMAND"))
+    {
+        Some(first_command) => format!("{} ...)", text(&command[..first_command], source_code)),
+        None => text(command, source_code).to_string(),
+    }
+}
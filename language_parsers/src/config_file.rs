@@ -0,0 +1,298 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A parse config can be loaded from a TOML file at runtime, so that what gets captured for a
+// language can be adjusted without recompiling, for example:
+//
+//     indentation = 2
+//     doc_comments = true
+//     leading_comments = true
+//     granularity = "small_bodies"
+//     elision_threshold = 3
+//     elision_summary = true
+//     strip_comments = true
+//     signatures_only = true
+//     tests = "summarize"
+//     max_depth = 1
+//     queries = ["(trait_item) @capture"]
+//     injections = ['(macro_invocation (token_tree) @injection.content (#set! injection.language "sql"))']
+//
+//     [selectors]
+//     mod_item = "skip"
+//     type_item = "skip"
+//
+// Selectors map node kinds to an action: `select_only`, `capture_without_block`, `capture_all`,
+// `capture_nested`, or `skip` to remove the kind's selectors. An action can instead be a table such
+// as `{ action = "capture_all", priority = 1 }`, which is tried before selectors of lower priority
+// for the kind, and otherwise goes before the default selectors of the same priority. A table can
+// also limit the selector to items whose name matches a regular expression, as with
+// `name = "^Handle"`, or whose fields have given values, as with `fields = { type = "int" }`. A list
+// of tables adds several selectors for the kind in order, so that it can start with `skip`. Queries
+// are tree-sitter queries as for `ParseConfig::add_query`, and injections are tree-sitter injection
+// queries as for `ParseConfig::add_injection`. `granularity` is `signatures`, `types`,
+// `small_bodies` or `full`, as for `ParseConfig::set_granularity`, and the settings below refine it.
+// `doc_comments` keeps the doc comments of captured items, `leading_comments` keeps any comments
+// directly above them, and function bodies spanning at most `elision_threshold` lines are kept
+// whole. `elision_summary` notes how many lines and calls each elided body held, `strip_comments`
+// removes comments from captured items, and `signatures_only` reduces them to their doc comments
+// and signatures. `tests` is `keep`, `summarize` or `skip`, as for `ParseConfig::set_test_code`.
+// `max_depth` stops the selectors at that depth in the syntax tree, so 1 keeps only top-level
+// items. The file adds to the language's default config unless it sets `defaults = false`.
+//
+// Languages parsed by a scanner, such as SQL and YAML, have no syntax tree for selectors and
+// queries to match, and only support `defaults`, `indentation` and `granularity = "full"`, so any
+// other setting is an error rather than silently ignored.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::{
+    default_parse_config_for_language, Granularity, Indentation, Language, ParseConfig, ParseError,
+    ParseResult, Selector, SelectorAction, TestCode,
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default = "default_defaults")]
+    defaults: bool,
+
+    doc_comments: Option<bool>,
+
+    elision_summary: Option<bool>,
+
+    elision_threshold: Option<usize>,
+
+    granularity: Option<GranularitySetting>,
+
+    indentation: Option<IndentationSetting>,
+
+    #[serde(default)]
+    injections: Vec<String>,
+
+    leading_comments: Option<bool>,
+
+    max_depth: Option<usize>,
+
+    #[serde(default)]
+    queries: Vec<String>,
+
+    #[serde(default)]
+    selectors: BTreeMap<String, SelectorSetting>,
+
+    signatures_only: Option<bool>,
+
+    strip_comments: Option<bool>,
+
+    tests: Option<TestsSetting>,
+}
+
+fn default_defaults() -> bool {
+    true
+}
+
+/// A number of spaces, or `"tabs"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IndentationSetting {
+    Spaces(usize),
+    Tabs(Tabs),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Tabs {
+    Tabs,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GranularitySetting {
+    Signatures,
+    Types,
+    SmallBodies,
+    Full,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TestsSetting {
+    Keep,
+    Summarize,
+    Skip,
+}
+
+/// An action, a table with an action and when it applies, or a list of tables.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SelectorSetting {
+    Action(ActionSetting),
+    Table(SelectorTable),
+    List(Vec<SelectorTable>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SelectorTable {
+    action: ActionSetting,
+
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+
+    name: Option<String>,
+
+    #[serde(default)]
+    priority: i32,
+}
+
+impl From<ActionSetting> for SelectorTable {
+    fn from(action: ActionSetting) -> Self {
+        SelectorTable {
+            action,
+            fields: BTreeMap::new(),
+            name: None,
+            priority: 0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ActionSetting {
+    SelectOnly,
+    CaptureWithoutBlock,
+    CaptureAll,
+    CaptureNested,
+    Skip,
+}
+
+/// Build a parse config for `language` from the contents of a TOML config file.
+pub fn parse_config_from_toml(language: Language, source: &str) -> ParseResult<ParseConfig> {
+    let file: ConfigFile = ::toml::from_str(source)?;
+    let indentation = file
+        .indentation
+        .as_ref()
+        .map(|indentation| match indentation {
+            IndentationSetting::Spaces(indent_size) => Indentation::Spaces(*indent_size),
+            IndentationSetting::Tabs(Tabs::Tabs) => Indentation::Tabs,
+        });
+
+    let mut config = if file.defaults {
+        let mut config = default_parse_config_for_language(language);
+        if let Some(indentation) = indentation {
+            config.set_indentation(indentation);
+        }
+        config
+    } else {
+        ParseConfig::new(language, indentation.unwrap_or_default())
+    };
+    if config.is_scanned() {
+        if let Some(setting) = unsupported_by_scanner(&file) {
+            return Err(ParseError::ScannerUnsupported(setting));
+        }
+    }
+    if let Some(granularity) = file.granularity {
+        config.set_granularity(match granularity {
+            GranularitySetting::Signatures => Granularity::Signatures,
+            GranularitySetting::Types => Granularity::Types,
+            GranularitySetting::SmallBodies => Granularity::SmallBodies,
+            GranularitySetting::Full => Granularity::Full,
+        });
+    }
+    if let Some(include_doc_comments) = file.doc_comments {
+        config.set_include_doc_comments(include_doc_comments);
+    }
+    if let Some(include_leading_comments) = file.leading_comments {
+        config.set_include_leading_comments(include_leading_comments);
+    }
+    if let Some(lines) = file.elision_threshold {
+        config.set_elision_threshold(lines);
+    }
+    if let Some(elision_summary) = file.elision_summary {
+        config.set_elision_summary(elision_summary);
+    }
+    if let Some(strip_comments) = file.strip_comments {
+        config.set_strip_comments(strip_comments);
+    }
+    if let Some(signatures_only) = file.signatures_only {
+        config.set_signatures_only(signatures_only);
+    }
+    if let Some(tests) = file.tests {
+        config.set_test_code(match tests {
+            TestsSetting::Keep => TestCode::Keep,
+            TestsSetting::Summarize => TestCode::Summarize,
+            TestsSetting::Skip => TestCode::Skip,
+        });
+    }
+    if let Some(depth) = file.max_depth {
+        config.set_max_depth(Some(depth));
+    }
+    for (node_kind, setting) in file.selectors {
+        let tables = match setting {
+            SelectorSetting::Action(action) => vec![action.into()],
+            SelectorSetting::Table(table) => vec![table],
+            SelectorSetting::List(tables) => tables,
+        };
+        for table in tables {
+            let action = match table.action {
+                ActionSetting::SelectOnly => SelectorAction::SelectOnly,
+                ActionSetting::CaptureWithoutBlock => SelectorAction::CaptureWithoutBlock,
+                ActionSetting::CaptureAll => SelectorAction::CaptureAll,
+                ActionSetting::CaptureNested => SelectorAction::CaptureNested,
+                ActionSetting::Skip => {
+                    config.remove_selector(&node_kind);
+                    continue;
+                }
+            };
+            let mut selector = Selector::new(&node_kind, action).with_priority(table.priority);
+            for (field, value) in table.fields {
+                selector = selector.with_field_equals(field, value);
+            }
+            if let Some(pattern) = &table.name {
+                selector = selector.with_name_matching(pattern)?;
+            }
+            config.add_selector(selector);
+        }
+    }
+    for query in &file.queries {
+        config.add_query(query)?;
+    }
+    for injection in &file.injections {
+        config.add_injection(injection)?;
+    }
+    Ok(config)
+}
+
+/// The first setting in `file` that languages parsed by a scanner do not support, if any.
+fn unsupported_by_scanner(file: &ConfigFile) -> Option<&'static str> {
+    [
+        ("doc_comments", file.doc_comments.is_some()),
+        ("elision_summary", file.elision_summary.is_some()),
+        ("elision_threshold", file.elision_threshold.is_some()),
+        (
+            "granularity",
+            file.granularity
+                .as_ref()
+                .is_some_and(|granularity| !matches!(granularity, GranularitySetting::Full)),
+        ),
+        ("injections", !file.injections.is_empty()),
+        ("leading_comments", file.leading_comments.is_some()),
+        ("max_depth", file.max_depth.is_some()),
+        ("queries", !file.queries.is_empty()),
+        ("selectors", !file.selectors.is_empty()),
+        ("signatures_only", file.signatures_only.is_some()),
+        ("strip_comments", file.strip_comments.is_some()),
+        ("tests", file.tests.is_some()),
+    ]
+    .into_iter()
+    .find(|&(_, set)| set)
+    .map(|(setting, _)| setting)
+}
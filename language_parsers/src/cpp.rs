@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+use crate::elide::NestedDefinitions;
+
+const NESTED_DEFINITIONS: NestedDefinitions = NestedDefinitions {
+    functions: &["function_definition"],
+    containers: &[
+        "namespace_definition",
+        "declaration_list",
+        "class_specifier",
+        "struct_specifier",
+        "union_specifier",
+        "field_declaration_list",
+        "template_declaration",
+        "linkage_specification",
+    ],
+    line_comment: "//",
+};
+
+/// Node kinds whose definition at the top level is followed by a separate `;` node.
+const SPECIFIER_KINDS: &[&str] = &[
+    "class_specifier",
+    "struct_specifier",
+    "union_specifier",
+    "enum_specifier",
+];
+
+/// The source of a top-level C++ definition with every function body in it elided, keeping
+/// enclosing namespaces, classes and templates as they are.
+pub(crate) fn elide_function_bodies(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    let mut result =
+        crate::elide::elide_function_bodies(node, source_code, indent, &NESTED_DEFINITIONS);
+    if SPECIFIER_KINDS.contains(&node.kind())
+        && node.next_sibling().is_some_and(|next| next.kind() == ";")
+    {
+        result.push(';');
+    }
+    result
+}
@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Dart is split into declarations by a scanner that matches braces and parentheses. Imports and
+// typedefs are kept whole, classes, mixins and extensions are kept with their fields and the
+// signatures of their methods, and top-level functions are kept as their signatures. Enums are kept
+// whole.
+
+use crate::scan::{first_word, key_content, text, tokenize, Lexicon, Spanned};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    quotes: &["'''", "\"\"\"", "'", "\""],
+    word_characters: &['$'],
+};
+
+const DIRECTIVES: &[&str] = &["import", "export", "part", "library", "typedef"];
+
+const CLASS_KEYWORDS: &[&str] = &["class", "mixin", "extension"];
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    split_declarations(&tokens)
+        .into_iter()
+        .filter_map(|declaration| {
+            let content = if first_word(declaration).is_some_and(|word| DIRECTIVES.contains(&word))
+                || declares(declaration, &["enum"])
+            {
+                text(declaration, source_code).to_string()
+            } else if declares(declaration, CLASS_KEYWORDS) {
+                class_outline(declaration, source_code, indent)?
+            } else if is_function(declaration) {
+                member_outline(declaration, source_code, indent, "")
+            } else {
+                return None;
+            };
+            Some(key_content(content, declaration, source_code))
+        })
+        .collect()
+}
+
+/// Split tokens into declarations, each ending in either a `;` or the `}` closing its body.
+/// Braces in an initializer, such as a map literal after `=`, or inside parentheses, such as named
+/// parameters or closures, do not end a declaration.
+fn split_declarations(tokens: &[Spanned]) -> Vec<&[Spanned]> {
+    let mut declarations = vec![];
+    let mut start = 0;
+    let mut braces = 0;
+    let mut parens = 0;
+    let mut seen_parens = false;
+    let mut initializer = false;
+    for (i, spanned) in tokens.iter().enumerate() {
+        let mut end_of_declaration = false;
+        if spanned.is_punctuation('(') || spanned.is_punctuation('[') {
+            parens += 1;
+            seen_parens |= braces == 0;
+        } else if spanned.is_punctuation(')') || spanned.is_punctuation(']') {
+            parens -= 1;
+        } else if parens == 0 {
+            if spanned.is_punctuation('=') && braces == 0 && !seen_parens {
+                initializer = true;
+            } else if spanned.is_punctuation('{') {
+                braces += 1;
+            } else if spanned.is_punctuation('}') {
+                braces -= 1;
+                end_of_declaration = braces <= 0 && !initializer;
+            } else if spanned.is_punctuation(';') {
+                end_of_declaration = braces <= 0;
+            }
+        }
+        if end_of_declaration {
+            // A `;` on its own, e.g. after a closing brace, is an empty declaration.
+            if i > start || !spanned.is_punctuation(';') {
+                declarations.push(&tokens[start..=i]);
+            }
+            start = i + 1;
+            (braces, parens, seen_parens, initializer) = (0, 0, false, false);
+        }
+    }
+    declarations
+}
+
+/// The index of the `{` opening the body of a declaration, outside any parentheses.
+fn body_start(declaration: &[Spanned]) -> Option<usize> {
+    let mut parens = 0;
+    for (i, spanned) in declaration.iter().enumerate() {
+        if spanned.is_punctuation('(') || spanned.is_punctuation('[') {
+            parens += 1;
+        } else if spanned.is_punctuation(')') || spanned.is_punctuation(']') {
+            parens -= 1;
+        } else if parens == 0 && spanned.is_punctuation('{') {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// The index of the `=>` starting an expression body, outside any parentheses.
+fn arrow_start(declaration: &[Spanned]) -> Option<usize> {
+    let mut parens = 0;
+    for (i, pair) in declaration.windows(2).enumerate() {
+        if pair[0].is_punctuation('(') || pair[0].is_punctuation('[') {
+            parens += 1;
+        } else if pair[0].is_punctuation(')') || pair[0].is_punctuation(']') {
+            parens -= 1;
+        } else if parens == 0
+            && pair[0].is_punctuation('=')
+            && pair[1].is_punctuation('>')
+            && pair[0].range.end == pair[1].range.start
+        {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Whether a declaration is introduced by one of `keywords`, past any modifiers or annotations.
+fn declares(declaration: &[Spanned], keywords: &[&str]) -> bool {
+    let header = &declaration[..body_start(declaration).unwrap_or(declaration.len())];
+    header
+        .iter()
+        .take_while(|spanned| !spanned.is_punctuation('('))
+        .any(|spanned| keywords.iter().any(|keyword| spanned.is_word(keyword)))
+}
+
+fn is_function(declaration: &[Spanned]) -> bool {
+    arrow_start(declaration).is_some()
+        || declaration
+            .last()
+            .is_some_and(|last| last.is_punctuation('}'))
+}
+
+/// A class, mixin or extension with its fields and the signatures of its methods.
+fn class_outline(declaration: &[Spanned], source_code: &str, indent: &str) -> Option<String> {
+    let open = body_start(declaration)?;
+    let mut result = text(&declaration[..=open], source_code).to_string();
+    result.push('\n');
+    for member in split_declarations(&declaration[open + 1..declaration.len() - 1]) {
+        result.push_str(indent);
+        result.push_str(&member_outline(member, source_code, indent, indent));
+        result.push('\n');
+    }
+    result.push('}');
+    Some(result)
+}
+
+/// A field as it is, or a function or method with its body elided. `nesting` is the indentation
+/// of the declaration itself.
+fn member_outline(member: &[Spanned], source_code: &str, indent: &str, nesting: &str) -> String {
+    if let Some(arrow) = arrow_start(member) {
+        return format!("{} => ...;", text(&member[..arrow], source_code));
+    }
+    match body_start(member) {
+        Some(open) if member.last().is_some_and(|last| last.is_punctuation('}')) => format!(
+            "{} {{\n{}{}// ...\n{}}}",
+            text(&member[..open], source_code),
+            nesting,
+            indent,
+            nesting
+        ),
+        _ => text(member, source_code).to_string(),
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Doc comments are usually the comments directly above a definition, such as `///` in Rust or
+// `/** */` in Java, which tree-sitter parses as siblings of the definition rather than part of it.
+// Python documents definitions with a docstring, the first statement of the body, instead.
+
+use tree_sitter as ts;
+
+use crate::{ItemKind, KeyContent, Language};
+
+/// Nodes that may come between a doc comment and the definition it documents.
+const BETWEEN_KINDS: &[&str] = &["attribute_item"];
+
+/// How doc comments start in a language.
+#[derive(Clone, Copy)]
+pub(crate) struct DocComments {
+    /// Comments that document the definition below them.
+    pub outer: &'static [&'static str],
+
+    /// Comments that document the file they are in, such as `//!` in Rust.
+    pub inner: &'static [&'static str],
+}
+
+pub(crate) fn doc_comments(language: Language) -> DocComments {
+    let outer: &[&str] = match language {
+        Language::Rust => &["///", "/**"],
+        Language::Cpp => &["///", "//!", "/**", "/*!"],
+        // godoc takes any comment directly above a declaration.
+        Language::Go => &["//", "/*"],
+        Language::Java | Language::Php => &["/**"],
+        Language::Bash => &["#"],
+        _ => &[],
+    };
+    let inner: &[&str] = match language {
+        Language::Rust => &["//!", "/*!"],
+        _ => &[],
+    };
+    DocComments { outer, inner }
+}
+
+/// Each run of top-level comments that document the file, with no blank lines within the run.
+pub(crate) fn file_doc_comments(
+    root_node: &ts::Node,
+    source_code: &str,
+    inner: &[&str],
+) -> Vec<KeyContent> {
+    let mut result: Vec<KeyContent> = vec![];
+    let mut cursor = root_node.walk();
+    for child in root_node.children(&mut cursor) {
+        let text = source_code[child.byte_range()].trim();
+        if !child.kind().ends_with("comment")
+            || !inner.iter().any(|prefix| text.starts_with(prefix))
+        {
+            continue;
+        }
+        let start_line = child.start_position().row + 1;
+        match result.last_mut() {
+            Some(last) if last.end_line + 1 >= start_line => {
+                last.content.push('\n');
+                last.content.push_str(text);
+                last.end_line = child.end_position().row + 1;
+                last.byte_range.end = child.end_byte();
+            }
+            _ => result.push(KeyContent {
+                content: text.to_string(),
+                start_line,
+                end_line: child.end_position().row + 1,
+                kind: ItemKind::Comment,
+                name: None,
+                byte_range: child.byte_range(),
+            }),
+        }
+    }
+    result
+}
+
+/// The first of the doc comments directly above `node`, with no blank line between them, if any.
+pub(crate) fn first_doc_comment<'a>(
+    node: &ts::Node<'a>,
+    source_code: &str,
+    prefixes: &[&str],
+) -> Option<ts::Node<'a>> {
+    let mut first = None;
+    let mut next = *node;
+    while let Some(previous) = next.prev_sibling() {
+        if previous.end_position().row + 1 < next.start_position().row {
+            break;
+        }
+        if previous.kind().ends_with("comment") {
+            let text = &source_code[previous.byte_range()];
+            // A comment at the end of a line documents that line instead.
+            let is_trailing = previous
+                .prev_sibling()
+                .is_some_and(|before| before.end_position().row == previous.start_position().row);
+            if is_trailing || !prefixes.iter().any(|prefix| text.starts_with(prefix)) {
+                break;
+            }
+            first = Some(previous);
+        } else if !BETWEEN_KINDS.contains(&previous.kind()) {
+            break;
+        }
+        next = previous;
+    }
+    first
+}
+
+/// The docstring at the start of a body, a statement that is only a string.
+pub(crate) fn docstring<'a>(body: &ts::Node<'a>) -> Option<ts::Node<'a>> {
+    let statement = body.named_child(0)?;
+    let is_docstring = statement.kind() == "expression_statement"
+        && statement.named_child_count() == 1
+        && statement.named_child(0)?.kind() == "string";
+    is_docstring.then_some(statement)
+}
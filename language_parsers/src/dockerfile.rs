@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A Dockerfile is a sequence of instructions, one per line unless a line ends in the escape
+// character. Instructions that describe the image, its base, arguments, environment, ports and
+// entrypoint, are kept whole, and RUN instructions that chain several commands are collapsed to
+// their first command.
+
+use crate::KeyContent;
+
+/// Instructions kept whole. Instructions are case-insensitive.
+const KEPT_INSTRUCTIONS: &[&str] = &["FROM", "ARG", "ENV", "EXPOSE", "ENTRYPOINT", "CMD"];
+
+struct Instruction {
+    keyword: String,
+    arguments: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    instructions(source_code)
+        .into_iter()
+        .filter_map(|instruction| {
+            let content = if KEPT_INSTRUCTIONS.contains(&instruction.keyword.as_str()) {
+                format!("{} {}", instruction.keyword, instruction.arguments)
+            } else if instruction.keyword == "RUN" {
+                run_summary(&instruction.arguments)
+            } else {
+                return None;
+            };
+            Some(KeyContent {
+                content,
+                start_line: instruction.start_line,
+                end_line: instruction.end_line,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Split a Dockerfile into instructions, joining continuation lines and skipping comments and
+/// heredoc bodies.
+fn instructions(source_code: &str) -> Vec<Instruction> {
+    let escape = escape_character(source_code);
+    let mut result = vec![];
+    let mut lines = source_code.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut end_line = i + 1;
+        let mut parts = vec![];
+        let mut part = line;
+        loop {
+            match part.strip_suffix(escape) {
+                Some(rest) => parts.push(rest.trim()),
+                None => {
+                    parts.push(part);
+                    break;
+                }
+            }
+            // Comments between continuation lines are removed before the instruction is parsed.
+            match lines.find(|(_, line)| !line.trim_start().starts_with('#')) {
+                Some((j, line)) => {
+                    end_line = j + 1;
+                    part = line.trim();
+                }
+                None => break,
+            }
+        }
+        let joined = parts
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (keyword, arguments) = joined
+            .split_once(char::is_whitespace)
+            .unwrap_or((&joined, ""));
+
+        if let Some(delimiter) = heredoc_delimiter(arguments) {
+            for (j, line) in lines.by_ref() {
+                end_line = j + 1;
+                if line.trim() == delimiter {
+                    break;
+                }
+            }
+        }
+
+        result.push(Instruction {
+            keyword: keyword.to_uppercase(),
+            arguments: arguments.trim().to_string(),
+            start_line: i + 1,
+            end_line,
+        });
+    }
+    result
+}
+
+/// The escape character, `\` unless changed by an `# escape=` parser directive at the top of the
+/// file.
+fn escape_character(source_code: &str) -> char {
+    for line in source_code.lines() {
+        let directive = match line.trim().strip_prefix('#') {
+            Some(directive) => directive,
+            None => break,
+        };
+        match directive.split_once('=') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("escape") => {
+                return value.trim().chars().next().unwrap_or('\\');
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    '\\'
+}
+
+/// The delimiter of a heredoc such as `<<EOF` or `<<-"EOF"` in an instruction's arguments.
+fn heredoc_delimiter(arguments: &str) -> Option<&str> {
+    let (_, rest) = arguments.split_once("<<")?;
+    let rest = rest.strip_prefix('-').unwrap_or(rest);
+    let delimiter = rest
+        .split_whitespace()
+        .next()?
+        .trim_matches(|c| c == '"' || c == '\'');
+    (!delimiter.is_empty()).then_some(delimiter)
+}
+
+/// A RUN instruction, collapsed to its first command if it chains several.
+fn run_summary(arguments: &str) -> String {
+    let commands = arguments.split("&&").map(str::trim).collect::<Vec<_>>();
+    if commands.len() == 1 {
+        return format!("RUN {}", arguments);
+    }
+    format!("RUN {} && ... # {} commands", commands[0], commands.len())
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Definitions in many languages nest: namespaces hold classes, classes hold methods, templates and
+// decorators wrap any of these. Rather than select each level separately, which would lose the
+// enclosing definitions, a definition can be captured whole with the body of every function inside
+// it elided.
+
+use std::ops::Range;
+
+use tree_sitter as ts;
+
+use crate::calls::CALL_KINDS;
+use crate::scan::line_indentation;
+
+/// The node kinds that matter when eliding function bodies in a language.
+pub(crate) struct NestedDefinitions {
+    /// Function-like nodes whose `body` field is elided.
+    pub functions: &'static [&'static str],
+
+    /// Nodes that are searched for nested functions. Any other node is kept as it is.
+    pub containers: &'static [&'static str],
+
+    /// How a line comment starts, for the comment that replaces a body.
+    pub line_comment: &'static str,
+}
+
+/// The source of `node` with the body of every function in it replaced by a `...` comment, keeping
+/// the enclosing definitions as they are.
+pub(crate) fn elide_function_bodies(
+    node: &ts::Node,
+    source_code: &str,
+    indent: &str,
+    nested: &NestedDefinitions,
+) -> String {
+    let mut bodies = vec![];
+    collect_function_bodies(*node, nested, &mut bodies);
+    elide_ranges(
+        node,
+        source_code,
+        indent,
+        nested.line_comment,
+        &bodies,
+        false,
+    )
+}
+
+/// The source of `node` with each of `ranges`, which are sorted and do not overlap, replaced by a
+/// block holding a `...` comment, followed by a summary of what was elided if `summarize` is set.
+pub(crate) fn elide_ranges(
+    node: &ts::Node,
+    source_code: &str,
+    indent: &str,
+    line_comment: &str,
+    ranges: &[Range<usize>],
+    summarize: bool,
+) -> String {
+    let mut result = String::with_capacity(node.byte_range().len());
+    let mut position = node.start_byte();
+    for range in ranges {
+        let line_indent = line_indentation(source_code, range.start);
+        let summary = node
+            .descendant_for_byte_range(range.start, range.end)
+            .filter(|_| summarize)
+            .map_or(String::new(), |elided| {
+                format!(" {}", elided_summary(&elided))
+            });
+        result.push_str(&source_code[position..range.start]);
+        result.push_str(&format!(
+            "{{\n{}{}{} ...{}\n{}}}",
+            line_indent, indent, line_comment, summary, line_indent
+        ));
+        position = range.end;
+    }
+    result.push_str(&source_code[position..node.end_byte()]);
+    result.trim().to_string()
+}
+
+/// How much an elided node held, such as `(37 lines, 5 calls elided)`. Lines are those its named
+/// children span, so the brackets around a block are not counted, and calls are counted at any
+/// depth.
+pub(crate) fn elided_summary(node: &ts::Node) -> String {
+    let mut lines = 0;
+    let mut next_row = 0;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let start_row = child.start_position().row.max(next_row);
+        let end_row = child.end_position().row + 1;
+        lines += end_row.saturating_sub(start_row);
+        next_row = next_row.max(end_row);
+    }
+    let calls = count_calls(*node);
+    format!(
+        "({} {}, {} {} elided)",
+        lines,
+        if lines == 1 { "line" } else { "lines" },
+        calls,
+        if calls == 1 { "call" } else { "calls" }
+    )
+}
+
+fn count_calls(node: ts::Node) -> usize {
+    let mut cursor = node.walk();
+    let nested = node
+        .named_children(&mut cursor)
+        .map(count_calls)
+        .sum::<usize>();
+    nested + usize::from(CALL_KINDS.contains(&node.kind()))
+}
+
+fn collect_function_bodies(
+    node: ts::Node,
+    nested: &NestedDefinitions,
+    bodies: &mut Vec<Range<usize>>,
+) {
+    if nested.functions.contains(&node.kind()) {
+        if let Some(body) = node.child_by_field_name("body") {
+            bodies.push(body.byte_range());
+        }
+        return;
+    }
+    if !nested.containers.contains(&node.kind()) {
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_function_bodies(child, nested, bodies);
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Files in a language with no grammar or scanner are digested by heuristics, so that they still
+// contribute something: the first lines of the file, which often say what it is, followed by each
+// later line that looks like a definition because it starts with a keyword such as `def`,
+// `function`, or `class`, possibly after modifiers such as `export` or `public`.
+
+use crate::KeyContent;
+
+/// The number of lines kept from the start of the file.
+const HEAD_LINES: usize = 10;
+
+/// Words that start a definition in many languages.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "def",
+    "defn",
+    "class",
+    "function",
+    "func",
+    "fn",
+    "fun",
+    "sub",
+    "proc",
+    "module",
+    "interface",
+    "struct",
+    "trait",
+];
+
+/// Words that may come before a definition keyword.
+const MODIFIERS: &[&str] = &[
+    "export",
+    "default",
+    "pub",
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "static",
+    "abstract",
+    "final",
+    "async",
+    "local",
+];
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let lines = source_code.lines().collect::<Vec<_>>();
+    let mut result = vec![];
+
+    let head = lines
+        .iter()
+        .take(HEAD_LINES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let head = head.trim_end();
+    if !head.trim().is_empty() {
+        result.push(KeyContent {
+            content: head.to_string(),
+            start_line: 1,
+            end_line: head.lines().count(),
+            ..Default::default()
+        });
+    }
+
+    for (i, line) in lines.iter().enumerate().skip(HEAD_LINES) {
+        if is_definition(line) {
+            result.push(KeyContent {
+                content: line.trim_end().to_string(),
+                start_line: i + 1,
+                end_line: i + 1,
+                ..Default::default()
+            });
+        }
+    }
+    result
+}
+
+fn is_definition(line: &str) -> bool {
+    let keyword = line
+        .split_whitespace()
+        .find(|word| !MODIFIERS.contains(word))
+        .unwrap_or_default();
+    let keyword = keyword.trim_start_matches('(');
+    DEFINITION_KEYWORDS.contains(&keyword)
+}
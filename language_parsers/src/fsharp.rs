@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// F# is split into declarations by indentation, since a declaration's body is indented more deeply
+// than the line it starts on. `open` statements and namespace and module declarations are kept, type
+// definitions are kept with member bodies elided, and `let` bindings are kept as their signatures
+// unless they fit on one line. Nested modules are kept with the digest of their contents.
+
+use crate::KeyContent;
+
+/// Keywords starting a binding inside a type whose body is elided if it spans several lines.
+const MEMBER_KEYWORDS: &[&str] = &[
+    "member", "override", "default", "static", "abstract", "new", "let", "do", "val",
+];
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+
+    /// The line without indentation or comments.
+    text: &'a str,
+}
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let source_code = strip_comments(source_code);
+    let lines = source_code
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Line {
+            number: i + 1,
+            indent: line.len() - line.trim_start().len(),
+            text: line.trim(),
+        })
+        .collect::<Vec<_>>();
+    digest_declarations(&lines, indent)
+}
+
+fn digest_declarations(lines: &[Line], indent: &str) -> Vec<KeyContent> {
+    let mut result = vec![];
+    let mut attributes = vec![];
+    // The keyword `and` continues, either `type` or `let`.
+    let mut continued = "";
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let end = block_end(lines, i);
+        let block = &lines[i..end];
+        i = end;
+
+        if line.text.starts_with("[<") {
+            attributes.push(line);
+            continue;
+        }
+        let mut keyword = line.text.split_whitespace().next().unwrap_or_default();
+        if keyword == "and" {
+            keyword = continued;
+        }
+        let content = match keyword {
+            "open" | "namespace" => line.text.to_string(),
+            "module" if line.text.ends_with('=') => {
+                let mut content = line.text.to_string();
+                for declaration in digest_declarations(&block[1..], indent) {
+                    content.push_str("\n\n");
+                    content.push_str(&reindent(&declaration.content, indent));
+                }
+                content
+            }
+            "module" => line.text.to_string(),
+            "type" | "exception" => type_outline(block, line.indent),
+            "let" => binding_outline(block),
+            _ => {
+                attributes.clear();
+                continued = "";
+                continue;
+            }
+        };
+        continued = keyword;
+
+        let first = attributes.first().copied().unwrap_or(line);
+        let mut with_attributes = attributes
+            .iter()
+            .map(|attribute| format!("{}\n", attribute.text))
+            .collect::<String>();
+        with_attributes.push_str(&content);
+        result.push(KeyContent {
+            content: with_attributes,
+            start_line: first.number,
+            end_line: block[block.len() - 1].number,
+            ..Default::default()
+        });
+        attributes.clear();
+    }
+    result
+}
+
+/// A type definition with the bodies of its members elided.
+fn type_outline(block: &[Line], base_indent: usize) -> String {
+    let mut result = vec![];
+    let mut i = 0;
+    while i < block.len() {
+        let line = &block[i];
+        let end = block_end(block, i);
+        let keyword = line.text.split_whitespace().next().unwrap_or_default();
+        let indentation = " ".repeat(line.indent.saturating_sub(base_indent));
+        if i > 0 && MEMBER_KEYWORDS.contains(&keyword) {
+            result.push(format!(
+                "{}{}",
+                indentation,
+                binding_outline(&block[i..end])
+            ));
+            i = end;
+        } else {
+            result.push(format!("{}{}", indentation, line.text));
+            i += 1;
+        }
+    }
+    result.join("\n")
+}
+
+/// A binding kept whole if it fits on one line, or as its signature with the body elided.
+fn binding_outline(block: &[Line]) -> String {
+    let first = block[0].text;
+    if block.len() == 1 {
+        return first.to_string();
+    }
+    match binding_equals(first) {
+        Some(equals) => format!("{} = ...", first[..equals].trim_end()),
+        None => first.to_string(),
+    }
+}
+
+/// The position of the `=` that separates a binding's signature from its body, the first one that
+/// is not part of an operator such as `<=` or `<>`, or inside brackets.
+fn binding_equals(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut brackets = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '(' | '[' | '{' => brackets += 1,
+            ')' | ']' | '}' => brackets -= 1,
+            '=' if brackets == 0 => {
+                let before = i.checked_sub(1).map(|j| bytes[j]);
+                let after = bytes.get(i + 1).copied();
+                let operator = |b: Option<u8>| b.is_some_and(|b| b"=<>!:|&+-*/%".contains(&b));
+                if !operator(before) && !operator(after) {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The index past the lines nested under `lines[start]`, which are indented more deeply.
+fn block_end(lines: &[Line], start: usize) -> usize {
+    let indent = lines[start].indent;
+    lines[start + 1..]
+        .iter()
+        .position(|line| line.indent <= indent)
+        .map_or(lines.len(), |offset| start + 1 + offset)
+}
+
+/// Indent every line of a nested declaration's content.
+fn reindent(content: &str, indent: &str) -> String {
+    content
+        .lines()
+        .map(|line| format!("{}{}", indent, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The source code with `//` and `(* *)` comments replaced by spaces, keeping line breaks so that
+/// line numbers are unchanged. Comment markers inside strings are left alone.
+fn strip_comments(source_code: &str) -> String {
+    let mut result = String::with_capacity(source_code.len());
+    let mut chars = source_code.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                result.push(c);
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    result.push(c);
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            // `(*)` is the multiplication operator rather than a comment.
+            '(' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&')') {
+                    result.push_str("(*");
+                    continue;
+                }
+                result.push_str("  ");
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    result.push(if c == '\n' { '\n' } else { ' ' });
+                    if previous == '*' && c == ')' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// HCL files, such as Terraform configurations, are a sequence of blocks, `type "label" { body }`,
+// and attributes, `name = value`, so the scanner only has to match brackets to find where each
+// ends.
+// The top-level blocks that describe infrastructure are kept with their attributes, and the
+// contents of nested blocks spanning several lines are elided.
+
+use crate::scan::{
+    key_content, line_indentation, newline_between, text, tokenize, Lexicon, Spanned, Token,
+};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["#", "//"],
+    block_comments: &[("/*", "*/")],
+    quotes: &["\""],
+    word_characters: &['-'],
+};
+
+/// Top-level Terraform blocks that are captured. Others, such as `locals` and `data`, are skipped.
+const CAPTURED_BLOCKS: &[&str] = &["resource", "module", "variable", "output", "provider"];
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    split_items(&tokens, source_code)
+        .into_iter()
+        .filter(|item| block_open(item).is_some() && is_captured(item))
+        .map(|block| {
+            key_content(
+                block_outline(block, source_code, indent),
+                block,
+                source_code,
+            )
+        })
+        .collect()
+}
+
+fn is_captured(block: &[Spanned]) -> bool {
+    CAPTURED_BLOCKS
+        .iter()
+        .any(|block_type| block[0].is_word(block_type))
+}
+
+/// Split tokens into items, each a block or an attribute, ending at a line break outside any
+/// brackets.
+fn split_items<'a>(tokens: &'a [Spanned], source_code: &str) -> Vec<&'a [Spanned]> {
+    let mut items = vec![];
+    let mut start = 0;
+    let mut brackets = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match spanned.token {
+            Token::Punctuation('(' | '[' | '{') => brackets += 1,
+            Token::Punctuation(')' | ']' | '}') => brackets -= 1,
+            _ => {}
+        }
+        let end_of_line = match tokens.get(i + 1) {
+            Some(next) => newline_between(source_code, spanned, next),
+            None => true,
+        };
+        if end_of_line && brackets <= 0 {
+            items.push(&tokens[start..=i]);
+            start = i + 1;
+            brackets = 0;
+        }
+    }
+    items
+}
+
+/// The index of the `{` that opens the body of `item` if it is a block, a type followed by any
+/// labels and then its body, and ends with the `}` that closes it.
+fn block_open(item: &[Spanned]) -> Option<usize> {
+    if !matches!(item.first()?.token, Token::Word(_)) || !item.last()?.is_punctuation('}') {
+        return None;
+    }
+    let open = item
+        .iter()
+        .position(|spanned| spanned.is_punctuation('{'))?;
+    item[1..open]
+        .iter()
+        .all(|label| matches!(label.token, Token::Word(_) | Token::String))
+        .then_some(open)
+}
+
+/// The source of a top-level block, such as `resource "aws_instance" "web" { ... }`, with its
+/// attributes and with the contents of nested blocks spanning several lines elided.
+fn block_outline(block: &[Spanned], source_code: &str, indent: &str) -> String {
+    let open = block_open(block).unwrap_or_default();
+    let body = &block[open + 1..block.len() - 1];
+
+    let mut result = String::with_capacity(text(block, source_code).len());
+    let mut position = block[0].range.start;
+    for nested_block in split_items(body, source_code) {
+        let nested_open = match block_open(nested_block) {
+            Some(nested_open) => &nested_block[nested_open],
+            None => continue,
+        };
+        let nested_close = &nested_block[nested_block.len() - 1];
+        if !newline_between(source_code, nested_open, nested_close) {
+            continue;
+        }
+        let line_indent = line_indentation(source_code, nested_block[0].range.start);
+        result.push_str(&source_code[position..nested_open.range.start]);
+        result.push_str(&format!(
+            "{{\n{}{}# ...\n{}}}",
+            line_indent, indent, line_indent
+        ));
+        position = nested_close.range.end;
+    }
+    result.push_str(&source_code[position..block[block.len() - 1].range.end]);
+    result.trim().to_string()
+}
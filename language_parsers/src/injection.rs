@@ -0,0 +1,196 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Source code often embeds another language, such as SQL in a string passed to `execute`. Injection
+// queries find it, in the syntax of the `injections.scm` files that ship with grammars: the node
+// captured as `@injection.content` holds the embedded code, and its language is set by the pattern
+// with `(#set! injection.language "sql")` or is the text of a node captured as
+// `@injection.language`. The embedded code is digested with the default config for its language,
+// or kept whole if that captures nothing, and emitted as a nested fenced section. Nested sections
+// are fenced with `~~~` so that they do not close the ``` fence around a file's digest.
+
+use tree_sitter as ts;
+
+use crate::registry::language_named;
+use crate::{
+    default_parse_config_for_language, ItemKind, KeyContent, Language, ParseError, ParseResult,
+};
+
+/// SQL passed to the usual database calls, such as `cursor.execute("SELECT ...")`.
+pub(crate) const PYTHON_SQL: &str = r#"
+(call
+  function: (attribute attribute: (identifier) @_method)
+  arguments: (argument_list . (string) @injection.content)
+  (#match? @_method "^(execute|executemany|executescript|read_sql|read_sql_query)$")
+  (#set! injection.language "sql"))
+"#;
+
+pub(crate) const GO_SQL: &str = r#"
+(call_expression
+  function: (selector_expression field: (field_identifier) @_method)
+  arguments: (argument_list
+    . [(raw_string_literal) (interpreted_string_literal)] @injection.content)
+  (#match? @_method "^(Query|QueryRow|Exec|Prepare)$")
+  (#set! injection.language "sql"))
+(call_expression
+  function: (selector_expression field: (field_identifier) @_method)
+  arguments: (argument_list
+    . (_) . [(raw_string_literal) (interpreted_string_literal)] @injection.content)
+  (#match? @_method "^(Query|QueryRow|Exec|Prepare)Context$")
+  (#set! injection.language "sql"))
+"#;
+
+pub(crate) const JAVA_SQL: &str = r#"
+(method_invocation
+  name: (identifier) @_method
+  arguments: (argument_list . (string_literal) @injection.content)
+  (#match? @_method "^(prepareStatement|prepareCall|executeQuery|executeUpdate|addBatch)$")
+  (#set! injection.language "sql"))
+"#;
+
+pub(crate) const RUST_SQL: &str = r#"
+(call_expression
+  function: (field_expression field: (field_identifier) @_method)
+  arguments: (arguments . [(string_literal) (raw_string_literal)] @injection.content)
+  (#match? @_method "^(execute|execute_batch|query|query_row|prepare)$")
+  (#set! injection.language "sql"))
+(call_expression
+  function: (scoped_identifier name: (identifier) @_function)
+  arguments: (arguments . [(string_literal) (raw_string_literal)] @injection.content)
+  (#match? @_function "^(query|query_as|query_scalar)$")
+  (#set! injection.language "sql"))
+"#;
+
+pub(crate) struct InjectionQuery {
+    query: ts::Query,
+    content: u32,
+    language: Option<u32>,
+}
+
+impl InjectionQuery {
+    pub(crate) fn new(language: ts::Language, source: &str) -> ParseResult<InjectionQuery> {
+        let query = ts::Query::new(language, source)?;
+        let content = query
+            .capture_index_for_name("injection.content")
+            .ok_or(ParseError::InjectionWithoutContent)?;
+        let language = query.capture_index_for_name("injection.language");
+        Ok(InjectionQuery {
+            query,
+            content,
+            language,
+        })
+    }
+
+    /// The embedded code in source order, each digested as a nested section. Code in a language
+    /// that is not supported is skipped.
+    pub(crate) fn key_contents(&self, root_node: ts::Node, source_code: &str) -> Vec<KeyContent> {
+        let mut result = vec![];
+        let mut cursor = ts::QueryCursor::new();
+        for query_match in cursor.matches(&self.query, root_node, source_code.as_bytes()) {
+            let name = query_match
+                .captures
+                .iter()
+                .find(|capture| Some(capture.index) == self.language)
+                .map(|capture| &source_code[capture.node.byte_range()])
+                .or_else(|| self.language_setting(query_match.pattern_index));
+            let language = match name.and_then(language_named) {
+                Some(language) => language,
+                None => continue,
+            };
+            let name = name.unwrap_or_default();
+            for capture in query_match.captures {
+                if capture.index != self.content {
+                    continue;
+                }
+                let embedded = dedent(strip_delimiters(&source_code[capture.node.byte_range()]));
+                if embedded.is_empty() {
+                    continue;
+                }
+                let content = nested_section(name, &digest(language, embedded));
+                result.push(KeyContent {
+                    kind: ItemKind::Other,
+                    ..KeyContent::new(content, &capture.node, source_code)
+                });
+            }
+        }
+        result
+    }
+
+    fn language_setting(&self, pattern_index: usize) -> Option<&str> {
+        self.query
+            .property_settings(pattern_index)
+            .iter()
+            .find(|property| &*property.key == "injection.language")
+            .and_then(|property| property.value.as_deref())
+    }
+}
+
+/// `embedded` code digested with the default config for `language`, or all of it if that captures
+/// nothing.
+pub(crate) fn digest(language: Language, embedded: String) -> String {
+    let config = default_parse_config_for_language(language);
+    match crate::parse(&embedded, &config) {
+        Ok(key_contents) if !key_contents.is_empty() => key_contents
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => embedded,
+    }
+}
+
+/// `content` fenced as a section in `language`, to nest inside the fence around a file's digest.
+pub(crate) fn nested_section(language: &str, content: &str) -> String {
+    format!("~~~{}\n{}\n~~~", language, content)
+}
+
+/// The contents of a string literal, without any prefix such as `r#` or `f` and the quotes around
+/// it. Text that is not a quoted literal is returned as it is.
+fn strip_delimiters(text: &str) -> &str {
+    let open = match text.find(['"', '\'', '`']) {
+        Some(open) => open,
+        None => return text,
+    };
+    let prefix = &text[..open];
+    if !prefix.chars().all(|c| c.is_ascii_alphabetic() || c == '#') {
+        return text;
+    }
+    let hashes = prefix.matches('#').count();
+    let quote = text[open..].chars().next().unwrap_or_default();
+    let quotes = match text[open..].chars().take_while(|&c| c == quote).count() {
+        count if count >= 3 && text.len() >= open + 6 + hashes => 3,
+        _ => 1,
+    };
+    let start = open + quotes;
+    let end = text.len().saturating_sub(quotes + hashes).max(start);
+    &text[start..end]
+}
+
+/// `text` without the indentation its lines share, ignoring blank lines and the first line, which
+/// usually follows the opening quote.
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => line.trim_start(),
+            _ => line.get(indent..).unwrap_or_else(|| line.trim_start()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
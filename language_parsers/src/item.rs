@@ -0,0 +1,188 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use serde::Serialize;
+use tree_sitter as ts;
+
+/// Words in the node kinds of type definitions, as in `struct_item` or `class_declaration`.
+const TYPE_KINDS: &[&str] = &[
+    "struct",
+    "enum",
+    "union",
+    "class",
+    "interface",
+    "trait",
+    "record",
+    "type",
+];
+
+/// What kind of item was captured, the same across languages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Import,
+    Module,
+    Type,
+    Function,
+    Constant,
+    Variable,
+    Macro,
+    Comment,
+    #[default]
+    Other,
+}
+
+/// The kind of item that a captured tree-sitter node is.
+pub(crate) fn item_kind(node: &ts::Node) -> ItemKind {
+    match node.kind() {
+        // A decorated or templated definition is the kind of the definition it wraps.
+        "decorated_definition" | "template_declaration" => {
+            let definition = node
+                .child_by_field_name("definition")
+                .or_else(|| node.named_child(node.named_child_count().saturating_sub(1)));
+            match definition {
+                Some(definition) if definition.id() != node.id() => item_kind(&definition),
+                _ => ItemKind::Other,
+            }
+        }
+        "use_declaration"
+        | "import_declaration"
+        | "import_statement"
+        | "import_from_statement"
+        | "future_import_statement"
+        | "preproc_include"
+        | "namespace_use_declaration"
+        | "using_declaration" => ItemKind::Import,
+        "mod_item" | "namespace_definition" | "linkage_specification" | "package_declaration" => {
+            ItemKind::Module
+        }
+        "const_item" | "static_item" | "const_declaration" => ItemKind::Constant,
+        // Variants are part of the enum they are in rather than types of their own.
+        "enum_variant" | "enum_constant" | "enumerator" => ItemKind::Other,
+        "macro_definition" => ItemKind::Macro,
+        "variable_assignment" | "declaration_command" | "declaration" | "expression_statement" => {
+            ItemKind::Variable
+        }
+        kind if kind.contains("comment") => ItemKind::Comment,
+        kind if kind.contains("function")
+            || kind.contains("method")
+            || kind.contains("constructor") =>
+        {
+            ItemKind::Function
+        }
+        kind if TYPE_KINDS.iter().any(|type_kind| kind.contains(type_kind)) => ItemKind::Type,
+        _ => ItemKind::Other,
+    }
+}
+
+/// The name of a captured tree-sitter node, such as a function's name, if it has one.
+pub(crate) fn item_name(node: &ts::Node, source_code: &str) -> Option<String> {
+    name_node(*node).map(|name| source_code[name.byte_range()].to_string())
+}
+
+fn name_node(node: ts::Node) -> Option<ts::Node> {
+    if node.kind().ends_with("identifier") {
+        return Some(node);
+    }
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(name);
+    }
+    // Decorated definitions, C++ declarators, and assignments hold the name further down, as do Go
+    // type declarations in their type spec.
+    for field in ["definition", "declarator", "left"] {
+        if let Some(child) = node.child_by_field_name(field) {
+            return name_node(child);
+        }
+    }
+    let first = node.named_child(0)?;
+    if matches!(first.kind(), "type_spec" | "assignment") {
+        return name_node(first);
+    }
+    None
+}
+
+/// A named definition in source code, such as a function or a type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: ItemKind,
+
+    /// The first line of the definition in the source code, starting from 1.
+    pub start_line: usize,
+
+    /// The last line of the definition in the source code, starting from 1.
+    pub end_line: usize,
+
+    pub byte_range: Range<usize>,
+
+    /// The number of symbols that this symbol is inside, such as 1 for a method in a class.
+    pub depth: usize,
+}
+
+/// Kinds of items that are definitions, and so symbols.
+const SYMBOL_KINDS: &[ItemKind] = &[
+    ItemKind::Module,
+    ItemKind::Type,
+    ItemKind::Function,
+    ItemKind::Constant,
+    ItemKind::Macro,
+];
+
+/// Every named definition under `root_node`, in source order.
+pub(crate) fn symbols(root_node: &ts::Node, source_code: &str) -> Vec<Symbol> {
+    let mut result = vec![];
+    collect_symbols(*root_node, source_code, 0, &mut HashSet::new(), &mut result);
+    result
+}
+
+/// The kind and name of `node` if it is a named definition of one of `kinds`, and its name is not
+/// in `names`, the ids of the names of the definitions found so far. A definition such as a
+/// decorated definition or a C++ function declarator shares its name with the node around it, and
+/// only the outermost counts.
+pub(crate) fn definition<'a>(
+    node: ts::Node<'a>,
+    kinds: &[ItemKind],
+    names: &mut HashSet<usize>,
+) -> Option<(ItemKind, ts::Node<'a>)> {
+    let kind = item_kind(&node);
+    if !kinds.contains(&kind) || node.kind().ends_with("identifier") {
+        return None;
+    }
+    let name = name_node(node)?;
+    names.insert(name.id()).then_some((kind, name))
+}
+
+fn collect_symbols(
+    node: ts::Node,
+    source_code: &str,
+    depth: usize,
+    names: &mut HashSet<usize>,
+    result: &mut Vec<Symbol>,
+) {
+    let mut child_depth = depth;
+    if let Some((kind, name)) = definition(node, SYMBOL_KINDS, names) {
+        result.push(Symbol {
+            name: source_code[name.byte_range()].to_string(),
+            kind,
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            byte_range: node.byte_range(),
+            depth,
+        });
+        child_depth += 1;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_symbols(child, source_code, child_depth, names, result);
+    }
+}
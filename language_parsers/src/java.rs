@@ -0,0 +1,40 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+use crate::elide::NestedDefinitions;
+
+const NESTED_DEFINITIONS: NestedDefinitions = NestedDefinitions {
+    functions: &[
+        "method_declaration",
+        "constructor_declaration",
+        "compact_constructor_declaration",
+    ],
+    containers: &[
+        "class_declaration",
+        "class_body",
+        "interface_declaration",
+        "interface_body",
+        "enum_declaration",
+        "enum_body",
+        "enum_body_declarations",
+        "record_declaration",
+        "annotation_type_declaration",
+        "annotation_type_body",
+    ],
+    line_comment: "//",
+};
+
+/// The source of a top-level Java type declaration with every method and constructor body in it
+/// elided, keeping annotations, fields, nested types and interface method signatures as they are.
+pub(crate) fn elide_method_bodies(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    crate::elide::elide_function_bodies(node, source_code, indent, &NESTED_DEFINITIONS)
+}
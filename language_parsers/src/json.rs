@@ -0,0 +1,247 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// JSON is digested as an outline of its key structure. Each top-level key is kept with its value
+// outlined: nested objects keep their keys down to a limited depth, arrays are shown as their length
+// and, if they hold objects or arrays, the outline of their first element, and long strings are
+// truncated. Comments and trailing commas are accepted, as in tsconfig.json.
+
+use std::ops::Range;
+
+use crate::scan::line_number;
+use crate::KeyContent;
+
+/// Objects nested within top-level values more deeply than this are shown as their number of keys.
+const MAX_DEPTH: usize = 3;
+
+/// Strings longer than this many characters are truncated.
+const MAX_STRING_LENGTH: usize = 40;
+
+enum Value<'a> {
+    Object(Vec<Member<'a>>),
+    Array(Vec<Value<'a>>),
+
+    /// A string, number, boolean or null, as written in the source code.
+    Scalar(&'a str),
+}
+
+struct Member<'a> {
+    /// The key, including its quotes.
+    key: &'a str,
+    value: Value<'a>,
+    range: Range<usize>,
+}
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let mut parser = Parser {
+        source_code,
+        position: 0,
+    };
+    parser.skip_whitespace();
+    let start = parser.position;
+    let value = match parser.value() {
+        Some(value) => value,
+        None => return vec![],
+    };
+
+    match value {
+        Value::Object(members) => members
+            .iter()
+            .map(|member| {
+                let mut content = format!("{}: ", member.key);
+                outline(&member.value, indent, 0, &mut content);
+                KeyContent {
+                    content,
+                    start_line: line_number(source_code, member.range.start),
+                    end_line: line_number(source_code, member.range.end),
+                    ..Default::default()
+                }
+            })
+            .collect(),
+        value => {
+            let mut content = String::new();
+            outline(&value, indent, 0, &mut content);
+            vec![KeyContent {
+                content,
+                start_line: line_number(source_code, start),
+                end_line: line_number(source_code, parser.position),
+                ..Default::default()
+            }]
+        }
+    }
+}
+
+fn outline(value: &Value, indent: &str, depth: usize, result: &mut String) {
+    match value {
+        Value::Scalar(scalar) => result.push_str(&truncate(scalar)),
+        Value::Object(members) if members.is_empty() => result.push_str("{}"),
+        Value::Object(members) if depth >= MAX_DEPTH => {
+            result.push_str(&format!("{{{}}}", count(members.len(), "key")))
+        }
+        Value::Object(members) => {
+            result.push_str("{\n");
+            for member in members {
+                result.push_str(&indent.repeat(depth + 1));
+                result.push_str(member.key);
+                result.push_str(": ");
+                outline(&member.value, indent, depth + 1, result);
+                result.push_str(",\n");
+            }
+            result.push_str(&indent.repeat(depth));
+            result.push('}');
+        }
+        Value::Array(elements) => match elements.first() {
+            None => result.push_str("[]"),
+            Some(Value::Scalar(_)) => {
+                result.push_str(&format!("[{}]", count(elements.len(), "item")))
+            }
+            Some(first) => {
+                result.push_str("[\n");
+                result.push_str(&indent.repeat(depth + 1));
+                outline(first, indent, depth + 1, result);
+                result.push_str(",\n");
+                result.push_str(&indent.repeat(depth + 1));
+                result.push_str(&format!("... {}\n", count(elements.len(), "item")));
+                result.push_str(&indent.repeat(depth));
+                result.push(']');
+            }
+        },
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn truncate(scalar: &str) -> String {
+    match scalar.char_indices().nth(MAX_STRING_LENGTH) {
+        Some((end, _)) if scalar.starts_with('"') => format!("{}...\"", &scalar[..end]),
+        _ => scalar.to_string(),
+    }
+}
+
+struct Parser<'a> {
+    source_code: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source_code[self.position..]
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                self.position += comment.find("*/").map_or(trimmed.len(), |end| end + 4);
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Skip whitespace, then a `c` if it is next.
+    fn skip(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn value(&mut self) -> Option<Value<'a>> {
+        self.skip_whitespace();
+        match self.rest().chars().next()? {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => self.string().map(Value::Scalar),
+            _ => {
+                let rest = self.rest();
+                let length = rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}'))
+                    .unwrap_or(rest.len());
+                if length == 0 {
+                    return None;
+                }
+                self.position += length;
+                Some(Value::Scalar(&rest[..length]))
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Value<'a>> {
+        self.position += 1;
+        let mut members = vec![];
+        loop {
+            if self.skip('}') {
+                return Some(Value::Object(members));
+            }
+            let start = self.position;
+            let key = self.string()?;
+            if !self.skip(':') {
+                return None;
+            }
+            let value = self.value()?;
+            members.push(Member {
+                key,
+                value,
+                range: start..self.position,
+            });
+            if !self.skip(',') && !self.rest().starts_with('}') {
+                return None;
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Value<'a>> {
+        self.position += 1;
+        let mut elements = vec![];
+        loop {
+            if self.skip(']') {
+                return Some(Value::Array(elements));
+            }
+            elements.push(self.value()?);
+            if !self.skip(',') && !self.rest().starts_with(']') {
+                return None;
+            }
+        }
+    }
+
+    /// A string, including its quotes.
+    fn string(&mut self) -> Option<&'a str> {
+        let rest = self.rest();
+        if !rest.starts_with('"') {
+            return None;
+        }
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                self.position += i + 1;
+                return Some(&rest[..=i]);
+            }
+        }
+        None
+    }
+}
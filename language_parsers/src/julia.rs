@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Julia is split into top-level items by a scanner that matches block keywords with their `end`.
+// `using`, `import` and `export` statements and type definitions are kept whole, functions and
+// macros are kept as their signatures, and modules are kept with the digest of their contents.
+
+use crate::scan::{key_content, newline_between, text, tokenize, Lexicon, Spanned, Token};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["#"],
+    block_comments: &[("#=", "=#")],
+    quotes: &["\"\"\"", "\""],
+    word_characters: &['!', '.'],
+};
+
+/// Keywords that open a block closed by `end`. `abstract type` and `primitive type` also open a
+/// block, but `type` alone does not.
+const BLOCK_KEYWORDS: &[&str] = &[
+    "function",
+    "macro",
+    "struct",
+    "module",
+    "baremodule",
+    "begin",
+    "let",
+    "quote",
+    "if",
+    "for",
+    "while",
+    "try",
+    "do",
+];
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    digest_items(&tokens, source_code, indent)
+        .into_iter()
+        .map(|(content, item)| key_content(content, item, source_code))
+        .collect()
+}
+
+fn digest_items<'a>(
+    tokens: &'a [Spanned],
+    source_code: &str,
+    indent: &str,
+) -> Vec<(String, &'a [Spanned])> {
+    split_items(tokens, source_code)
+        .into_iter()
+        .filter_map(|item| {
+            let content = match keyword(item)? {
+                "using" | "import" | "export" | "struct" | "mutable" | "abstract" | "primitive" => {
+                    text(item, source_code).to_string()
+                }
+                "function" | "macro" => signature(item, source_code, indent),
+                "module" | "baremodule" => module_outline(item, source_code, indent),
+                _ => short_function(item, source_code)?,
+            };
+            Some((content, item))
+        })
+        .collect()
+}
+
+/// Split tokens into items, each ending at a line break outside any brackets or blocks. A line
+/// ending in a comma or an operator continues on the next line.
+fn split_items<'a>(tokens: &'a [Spanned], source_code: &str) -> Vec<&'a [Spanned]> {
+    let mut items = vec![];
+    let mut start = 0;
+    let mut blocks = 0;
+    let mut brackets = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match &spanned.token {
+            Token::Punctuation('(' | '[' | '{') => brackets += 1,
+            Token::Punctuation(')' | ']' | '}') => brackets -= 1,
+            Token::Word(word) if brackets == 0 => {
+                if opens_block(word, tokens.get(i + 1)) {
+                    blocks += 1;
+                } else if word == "end" {
+                    blocks -= 1;
+                }
+            }
+            _ => {}
+        }
+
+        let end_of_line = match tokens.get(i + 1) {
+            Some(next) => newline_between(source_code, spanned, next),
+            None => true,
+        };
+        let continues =
+            matches!(spanned.token, Token::Punctuation(c) if !matches!(c, ')' | ']' | '}' | ';'));
+        if ((end_of_line && !continues) || spanned.is_punctuation(';'))
+            && blocks <= 0
+            && brackets <= 0
+        {
+            items.push(&tokens[start..=i]);
+            start = i + 1;
+            (blocks, brackets) = (0, 0);
+        }
+    }
+    items
+}
+
+fn opens_block(word: &str, next: Option<&Spanned>) -> bool {
+    BLOCK_KEYWORDS.contains(&word)
+        || ((word == "abstract" || word == "primitive")
+            && next.is_some_and(|next| next.is_word("type")))
+}
+
+/// The first word of an item, past any macro calls such as `@inline` or `Base.@kwdef`.
+fn keyword(item: &[Spanned]) -> Option<&str> {
+    let mut tokens = item.iter();
+    while let Some(spanned) = tokens.next() {
+        match &spanned.token {
+            Token::Punctuation('@') => {
+                tokens.next();
+            }
+            Token::Word(word) if !word.ends_with('.') => return Some(word),
+            Token::Word(_) => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// The first line of a block, extended while brackets are open, with the rest of the block elided.
+fn signature(item: &[Spanned], source_code: &str, indent: &str) -> String {
+    let header_end = header_end(item, source_code);
+    if header_end == item.len() {
+        // The whole definition is on one line, e.g. `function f end`.
+        return text(item, source_code).to_string();
+    }
+    format!(
+        "{}\n{}# ...\nend",
+        text(&item[..header_end], source_code),
+        indent
+    )
+}
+
+/// The number of tokens on the first line of an item, extended while brackets are open.
+fn header_end(item: &[Spanned], source_code: &str) -> usize {
+    let mut brackets = 0;
+    for (i, pair) in item.windows(2).enumerate() {
+        match pair[0].token {
+            Token::Punctuation('(' | '[' | '{') => brackets += 1,
+            Token::Punctuation(')' | ']' | '}') => brackets -= 1,
+            _ => {}
+        }
+        if brackets <= 0 && newline_between(source_code, &pair[0], &pair[1]) {
+            return i + 1;
+        }
+    }
+    item.len()
+}
+
+fn module_outline(item: &[Spanned], source_code: &str, indent: &str) -> String {
+    let header_end = header_end(item, source_code);
+    let mut result = text(&item[..header_end], source_code).to_string();
+    result.push('\n');
+    let body = &item[header_end..item.len().saturating_sub(1).max(header_end)];
+    for (content, _) in digest_items(body, source_code, indent) {
+        result.push('\n');
+        result.push_str(&content);
+        result.push('\n');
+    }
+    result.push_str("\nend");
+    result
+}
+
+/// A one-line function definition such as `area(r) = pi * r^2`, with its body elided, or None if
+/// the item is not one.
+fn short_function(item: &[Spanned], source_code: &str) -> Option<String> {
+    if !item.get(1)?.is_punctuation('(') {
+        return None;
+    }
+    let mut brackets = 0;
+    for (i, spanned) in item.iter().enumerate() {
+        match spanned.token {
+            Token::Punctuation('(' | '[' | '{') => brackets += 1,
+            Token::Punctuation(')' | ']' | '}') => brackets -= 1,
+            Token::Punctuation('=') if brackets == 0 => {
+                let adjacent = |other: Option<&Spanned>, punctuation: &[char]| {
+                    other.is_some_and(|other| {
+                        punctuation.iter().any(|&c| other.is_punctuation(c))
+                            && (other.range.end == spanned.range.start
+                                || other.range.start == spanned.range.end)
+                    })
+                };
+                // Not part of a comparison or other operator such as `==`, `<=` or `=>`.
+                if adjacent(
+                    i.checked_sub(1).and_then(|j| item.get(j)),
+                    &['=', '<', '>', '!'],
+                ) || adjacent(item.get(i + 1), &['=', '>'])
+                {
+                    return None;
+                }
+                return Some(format!("{} = ...", text(&item[..i], source_code)));
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -9,46 +9,278 @@
  */
 
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
-use crate::tree_sitter_parse::{from_language, to_tree};
+use crate::scan::{line_indentation, lines_byte_range};
+use crate::tree_sitter_parse::{from_language, to_tree, to_tree_with_limits};
 use tree_sitter as ts;
 
+pub use crate::calls::FunctionCalls;
+pub use crate::config_file::parse_config_from_toml;
+pub use crate::item::{ItemKind, Symbol};
+pub use crate::metrics::FileMetrics;
+pub use crate::registry::LanguageRegistry;
+pub use crate::syntax_error::SyntaxError;
+pub use crate::test_code::{is_test_path, TestCode};
+pub use crate::tree_sitter_parse::{CancellationToken, ParserPool};
+
+mod bash;
+mod calls;
+mod clojure;
+mod cmake;
+mod config_file;
+mod cpp;
+mod dart;
+mod doc_comment;
+mod dockerfile;
+mod elide;
+mod fallback;
+mod fsharp;
+mod hcl;
+mod injection;
+mod item;
+mod java;
+mod json;
+mod julia;
+mod make;
+mod markdown;
+mod metrics;
+mod nix;
+mod notebook;
+mod php;
+mod proto;
+mod python;
+mod query;
+mod registry;
+mod rust;
+mod scan;
+mod sql;
+mod strip_comments;
+mod svelte;
+mod syntax_error;
+mod test_code;
+mod toml;
 mod tree_sitter_parse;
+mod yaml;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Language {
+    Bash,
+    Clojure,
+    CMake,
+    Cpp,
+    Dart,
+    Dockerfile,
+    FSharp,
     Go,
     Hcl,
     Java,
+    Json,
+    Julia,
+    /// Jupyter notebooks, digested as the headings of their markdown cells and what the default
+    /// Python config captures from their code cells.
+    Jupyter,
+    Make,
+    Markdown,
+    Nix,
+    Php,
+    Proto,
     Python,
     Rust,
+    Sql,
+    Svelte,
+    Toml,
+    /// A language with no grammar or scanner, digested by heuristics: its first lines, and later
+    /// lines that look like definitions.
+    Unknown,
+    Yaml,
+}
+
+impl Language {
+    /// The name that marks a fenced markdown code block as this language, for syntax highlighting.
+    pub fn fence_name(self) -> &'static str {
+        match self {
+            Language::Bash => "bash",
+            Language::Clojure => "clojure",
+            Language::CMake => "cmake",
+            Language::Cpp => "cpp",
+            Language::Dart => "dart",
+            Language::Dockerfile => "dockerfile",
+            Language::FSharp => "fsharp",
+            Language::Go => "go",
+            Language::Hcl => "hcl",
+            Language::Java => "java",
+            Language::Json => "json",
+            Language::Julia => "julia",
+            Language::Jupyter => "python",
+            Language::Make => "make",
+            Language::Markdown => "markdown",
+            Language::Nix => "nix",
+            Language::Php => "php",
+            Language::Proto => "protobuf",
+            Language::Python => "python",
+            Language::Rust => "rust",
+            Language::Sql => "sql",
+            Language::Svelte => "svelte",
+            Language::Toml => "toml",
+            Language::Unknown => "",
+            Language::Yaml => "yaml",
+        }
+    }
+
+    /// The marker that starts a line comment. Languages without line comments, such as JSON, use
+    /// `//`.
+    pub fn line_comment(self) -> &'static str {
+        match self {
+            Language::Bash
+            | Language::CMake
+            | Language::Dockerfile
+            | Language::Hcl
+            | Language::Julia
+            | Language::Jupyter
+            | Language::Make
+            | Language::Nix
+            | Language::Python
+            | Language::Toml
+            | Language::Yaml => "#",
+            Language::Clojure => ";;",
+            Language::Sql => "--",
+            Language::Cpp
+            | Language::Dart
+            | Language::FSharp
+            | Language::Go
+            | Language::Java
+            | Language::Json
+            | Language::Markdown
+            | Language::Php
+            | Language::Proto
+            | Language::Rust
+            | Language::Svelte
+            | Language::Unknown => "//",
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum ParseError {
-    #[error("custom selector action failed")]
+    #[error("custom selector action failed: {0}")]
     CustomActionFailed(String),
 
+    #[error("custom selector for `{kind}` at line {line}, column {column} failed: {source}")]
+    SelectorFailed {
+        kind: String,
+        line: usize,
+        column: usize,
+        source: Box<ParseError>,
+    },
+
+    #[error("text of `{kind}` at line {line}, column {column} is not valid UTF-8 in the source")]
+    InvalidNodeText {
+        kind: String,
+        line: usize,
+        column: usize,
+    },
+
     #[error("tree-sitter parse error")]
     TreeSitterParseError(#[from] tree_sitter::LanguageError),
+
+    #[error("invalid tree-sitter query")]
+    QueryError(#[from] tree_sitter::QueryError),
+
+    #[error("tree-sitter query has no @capture")]
+    QueryWithoutCapture,
+
+    #[error("tree-sitter injection query has no @injection.content")]
+    InjectionWithoutContent,
+
+    #[error("language is parsed by a scanner, which does not support {0}")]
+    ScannerUnsupported(&'static str),
+
+    #[error("invalid selector name pattern: {0}")]
+    InvalidNamePattern(#[from] regex::Error),
+
+    #[error("invalid parse config file: {0}")]
+    InvalidConfigFile(#[from] ::toml::de::Error),
+
+    #[error("syntax errors at {}", syntax_error::describe(.0))]
+    SyntaxErrors(Vec<SyntaxError>),
+
+    #[error("parsing took longer than the timeout of {0:?}")]
+    TimedOut(Duration),
+
+    #[error("parsing was cancelled")]
+    Cancelled,
+
+    #[error("error reading file: {0}")]
+    ReadingFile(#[from] std::io::Error),
+
+    #[error("unknown language for {0}")]
+    UnknownLanguage(PathBuf),
 }
 
 type ParseResult<T, E = ParseError> = Result<T, E>;
-type SelectorFunction = dyn Fn(&ts::Node, &mut ts::TreeCursor, &str) -> ParseResult<String>;
+
+// Configs are shared between threads by `parse_many`, so must stay safe to share.
+const _: fn() = assert_send_sync::<ParseConfig>;
+fn assert_send_sync<T: Send + Sync>() {}
+type SelectorFunction =
+    dyn Fn(&ts::Node, &mut ts::TreeCursor, &str) -> ParseResult<String> + Send + Sync;
+type OutlineFunction = fn(&ts::Node, &str, &str) -> String;
 
 // SelectorType lets you choose which tree-sitter AST nodes to select (traverse), which to capture,
 // and if captured whether or not to elide the block contents. You need to select AST nodes that
 // are parents of other nodes that you are interested in.
+//
+// CaptureNested captures a node's header, everything before its `body` field, followed by what the
+// selectors capture inside the body, indented under it as in the source. Nested definitions such
+// as inner functions and classes are kept at any depth this way. A body in which nothing is
+// captured is elided.
+//
+// Outline captures what a function makes of the node's source given the config's indent, such as a
+// class with its method bodies elided, so that the digest follows `set_indentation`. Like a custom
+// action, an outline that is empty is not captured.
 pub enum SelectorAction {
     SelectOnly,
     CaptureWithoutBlock,
     CaptureAll,
+    CaptureNested,
     Custom(Box<SelectorFunction>),
+    Outline(OutlineFunction),
 }
 
+// Several selectors can apply to the same node kind. They are tried from the highest priority to
+// the lowest, and among equal priorities from the most recently added, and the first that matches
+// decides the action, so custom rules can be layered over a language's defaults. A selector with
+// predicates only matches nodes that satisfy all of them.
 pub struct Selector {
     pub node_kind: String,
     pub action: SelectorAction,
+    pub priority: i32,
+    pub predicates: Vec<SelectorPredicate>,
+}
+
+pub enum SelectorPredicate {
+    /// The node's child in the field, such as `name` or `type`, is exactly the value.
+    FieldEquals { field: String, value: String },
+
+    /// The name of the item, as in `KeyContent::name`, matches the pattern.
+    NameMatches(regex::Regex),
+}
+
+impl SelectorPredicate {
+    fn matches(&self, node: &ts::Node, source_code: &str) -> bool {
+        match self {
+            SelectorPredicate::FieldEquals { field, value } => node
+                .child_by_field_name(field)
+                .is_some_and(|child| source_code[child.byte_range()] == *value),
+            SelectorPredicate::NameMatches(pattern) => {
+                item::item_name(node, source_code).is_some_and(|name| pattern.is_match(&name))
+            }
+        }
+    }
 }
 
 impl Selector {
@@ -56,8 +288,43 @@ impl Selector {
         Selector {
             node_kind: node_kind.into(),
             action,
+            priority: 0,
+            predicates: vec![],
         }
     }
+
+    /// Try this selector before those of lower priority for the same node kind. The default is 0.
+    pub fn with_priority(mut self, priority: i32) -> Selector {
+        self.priority = priority;
+        self
+    }
+
+    /// Only match nodes whose child in `field` is exactly `value`.
+    pub fn with_field_equals(
+        mut self,
+        field: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Selector {
+        self.predicates.push(SelectorPredicate::FieldEquals {
+            field: field.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Only match nodes whose name matches the regular expression `pattern`, such as `^Handle` for
+    /// names that start with `Handle`. Nodes without a name never match.
+    pub fn with_name_matching(mut self, pattern: &str) -> ParseResult<Selector> {
+        self.predicates
+            .push(SelectorPredicate::NameMatches(regex::Regex::new(pattern)?));
+        Ok(self)
+    }
+
+    fn matches(&self, node: &ts::Node, source_code: &str) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(node, source_code))
+    }
 }
 
 pub enum Indentation {
@@ -71,48 +338,464 @@ impl Default for Indentation {
     }
 }
 
+/// How much of each captured item a digest keeps, from least to most.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Granularity {
+    /// Doc comments and signatures, with types reduced to their headers
+    Signatures,
+
+    /// Signatures, with types kept whole so that their fields are shown, as the default configs do
+    #[default]
+    Types,
+
+    /// As `Types`, also keeping function bodies of up to `SMALL_BODY_LINES` lines whole
+    SmallBodies,
+
+    /// The whole of the source code
+    Full,
+}
+
+/// The longest function body that `Granularity::SmallBodies` keeps whole, in lines.
+pub const SMALL_BODY_LINES: usize = 10;
+
+// Grammar is how source code is parsed into key contents. Most languages use a tree-sitter grammar
+// and selectors, but languages without a suitable grammar use a hand-written scanner instead, which
+// ignores the selectors.
+enum Grammar {
+    TreeSitter(tree_sitter_parse::TreeSitterConfig),
+    Scanner(fn(&str, &str) -> Vec<KeyContent>),
+}
+
+/// What to capture from source code in one language, and how. A config is `Send + Sync`, custom
+/// selector actions included, so threads parsing files at the same time can share one config by
+/// reference rather than each cloning it, as `parse_many` does.
 pub struct ParseConfig {
-    language_config: tree_sitter_parse::TreeSitterConfig,
-    selectors: HashMap<String, Selector>,
+    language: Language,
+    grammar: Grammar,
+    selectors: HashMap<String, Vec<Selector>>,
+    queries: Vec<query::QuerySelector>,
+    injections: Vec<injection::InjectionQuery>,
     indent_value: String,
+    doc_comments: doc_comment::DocComments,
+    include_doc_comments: bool,
+    include_leading_comments: bool,
+    elision_threshold: usize,
+    elision_summary: bool,
+    strip_comments: bool,
+    signatures_only: bool,
+    test_code: TestCode,
+    max_depth: Option<usize>,
+    fail_on_syntax_errors: bool,
+    timeout: Option<Duration>,
+    full_source: bool,
 }
 
 impl ParseConfig {
     pub fn new(language: Language, indentation: Indentation) -> ParseConfig {
-        let indent_value = match indentation {
-            Indentation::Tabs => "\t".to_string(),
-            Indentation::Spaces(indent_size) => {
-                let mut indent_value = String::with_capacity(indent_size);
-                for _ in 0..indent_size {
-                    indent_value.push(' ');
-                }
-                indent_value
-            }
-        };
+        let indent_value = indent_value(indentation);
 
         ParseConfig {
-            language_config: from_language(language),
+            language,
+            grammar: match language {
+                Language::Clojure => Grammar::Scanner(clojure::parse),
+                Language::CMake => Grammar::Scanner(cmake::parse),
+                Language::Dart => Grammar::Scanner(dart::parse),
+                Language::Dockerfile => Grammar::Scanner(dockerfile::parse),
+                Language::FSharp => Grammar::Scanner(fsharp::parse),
+                Language::Hcl => Grammar::Scanner(hcl::parse),
+                Language::Json => Grammar::Scanner(json::parse),
+                Language::Julia => Grammar::Scanner(julia::parse),
+                Language::Jupyter => Grammar::Scanner(notebook::parse),
+                Language::Make => Grammar::Scanner(make::parse),
+                Language::Markdown => Grammar::Scanner(markdown::parse),
+                Language::Nix => Grammar::Scanner(nix::parse),
+                Language::Proto => Grammar::Scanner(proto::parse),
+                Language::Sql => Grammar::Scanner(sql::parse),
+                Language::Svelte => Grammar::Scanner(svelte::parse),
+                Language::Toml => Grammar::Scanner(toml::parse),
+                Language::Unknown => Grammar::Scanner(fallback::parse),
+                Language::Yaml => Grammar::Scanner(yaml::parse),
+                _ => Grammar::TreeSitter(from_language(language)),
+            },
             selectors: HashMap::new(),
+            queries: vec![],
+            injections: vec![],
             indent_value,
+            doc_comments: doc_comment::doc_comments(language),
+            include_doc_comments: false,
+            include_leading_comments: false,
+            elision_threshold: 0,
+            elision_summary: false,
+            strip_comments: false,
+            signatures_only: false,
+            test_code: TestCode::Keep,
+            max_depth: None,
+            fail_on_syntax_errors: false,
+            timeout: None,
+            full_source: false,
         }
     }
 
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Whether the language is parsed by a hand-written scanner rather than a tree-sitter grammar.
+    pub(crate) fn is_scanned(&self) -> bool {
+        matches!(self.grammar, Grammar::Scanner(_))
+    }
+
+    /// Add a selector ahead of the existing selectors for its node kind that have the same or a
+    /// lower priority. Languages parsed by a scanner ignore selectors.
     pub fn add_selector(&mut self, selector: Selector) {
-        self.selectors.insert(selector.node_kind.clone(), selector);
+        let selectors = self
+            .selectors
+            .entry(selector.node_kind.clone())
+            .or_default();
+        let position = selectors
+            .iter()
+            .position(|existing| existing.priority <= selector.priority)
+            .unwrap_or(selectors.len());
+        selectors.insert(position, selector);
+    }
+
+    /// Remove every selector for `node_kind`.
+    pub fn remove_selector(&mut self, node_kind: &str) {
+        self.selectors.remove(node_kind);
+    }
+
+    pub fn set_indentation(&mut self, indentation: Indentation) {
+        self.indent_value = indent_value(indentation);
+    }
+
+    /// Include the doc comments directly above captured nodes, such as `///` comments in Rust or
+    /// Javadoc, and keep Python docstrings when function bodies are elided. Languages parsed by a
+    /// scanner ignore this.
+    pub fn set_include_doc_comments(&mut self, include_doc_comments: bool) {
+        self.include_doc_comments = include_doc_comments;
+    }
+
+    /// Include any comments directly above captured nodes, not only doc comments, since ordinary
+    /// comments often explain why code is the way it is. Languages parsed by a scanner ignore this.
+    pub fn set_include_leading_comments(&mut self, include_leading_comments: bool) {
+        self.include_leading_comments = include_leading_comments;
+    }
+
+    /// Keep function bodies that span at most `lines` lines in full rather than eliding them, since
+    /// small getters and helpers are often clearer shown whole. This applies to bodies elided by
+    /// `CaptureWithoutBlock`, `CaptureNested` and queries, but not by custom actions. The default, 0,
+    /// elides every body. Languages parsed by a scanner ignore this.
+    pub fn set_elision_threshold(&mut self, lines: usize) {
+        self.elision_threshold = lines;
+    }
+
+    /// Keep as much of each captured item as `granularity` asks for, replacing any earlier
+    /// signatures-only setting and elision threshold. At `Granularity::Full` the whole source code
+    /// is one item, whatever the selectors, for every language. Languages parsed by a scanner ignore
+    /// the other granularities.
+    pub fn set_granularity(&mut self, granularity: Granularity) {
+        self.signatures_only = granularity == Granularity::Signatures;
+        self.elision_threshold = match granularity {
+            Granularity::SmallBodies => SMALL_BODY_LINES,
+            _ => 0,
+        };
+        self.full_source = granularity == Granularity::Full;
+    }
+
+    /// Follow each elided body's `...` with how much it held, as in `// ... (37 lines, 5 calls
+    /// elided)`, so that it is clear how much is missing. Like the elision threshold, this applies
+    /// to bodies elided by `CaptureWithoutBlock`, `CaptureNested` and queries, but not by custom
+    /// actions. Languages parsed by a scanner ignore this.
+    pub fn set_elision_summary(&mut self, elision_summary: bool) {
+        self.elision_summary = elision_summary;
+    }
+
+    /// Remove comments from captured items, for the most code in the fewest tokens. Doc comments
+    /// and leading comments above top-level items are still kept if included, but those of items
+    /// nested inside them are removed. Languages parsed by a scanner ignore this.
+    pub fn set_strip_comments(&mut self, strip_comments: bool) {
+        self.strip_comments = strip_comments;
+    }
+
+    /// Reduce each captured item to its doc comments and its signature, everything before its
+    /// body, for a digest like an API reference. Python docstrings are kept under the signature,
+    /// items captured with `CaptureNested` keep the signatures of what is captured inside them,
+    /// and items without a body are kept whole. This includes doc comments whether or not they
+    /// were asked for. Languages parsed by a scanner ignore this.
+    pub fn set_signatures_only(&mut self, signatures_only: bool) {
+        self.signatures_only = signatures_only;
+    }
+
+    /// Summarize or skip tests, such as `#[cfg(test)]` modules in Rust or `TestXxx` functions in Go,
+    /// wherever the selectors would capture them. Languages parsed by a scanner ignore this.
+    pub fn set_test_code(&mut self, test_code: TestCode) {
+        self.test_code = test_code;
+    }
+
+    /// Whether `node` is a test that is not digested as usual.
+    fn elides_test(&self, node: &ts::Node, source_code: &str) -> bool {
+        self.test_code != TestCode::Keep && test_code::is_test(node, source_code, self.language)
+    }
+
+    /// What an elided test is captured as, or None if tests are skipped.
+    fn test_summary(&self, node: ts::Node, source_code: &str) -> Option<String> {
+        (self.test_code == TestCode::Summarize)
+            .then(|| test_code::summary(node, source_code, self.language))
+    }
+
+    /// Whether doc comments are included, as asked for or for signatures.
+    fn includes_doc_comments(&self) -> bool {
+        self.include_doc_comments || self.signatures_only
+    }
+
+    /// Stop the selectors at nodes more than `depth` levels below the root of the syntax tree, so
+    /// that 1 captures only top-level items. Depth counts every node in between, so in Rust a
+    /// method is at depth 3, inside an `impl_item` and its `declaration_list`. Queries are not
+    /// limited. The default, None, has no limit. Languages parsed by a scanner ignore this.
+    pub fn set_max_depth(&mut self, depth: Option<usize>) {
+        self.max_depth = depth;
+    }
+
+    /// Whether `node` is deeper in its tree than the selectors go.
+    fn beyond_max_depth(&self, node: &ts::Node) -> bool {
+        let max_depth = match self.max_depth {
+            Some(max_depth) => max_depth,
+            None => return false,
+        };
+        let mut depth = 0;
+        let mut parent = node.parent();
+        while let Some(node) = parent {
+            depth += 1;
+            if depth > max_depth {
+                return true;
+            }
+            parent = node.parent();
+        }
+        false
+    }
+
+    /// Fail with `ParseError::SyntaxErrors` rather than capture what can be captured from source
+    /// code with syntax errors. Languages parsed by a scanner ignore this.
+    pub fn set_fail_on_syntax_errors(&mut self, fail_on_syntax_errors: bool) {
+        self.fail_on_syntax_errors = fail_on_syntax_errors;
+    }
+
+    /// Fail with `ParseError::TimedOut` rather than keep parsing a file for longer than `timeout`,
+    /// such as a huge generated file or one with deeply nested literals. Languages parsed by a
+    /// scanner ignore this.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Whether `node` is small enough to keep rather than elide.
+    fn keeps_whole(&self, node: &ts::Node) -> bool {
+        node.end_position().row - node.start_position().row < self.elision_threshold
+    }
+
+    /// Add a tree-sitter query, such as the contents of a `.scm` file, that captures nodes as
+    /// `@capture` and elides nodes inside them captured as `@elide`. Queries run alongside the
+    /// selectors, for example:
+    ///
+    /// ```text
+    /// (function_item name: (identifier) @name body: (block) @elide) @capture
+    /// ```
+    pub fn add_query(&mut self, source: &str) -> ParseResult<()> {
+        let language = match &self.grammar {
+            Grammar::TreeSitter(language_config) => language_config.language,
+            Grammar::Scanner(_) => return Err(ParseError::ScannerUnsupported("queries")),
+        };
+        self.queries
+            .push(query::QuerySelector::new(language, source)?);
+        Ok(())
     }
 
+    /// Add a tree-sitter injection query, as in a grammar's `injections.scm`, that finds code in
+    /// another language embedded in this one. The node captured as `@injection.content` is digested
+    /// in the language set with `#set! injection.language` or captured as `@injection.language`,
+    /// and emitted as a section fenced with `~~~`. For example, for SQL in Python:
+    ///
+    /// ```text
+    /// (call arguments: (argument_list (string) @injection.content)
+    ///   (#set! injection.language "sql"))
+    /// ```
+    pub fn add_injection(&mut self, source: &str) -> ParseResult<()> {
+        let language = match &self.grammar {
+            Grammar::TreeSitter(language_config) => language_config.language,
+            Grammar::Scanner(_) => return Err(ParseError::ScannerUnsupported("injections")),
+        };
+        self.injections
+            .push(injection::InjectionQuery::new(language, source)?);
+        Ok(())
+    }
+
+    /// The action of the first selector for `node_kind` without predicates, which applies to any
+    /// node of that kind that no earlier selector matches.
     pub fn get_selector_action(&self, node_kind: &str) -> Option<&SelectorAction> {
-        self.selectors.get(node_kind).map(|s| &s.action)
+        self.selectors
+            .get(node_kind)
+            .and_then(|selectors| selectors.iter().find(|s| s.predicates.is_empty()))
+            .map(|s| &s.action)
+    }
+
+    /// The action of the first selector that matches `node`.
+    fn selector_action(&self, node: &ts::Node, source_code: &str) -> Option<&SelectorAction> {
+        self.selectors
+            .get(node.kind())
+            .and_then(|selectors| selectors.iter().find(|s| s.matches(node, source_code)))
+            .map(|s| &s.action)
+    }
+
+    /// The first doc comment above `node` if doc comments are included, or the first comment of
+    /// any kind if leading comments are.
+    fn doc_comment<'a>(&self, node: &ts::Node<'a>, source_code: &str) -> Option<ts::Node<'a>> {
+        if self.include_leading_comments {
+            return doc_comment::first_doc_comment(node, source_code, &[""]);
+        }
+        if !self.includes_doc_comments() {
+            return None;
+        }
+        doc_comment::first_doc_comment(node, source_code, self.doc_comments.outer)
+    }
+
+    /// The first of the doc comments and attributes above `node` that are captured with it. Rust
+    /// attributes such as `#[derive(Debug)]` are siblings of the item they apply to, so are not
+    /// part of its text.
+    fn leading_node<'a>(&self, node: &ts::Node<'a>, source_code: &str) -> Option<ts::Node<'a>> {
+        let doc_comment = self.doc_comment(node, source_code);
+        let attribute = match self.language {
+            Language::Rust => rust::first_attribute(node),
+            _ => None,
+        };
+        match (doc_comment, attribute) {
+            (Some(doc_comment), Some(attribute)) => Some(std::cmp::min_by_key(
+                doc_comment,
+                attribute,
+                ts::Node::start_byte,
+            )),
+            (doc_comment, attribute) => doc_comment.or(attribute),
+        }
+    }
+}
+
+fn indent_value(indentation: Indentation) -> String {
+    match indentation {
+        Indentation::Tabs => "\t".to_string(),
+        Indentation::Spaces(indent_size) => {
+            let mut indent_value = String::with_capacity(indent_size);
+            for _ in 0..indent_size {
+                indent_value.push(' ');
+            }
+            indent_value
+        }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct KeyContent {
     pub content: String,
+
+    /// The first line of the captured node in the source code, starting from 1.
+    pub start_line: usize,
+
+    /// The last line of the captured node in the source code, starting from 1.
+    pub end_line: usize,
+
+    /// What kind of item was captured, such as a function or an import. Languages parsed by a
+    /// scanner report `ItemKind::Other`.
+    pub kind: ItemKind,
+
+    /// The name of the captured item, such as a function's name, if it has one. Languages parsed by
+    /// a scanner report no name.
+    pub name: Option<String>,
+
+    /// The bytes of the captured node in the source code. For languages parsed by a scanner this is
+    /// every line from `start_line` to `end_line`.
+    pub byte_range: Range<usize>,
+}
+
+impl KeyContent {
+    fn new(content: String, node: &ts::Node, source_code: &str) -> KeyContent {
+        KeyContent {
+            content,
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            kind: item::item_kind(node),
+            name: item::item_name(node, source_code),
+            byte_range: node.byte_range(),
+        }
+    }
 }
 
 pub fn default_parse_config_for_language(language: Language) -> ParseConfig {
     match language {
+        Language::Bash => {
+            let mut config = ParseConfig::new(language, Indentation::Spaces(4));
+            config.add_selector(Selector::new("program", SelectorAction::SelectOnly));
+            config.add_selector(Selector::new(
+                "comment",
+                SelectorAction::Custom(Box::new(|node, _cursor, source_code| {
+                    Ok(bash::shebang(node, source_code))
+                })),
+            ));
+            config.add_selector(Selector::new(
+                "variable_assignment",
+                SelectorAction::CaptureAll,
+            ));
+
+            // export, readonly, declare and local
+            config.add_selector(Selector::new(
+                "declaration_command",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "function_definition",
+                SelectorAction::Outline(bash::elide_function_body),
+            ));
+            config
+        }
+        Language::Clojure | Language::CMake => ParseConfig::new(language, Indentation::Spaces(2)),
+        Language::Cpp => {
+            let mut config = ParseConfig::new(language, Indentation::Spaces(4));
+            config.add_selector(Selector::new(
+                "translation_unit",
+                SelectorAction::SelectOnly,
+            ));
+            config.add_selector(Selector::new("preproc_include", SelectorAction::CaptureAll));
+            config.add_selector(Selector::new(
+                "using_declaration",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "alias_declaration",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new("type_definition", SelectorAction::CaptureAll));
+
+            // Free function declarations, as in headers, as well as global variables.
+            config.add_selector(Selector::new("declaration", SelectorAction::CaptureAll));
+
+            // Namespaces, classes and templates are captured whole, including any nested
+            // namespaces, with function bodies elided at every level.
+            for node_kind in [
+                "namespace_definition",
+                "linkage_specification",
+                "template_declaration",
+                "class_specifier",
+                "struct_specifier",
+                "union_specifier",
+                "enum_specifier",
+                "function_definition",
+            ] {
+                config.add_selector(Selector::new(
+                    node_kind,
+                    SelectorAction::Outline(cpp::elide_function_bodies),
+                ));
+            }
+            config
+        }
+        Language::Dart => ParseConfig::new(language, Indentation::Spaces(2)),
+        Language::Dockerfile => ParseConfig::new(language, Indentation::Spaces(4)),
+        Language::FSharp => ParseConfig::new(language, Indentation::Spaces(4)),
         Language::Go => {
             let mut config = ParseConfig::new(language, Indentation::Tabs);
             config.add_selector(Selector::new("source_file", SelectorAction::SelectOnly));
@@ -145,8 +828,7 @@ pub fn default_parse_config_for_language(language: Language) -> ParseConfig {
                             let type_name_kind = type_name.kind().to_string();
                             if type_name_kind == "struct_type" || type_name_kind == "interface_type"
                             {
-                                let content = node.utf8_text(source_code.as_bytes()).unwrap();
-                                return Ok(content.into());
+                                return node_text(node, source_code).map(String::from);
                             }
                             return Ok("".into());
                         }
@@ -157,6 +839,120 @@ pub fn default_parse_config_for_language(language: Language) -> ParseConfig {
                 })),
             ));
             config
+                .add_injection(injection::GO_SQL)
+                .expect("default injection queries are valid");
+            config
+        }
+        Language::Hcl => ParseConfig::new(language, Indentation::Spaces(2)),
+        Language::Java => {
+            let mut config = ParseConfig::new(language, Indentation::Spaces(4));
+            config.add_selector(Selector::new("program", SelectorAction::SelectOnly));
+            config.add_selector(Selector::new(
+                "package_declaration",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "import_declaration",
+                SelectorAction::CaptureAll,
+            ));
+
+            // Type declarations are captured whole, keeping annotations, fields and nested types,
+            // with method and constructor bodies elided.
+            for node_kind in [
+                "class_declaration",
+                "interface_declaration",
+                "enum_declaration",
+                "record_declaration",
+                "annotation_type_declaration",
+            ] {
+                config.add_selector(Selector::new(
+                    node_kind,
+                    SelectorAction::Outline(java::elide_method_bodies),
+                ));
+            }
+            config
+                .add_injection(injection::JAVA_SQL)
+                .expect("default injection queries are valid");
+            config
+        }
+        Language::Json => ParseConfig::new(language, Indentation::Spaces(2)),
+        Language::Julia => ParseConfig::new(language, Indentation::Spaces(4)),
+        Language::Jupyter => ParseConfig::new(language, Indentation::Spaces(4)),
+        Language::Make => ParseConfig::new(language, Indentation::Tabs),
+        Language::Markdown => ParseConfig::new(language, Indentation::Spaces(4)),
+        Language::Nix => ParseConfig::new(language, Indentation::Spaces(2)),
+        Language::Php => {
+            let mut config = ParseConfig::new(language, Indentation::Spaces(4));
+            config.add_selector(Selector::new("program", SelectorAction::SelectOnly));
+            config.add_selector(Selector::new(
+                "namespace_use_declaration",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "const_declaration",
+                SelectorAction::CaptureAll,
+            ));
+
+            // Namespaces and class-like declarations are captured whole, keeping properties and
+            // constants, with function and method bodies elided.
+            for node_kind in [
+                "namespace_definition",
+                "class_declaration",
+                "interface_declaration",
+                "trait_declaration",
+                "enum_declaration",
+                "function_definition",
+            ] {
+                config.add_selector(Selector::new(
+                    node_kind,
+                    SelectorAction::Outline(php::elide_function_bodies),
+                ));
+            }
+            config
+        }
+        Language::Python => {
+            let mut config = ParseConfig::new(language, Indentation::Spaces(4));
+            config.add_selector(Selector::new("module", SelectorAction::SelectOnly));
+            config.add_selector(Selector::new(
+                "import_statement",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "import_from_statement",
+                SelectorAction::CaptureAll,
+            ));
+            config.add_selector(Selector::new(
+                "future_import_statement",
+                SelectorAction::CaptureAll,
+            ));
+
+            // Constants and type aliases, and class attributes and docstrings. Grammars with PEP
+            // 695 support parse `type X = ...` as a type_alias_statement.
+            config.add_selector(Selector::new(
+                "expression_statement",
+                SelectorAction::Custom(Box::new(|node, _cursor, source_code| {
+                    Ok(python::statement(node, source_code))
+                })),
+            ));
+            config.add_selector(Selector::new(
+                "type_alias_statement",
+                SelectorAction::CaptureAll,
+            ));
+
+            // Definitions are captured with their decorators, which belong to the enclosing
+            // decorated_definition rather than to the function or class itself, and with nested
+            // functions and classes indented under them.
+            for node_kind in [
+                "function_definition",
+                "class_definition",
+                "decorated_definition",
+            ] {
+                config.add_selector(Selector::new(node_kind, SelectorAction::CaptureNested));
+            }
+            config
+                .add_injection(injection::PYTHON_SQL)
+                .expect("default injection queries are valid");
+            config
         }
         Language::Rust => {
             let mut config = ParseConfig::new(language, Indentation::Spaces(4));
@@ -165,6 +961,16 @@ pub fn default_parse_config_for_language(language: Language) -> ParseConfig {
             config.add_selector(Selector::new("struct_item", SelectorAction::CaptureAll));
             config.add_selector(Selector::new("enum_item", SelectorAction::CaptureAll));
             config.add_selector(Selector::new("type_item", SelectorAction::CaptureAll));
+            config.add_selector(Selector::new("const_item", SelectorAction::CaptureAll));
+            config.add_selector(Selector::new("static_item", SelectorAction::CaptureAll));
+            config.add_selector(Selector::new(
+                "macro_definition",
+                SelectorAction::Outline(rust::macro_outline),
+            ));
+            config.add_selector(Selector::new(
+                "mod_item",
+                SelectorAction::Outline(rust::module_outline),
+            ));
             config.add_selector(Selector::new(
                 "function_item",
                 SelectorAction::CaptureWithoutBlock,
@@ -174,98 +980,878 @@ pub fn default_parse_config_for_language(language: Language) -> ParseConfig {
                 SelectorAction::CaptureWithoutBlock,
             ));
             config
+                .add_injection(injection::RUST_SQL)
+                .expect("default injection queries are valid");
+            config
         }
-        _ => todo!(),
+        Language::Proto | Language::Sql | Language::Toml | Language::Unknown => {
+            ParseConfig::new(language, Indentation::Spaces(4))
+        }
+        Language::Svelte | Language::Yaml => ParseConfig::new(language, Indentation::Spaces(2)),
     }
 }
 
-pub fn parse(source_code: &str, config: &ParseConfig) -> ParseResult<Vec<KeyContent>> {
-    let mut result = vec![];
+/// The key contents captured from source code, and where it has syntax errors.
+pub struct Parsed {
+    pub key_contents: Vec<KeyContent>,
 
-    let tree = to_tree(source_code, &config.language_config).unwrap();
-    let root_node = tree.root_node();
+    /// Syntax errors that tree-sitter recovered from, in source order. Items captured near them may
+    /// be incomplete. Languages parsed by a scanner report none.
+    pub syntax_errors: Vec<SyntaxError>,
 
-    let cursor = &mut root_node.walk();
-    let mut queue: VecDeque<ts::Node> = VecDeque::new();
-    queue.push_back(root_node);
-    loop {
-        if queue.is_empty() {
-            break;
-        }
-        let node = queue.pop_front().unwrap();
-        let node_kind = node.kind();
+    pub diagnostics: ParseDiagnostics,
+}
 
-        // if there is no selector action, continue
-        let selector_action = config.get_selector_action(node_kind);
-        if selector_action.is_none() {
-            continue;
-        }
-        let selector_action = selector_action.unwrap();
+/// How much work parsing took, for finding files that are slow to digest.
+#[derive(Clone, Debug, Default)]
+pub struct ParseDiagnostics {
+    pub elapsed: Duration,
 
-        match selector_action {
-            SelectorAction::SelectOnly => {
-                for child in node.children(cursor) {
-                    queue.push_back(child);
-                }
-            }
-            SelectorAction::CaptureWithoutBlock => {
-                let content = block_like_to_string(node, cursor, source_code, config);
-                result.push(KeyContent { content });
-            }
-            SelectorAction::CaptureAll => {
-                let content = node
-                    .utf8_text(source_code.as_bytes())
-                    .unwrap()
-                    .trim()
-                    .to_string();
-                result.push(KeyContent { content });
-            }
-            SelectorAction::Custom(action) => {
-                let content = action(&node, cursor, source_code)?;
-                result.push(KeyContent { content });
-            }
-        }
-    }
+    /// The number of tree-sitter nodes visited by the selectors. Languages parsed by a scanner
+    /// report 0.
+    pub nodes: usize,
+}
 
-    Ok(result)
+/// Every named definition in the source code, such as functions, types, and modules, with nested
+/// definitions such as methods after the definition they are in. Unlike `parse` this ignores the
+/// selectors, so it lists definitions whether or not they are captured. Languages parsed by a
+/// scanner have no symbols.
+pub fn outline(source_code: &str, config: &ParseConfig) -> Vec<Symbol> {
+    let language_config = match &config.grammar {
+        Grammar::TreeSitter(language_config) => language_config,
+        Grammar::Scanner(_) => return vec![],
+    };
+    match to_tree(source_code, language_config, ParserPool::global()) {
+        Some(tree) => item::symbols(&tree.root_node(), source_code),
+        None => vec![],
+    }
 }
 
-fn block_like_to_string<'a>(
-    node: ts::Node<'a>,
-    cursor: &mut ts::TreeCursor<'a>,
-    source_code: &str,
-    config: &ParseConfig,
-) -> String {
-    let capacity_guess = node.byte_range().len();
-    let mut result = String::with_capacity(capacity_guess);
-    for child in node.children(cursor) {
-        if child.kind() == "block" {
-            result.push_str(" {\n");
-            result.push_str(&config.indent_value);
-            result.push_str("// ...\n}");
-        } else {
-            if child.kind() != "parameter_list"
-                && child.kind() != "func"
-                && child.kind() != "type_parameters"
-                && child.kind() != "parameters"
-            {
-                result.push(' ');
-            }
-            result.push_str(child.utf8_text(source_code.as_bytes()).unwrap());
-        }
+/// Every named function in the source code with the names of the functions it calls, for building
+/// an approximate call graph. Languages parsed by a scanner have no functions.
+pub fn function_calls(source_code: &str, config: &ParseConfig) -> Vec<FunctionCalls> {
+    let language_config = match &config.grammar {
+        Grammar::TreeSitter(language_config) => language_config,
+        Grammar::Scanner(_) => return vec![],
+    };
+    match to_tree(source_code, language_config, ParserPool::global()) {
+        Some(tree) => calls::function_calls(&tree.root_node(), source_code),
+        None => vec![],
     }
-    result.trim().to_string()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::assert_eq;
+/// Lines of code, the number of functions and types, and approximate cyclomatic complexity of the
+/// source code, or None for languages parsed by a scanner.
+pub fn metrics(source_code: &str, config: &ParseConfig) -> Option<FileMetrics> {
+    let language_config = match &config.grammar {
+        Grammar::TreeSitter(language_config) => language_config,
+        Grammar::Scanner(_) => return None,
+    };
+    let tree = to_tree(source_code, language_config, ParserPool::global())?;
+    Some(metrics::file_metrics(&tree.root_node(), source_code))
+}
 
-    #[test]
-    fn test_parse_go() {
-        let source_code = r#"
-package test
+/// What was captured from a file, and in which language.
+pub struct DigestedFile {
+    pub path: PathBuf,
+    pub language: Language,
+    pub key_contents: Vec<KeyContent>,
+
+    /// Syntax errors that tree-sitter recovered from, in source order.
+    pub syntax_errors: Vec<SyntaxError>,
+}
+
+/// Read the file at `path`, detect its language with `registry` by its name or contents, and parse
+/// it with the default config for that language. Fails with `ParseError::UnknownLanguage` if the
+/// language cannot be detected.
+pub fn parse_file(path: &Path, registry: &LanguageRegistry) -> ParseResult<DigestedFile> {
+    let source_code = std::fs::read_to_string(path)?;
+    let language = registry
+        .language_for_file(path, &source_code)
+        .ok_or_else(|| ParseError::UnknownLanguage(path.to_path_buf()))?;
+    let parsed =
+        parse_with_syntax_errors(&source_code, &default_parse_config_for_language(language))?;
+    Ok(DigestedFile {
+        path: path.to_path_buf(),
+        language,
+        key_contents: parsed.key_contents,
+        syntax_errors: parsed.syntax_errors,
+    })
+}
+
+pub fn parse(source_code: &str, config: &ParseConfig) -> ParseResult<Vec<KeyContent>> {
+    parse_with_syntax_errors(source_code, config).map(|parsed| parsed.key_contents)
+}
+
+/// Parse as `parse` does, also returning the syntax errors in the source code and diagnostics.
+///
+/// Parsing runs in a `parse` tracing span and ends with a debug event carrying the time taken and
+/// the number of nodes, items, and syntax errors, which a `tracing` subscriber can record.
+pub fn parse_with_syntax_errors(source_code: &str, config: &ParseConfig) -> ParseResult<Parsed> {
+    parse_with_pool(source_code, config, ParserPool::global())
+}
+
+/// Parse as `parse_with_syntax_errors` does, with parsers from `pool` rather than the global pool.
+pub fn parse_with_pool(
+    source_code: &str,
+    config: &ParseConfig,
+    pool: &ParserPool,
+) -> ParseResult<Parsed> {
+    parse_cancellable(source_code, config, pool, None)
+}
+
+/// Parse each of `sources` with its config on up to `threads` threads, which share the configs and
+/// the parsers in `pool`. Results are in the same order as `sources`, and each file's result is the
+/// same as parsing it alone. A panic while parsing is passed on once the other threads have
+/// finished.
+pub fn parse_many(
+    sources: &[(&str, &ParseConfig)],
+    pool: &ParserPool,
+    threads: usize,
+) -> Vec<ParseResult<Parsed>> {
+    let next = AtomicUsize::new(0);
+    let parse_next = || {
+        let mut parsed = vec![];
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let Some(&(source_code, config)) = sources.get(i) else {
+                return parsed;
+            };
+            parsed.push((i, parse_with_pool(source_code, config, pool)));
+        }
+    };
+    let mut results = std::thread::scope(|scope| {
+        let workers = (0..threads.clamp(1, sources.len().max(1)))
+            .map(|_| scope.spawn(parse_next))
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Parse as `parse_with_pool` does, failing with `ParseError::Cancelled` if `cancellation` is
+/// cancelled before parsing finishes.
+pub fn parse_cancellable(
+    source_code: &str,
+    config: &ParseConfig,
+    pool: &ParserPool,
+    cancellation: Option<&CancellationToken>,
+) -> ParseResult<Parsed> {
+    let _span = tracing::debug_span!("parse", language = ?config.language).entered();
+    let start = Instant::now();
+    let mut parsed = parse_source(source_code, config, pool, cancellation)?;
+    parsed.diagnostics.elapsed = start.elapsed();
+    tracing::debug!(
+        elapsed_us = parsed.diagnostics.elapsed.as_micros() as u64,
+        nodes = parsed.diagnostics.nodes,
+        items = parsed.key_contents.len(),
+        syntax_errors = parsed.syntax_errors.len(),
+        "parsed"
+    );
+    Ok(parsed)
+}
+
+fn parse_source(
+    source_code: &str,
+    config: &ParseConfig,
+    pool: &ParserPool,
+    cancellation: Option<&CancellationToken>,
+) -> ParseResult<Parsed> {
+    let mut result = vec![];
+    let mut nodes = 0;
+    if cancellation.is_some_and(CancellationToken::is_cancelled) {
+        return Err(ParseError::Cancelled);
+    }
+    if config.full_source {
+        return Ok(Parsed {
+            key_contents: full_source(source_code),
+            syntax_errors: vec![],
+            diagnostics: ParseDiagnostics::default(),
+        });
+    }
+
+    let language_config = match &config.grammar {
+        Grammar::TreeSitter(language_config) => language_config,
+        Grammar::Scanner(scan) => {
+            let mut result = scan(source_code, &config.indent_value);
+            for key_content in &mut result {
+                key_content.byte_range =
+                    lines_byte_range(source_code, key_content.start_line, key_content.end_line);
+            }
+            return Ok(Parsed {
+                key_contents: result,
+                syntax_errors: vec![],
+                diagnostics: ParseDiagnostics::default(),
+            });
+        }
+    };
+    let deadline = config
+        .timeout
+        .map(|timeout| (Instant::now() + timeout, timeout));
+    let stopped = || match deadline {
+        _ if cancellation.is_some_and(CancellationToken::is_cancelled) => {
+            Some(ParseError::Cancelled)
+        }
+        Some((deadline, timeout)) if Instant::now() > deadline => {
+            Some(ParseError::TimedOut(timeout))
+        }
+        _ => None,
+    };
+    let tree = to_tree_with_limits(
+        source_code,
+        language_config,
+        pool,
+        config.timeout,
+        cancellation,
+    )?;
+    let tree = match tree {
+        Some(tree) => tree,
+        // tree-sitter only stops early when cancelled or out of time.
+        None if cancellation.is_some_and(CancellationToken::is_cancelled) => {
+            return Err(ParseError::Cancelled)
+        }
+        None => return Err(ParseError::TimedOut(config.timeout.unwrap_or_default())),
+    };
+    let root_node = tree.root_node();
+    let syntax_errors = syntax_error::syntax_errors(&root_node);
+    if config.fail_on_syntax_errors && !syntax_errors.is_empty() {
+        return Err(ParseError::SyntaxErrors(syntax_errors));
+    }
+
+    let cursor = &mut root_node.walk();
+    let mut queue: VecDeque<ts::Node> = VecDeque::new();
+    queue.push_back(root_node);
+    while let Some(node) = queue.pop_front() {
+        if config.beyond_max_depth(&node) {
+            continue;
+        }
+        nodes += 1;
+        // Walking the tree is much quicker than parsing it, so limits are only checked now and
+        // then.
+        if nodes % 1024 == 0 {
+            if let Some(error) = stopped() {
+                return Err(error);
+            }
+        }
+
+        // if there is no selector action, continue
+        let selector_action = match config.selector_action(&node, source_code) {
+            Some(selector_action) => selector_action,
+            None => continue,
+        };
+
+        if config.elides_test(&node, source_code) {
+            if let Some(summary) = config.test_summary(node, source_code) {
+                result.push(KeyContent::new(summary, &node, source_code));
+            }
+            continue;
+        }
+        if let SelectorAction::SelectOnly = selector_action {
+            for child in node.children(cursor) {
+                queue.push_back(child);
+            }
+            continue;
+        }
+        if let Some(content) = capture(node, cursor, source_code, config, selector_action)? {
+            let content = match config.strip_comments {
+                true => strip_comments::strip_comments(&content, &node, source_code),
+                false => content,
+            };
+            let mut key_content = KeyContent::new(content, &node, source_code);
+            if let Some(leading) = config.leading_node(&node, source_code) {
+                key_content.content = format!(
+                    "{}{}",
+                    &source_code[leading.start_byte()..node.start_byte()],
+                    key_content.content
+                );
+                key_content.start_line = leading.start_position().row + 1;
+                key_content.byte_range.start = leading.start_byte();
+            }
+            result.push(key_content);
+        }
+    }
+
+    if config.includes_doc_comments() {
+        result.extend(doc_comment::file_doc_comments(
+            &root_node,
+            source_code,
+            config.doc_comments.inner,
+        ));
+    }
+    for query in &config.queries {
+        result.extend(query.key_contents(root_node, source_code, config));
+    }
+    for injection in &config.injections {
+        result.extend(injection.key_contents(root_node, source_code));
+    }
+    result.sort_by_key(|key_content| key_content.start_line);
+
+    Ok(Parsed {
+        key_contents: result,
+        syntax_errors,
+        diagnostics: ParseDiagnostics {
+            elapsed: Duration::ZERO,
+            nodes,
+        },
+    })
+}
+
+/// The whole of the source code as one item, or nothing if it is blank.
+fn full_source(source_code: &str) -> Vec<KeyContent> {
+    let content = source_code.trim_end();
+    if content.trim().is_empty() {
+        return vec![];
+    }
+    let end_line = content.lines().count();
+    vec![KeyContent {
+        content: content.to_string(),
+        start_line: 1,
+        end_line,
+        byte_range: 0..content.len(),
+        ..Default::default()
+    }]
+}
+
+/// The content captured for `node` by `selector_action`, or None if nothing is captured.
+fn capture<'a>(
+    node: ts::Node<'a>,
+    cursor: &mut ts::TreeCursor<'a>,
+    source_code: &str,
+    config: &ParseConfig,
+    selector_action: &SelectorAction,
+) -> ParseResult<Option<String>> {
+    let content = match selector_action {
+        SelectorAction::SelectOnly => return Ok(None),
+        SelectorAction::CaptureWithoutBlock | SelectorAction::CaptureAll
+            if config.signatures_only =>
+        {
+            signature(node, source_code)
+        }
+        SelectorAction::CaptureWithoutBlock => {
+            block_like_to_string(node, cursor, source_code, config)?
+        }
+        SelectorAction::CaptureAll => node_text(&node, source_code)?.trim().to_string(),
+        SelectorAction::CaptureNested => nested_to_string(node, source_code, config)?,
+        SelectorAction::Custom(action) => {
+            // A custom action returns nothing for a node it decides not to capture.
+            let content =
+                action(&node, cursor, source_code).map_err(|error| ParseError::SelectorFailed {
+                    kind: node.kind().to_string(),
+                    line: node.start_position().row + 1,
+                    column: node.start_position().column + 1,
+                    source: Box::new(error),
+                })?;
+            if content.is_empty() {
+                return Ok(None);
+            }
+            match config.signatures_only {
+                true => signature(node, source_code),
+                false => content,
+            }
+        }
+        SelectorAction::Outline(outline) => {
+            let content = outline(&node, source_code, &config.indent_value);
+            if content.is_empty() {
+                return Ok(None);
+            }
+            match config.signatures_only {
+                true => signature(node, source_code),
+                false => content,
+            }
+        }
+    };
+    Ok(Some(content))
+}
+
+/// The node that holds the body of `node`, which is a wrapper such as a decorated definition if it
+/// has a `definition` field but no body of its own.
+fn body_owner(node: ts::Node) -> ts::Node {
+    match node.child_by_field_name("definition") {
+        Some(definition) if node.child_by_field_name("body").is_none() => definition,
+        _ => node,
+    }
+}
+
+/// Where the header of `owner` ends, before `body` and any comments between them.
+fn header_end(owner: ts::Node, body: ts::Node) -> usize {
+    let mut cursor = owner.walk();
+    let header_end = owner
+        .children(&mut cursor)
+        .take_while(|child| child.id() != body.id())
+        .filter(|child| child.kind() != "comment")
+        .last()
+        .map_or(body.start_byte(), |child| child.end_byte());
+    header_end
+}
+
+/// Everything before the body of `node`, followed by the docstring at the start of the body if
+/// there is one. A node without a body is kept whole.
+fn signature(node: ts::Node, source_code: &str) -> String {
+    let owner = body_owner(node);
+    let body = match owner.child_by_field_name("body") {
+        Some(body) => body,
+        None => return source_code[node.byte_range()].trim().to_string(),
+    };
+    let mut result = source_code[node.start_byte()..header_end(owner, body)]
+        .trim()
+        .to_string();
+    if let Some(docstring) = doc_comment::docstring(&body) {
+        result.push('\n');
+        result.push_str(line_indentation(source_code, docstring.start_byte()));
+        result.push_str(&source_code[docstring.byte_range()]);
+    }
+    result
+}
+
+fn nested_to_string(
+    node: ts::Node,
+    source_code: &str,
+    config: &ParseConfig,
+) -> ParseResult<String> {
+    // A wrapper such as a decorated definition nests through the definition it wraps.
+    let owner = body_owner(node);
+    let body = match owner.child_by_field_name("body") {
+        Some(body) => body,
+        None => return Ok(source_code[node.byte_range()].trim().to_string()),
+    };
+    let mut result = source_code[node.start_byte()..header_end(owner, body)].to_string();
+
+    // Brackets around the body, as in `{ ... }`, are kept around the nested content.
+    let line_indent = line_indentation(source_code, owner.start_byte());
+    let delimiter = |child: Option<ts::Node>| {
+        child
+            .filter(|child| !child.is_named())
+            .map(|child| &source_code[child.byte_range()])
+    };
+    let opening = delimiter(body.child(0));
+    let closing = delimiter(body.child(body.child_count().saturating_sub(1)));
+    if let Some(opening) = opening {
+        result.push(' ');
+        result.push_str(opening);
+    }
+
+    let mut nested = vec![];
+    let docstring = doc_comment::docstring(&body).filter(|_| config.includes_doc_comments());
+    if let Some(docstring) = docstring {
+        nested.push(format!(
+            "{}{}",
+            line_indentation(source_code, docstring.start_byte()),
+            &source_code[docstring.byte_range()]
+        ));
+    }
+    collect_nested(body, source_code, config, &mut nested)?;
+    // A docstring that the selectors capture too is only kept once.
+    if docstring.is_some() && nested.len() > 1 && nested[0] == nested[1] {
+        nested.remove(1);
+    }
+    let elided = nested.len() == docstring.map_or(0, |_| 1);
+    if elided && config.signatures_only {
+        return Ok(signature(node, source_code));
+    }
+    if elided && config.keeps_whole(&body) {
+        return Ok(source_code[node.byte_range()].trim().to_string());
+    }
+    for content in nested {
+        result.push('\n');
+        result.push_str(&content);
+    }
+    if elided {
+        result.push_str(&format!("\n{}{}...", line_indent, config.indent_value));
+        if config.elision_summary {
+            result.push(' ');
+            result.push_str(&elide::elided_summary(&body));
+        }
+    }
+    if let Some(closing) = closing.filter(|_| body.child_count() > 1) {
+        result.push_str(&format!("\n{}{}", line_indent, closing));
+    }
+    Ok(result)
+}
+
+/// Collect what the selectors capture among the children of `node`, each indented as in the
+/// source, descending into children that are only selected.
+fn collect_nested(
+    node: ts::Node,
+    source_code: &str,
+    config: &ParseConfig,
+    nested: &mut Vec<String>,
+) -> ParseResult<()> {
+    let mut cursor = node.walk();
+    let children = node.children(&mut cursor).collect::<Vec<_>>();
+    for child in children {
+        if config.beyond_max_depth(&child) {
+            continue;
+        }
+        match config.selector_action(&child, source_code) {
+            None => {}
+            Some(_) if config.elides_test(&child, source_code) => {
+                let indent = line_indentation(source_code, child.start_byte());
+                nested.extend(
+                    config
+                        .test_summary(child, source_code)
+                        .map(|summary| format!("{}{}", indent, summary)),
+                );
+            }
+            Some(SelectorAction::SelectOnly) => collect_nested(child, source_code, config, nested)?,
+            Some(selector_action) => {
+                let content = capture(
+                    child,
+                    &mut child.walk(),
+                    source_code,
+                    config,
+                    selector_action,
+                )?;
+                if let Some(content) = content {
+                    let start = config
+                        .leading_node(&child, source_code)
+                        .map_or(child.start_byte(), |leading| leading.start_byte());
+                    nested.push(format!(
+                        "{}{}{}",
+                        line_indentation(source_code, start),
+                        &source_code[start..child.start_byte()],
+                        content
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn block_like_to_string<'a>(
+    node: ts::Node<'a>,
+    cursor: &mut ts::TreeCursor<'a>,
+    source_code: &str,
+    config: &ParseConfig,
+) -> ParseResult<String> {
+    let capacity_guess = node.byte_range().len();
+    let mut result = String::with_capacity(capacity_guess);
+    for child in node.children(cursor) {
+        if child.kind() == "block" && !config.keeps_whole(&child) {
+            result.push_str(" {\n");
+            result.push_str(&config.indent_value);
+            result.push_str("// ...");
+            if config.elision_summary {
+                result.push(' ');
+                result.push_str(&elide::elided_summary(&child));
+            }
+            result.push_str("\n}");
+        } else {
+            if child.kind() != "parameter_list"
+                && child.kind() != "func"
+                && child.kind() != "type_parameters"
+                && child.kind() != "parameters"
+            {
+                result.push(' ');
+            }
+            result.push_str(node_text(&child, source_code)?);
+        }
+    }
+    Ok(result.trim().to_string())
+}
+
+/// The text of `node`, or an error saying where it is if the node is not within `source_code`, as
+/// for a tree parsed from different source code.
+pub(crate) fn node_text<'a>(node: &ts::Node, source_code: &'a str) -> ParseResult<&'a str> {
+    source_code
+        .get(node.byte_range())
+        .ok_or_else(|| ParseError::InvalidNodeText {
+            kind: node.kind().to_string(),
+            line: node.start_position().row + 1,
+            column: node.start_position().column + 1,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::assert_eq;
+
+    #[test]
+    fn test_parse_dart() {
+        let source_code = r#"
+import 'package:flutter/material.dart';
+
+const defaultName = 'world';
+
+/// A counter that can't go below zero.
+@immutable
+class Counter extends ChangeNotifier {
+  final Map<String, int> counts = {};
+  int _value = 0;
+
+  Counter({this.name = '{unnamed}'});
+
+  int get value => _value;
+
+  void increment() {
+    _value++;
+    notifyListeners();
+  }
+
+  void reset();
+}
+
+enum Direction { up, down }
+
+Widget build(BuildContext context, {bool dense = false}) {
+  return Text('${context.hashCode}');
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Dart);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0].content, "import 'package:flutter/material.dart';");
+        assert_eq!(
+            result[1].content,
+            r#"@immutable
+class Counter extends ChangeNotifier {
+  final Map<String, int> counts = {};
+  int _value = 0;
+  Counter({this.name = '{unnamed}'});
+  int get value => ...;
+  void increment() {
+    // ...
+  }
+  void reset();
+}"#
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (6, 21));
+        assert_eq!(result[2].content, "enum Direction { up, down }");
+        assert_eq!(
+            result[3].content,
+            r#"Widget build(BuildContext context, {bool dense = false}) {
+  // ...
+}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_dart_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Dart,
+            "class Greeter {\n  // Says hello.\n  String greet(String name) {\n    return 'Hello';\n  }\n}\n",
+            &["class Greeter {\n  String greet(String name) {\n    // ...\n  }\n}"],
+        );
+    }
+
+    #[test]
+    fn test_parse_dockerfile() {
+        let source_code = r#"
+# syntax=docker/dockerfile:1
+ARG RUST_VERSION=1.70
+FROM rust:${RUST_VERSION} AS build
+WORKDIR /src
+COPY . .
+RUN apt-get update \
+    # The linker needs clang.
+    && apt-get install -y clang \
+    && rm -rf /var/lib/apt/lists/*
+RUN cargo build --release
+
+FROM debian:bookworm-slim
+ENV RUST_LOG=info \
+    PORT=8080
+EXPOSE 8080
+ENTRYPOINT ["/usr/local/bin/server"]
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Dockerfile);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 8);
+        assert_eq!(result[0].content, "ARG RUST_VERSION=1.70");
+        assert_eq!(result[1].content, "FROM rust:${RUST_VERSION} AS build");
+        assert_eq!(result[2].content, "RUN apt-get update && ... # 3 commands");
+        assert_eq!((result[2].start_line, result[2].end_line), (6, 9));
+        assert_eq!(result[3].content, "RUN cargo build --release");
+        assert_eq!(result[4].content, "FROM debian:bookworm-slim");
+        assert_eq!(result[5].content, "ENV RUST_LOG=info PORT=8080");
+        assert_eq!(result[6].content, "EXPOSE 8080");
+        assert_eq!(result[7].content, r#"ENTRYPOINT ["/usr/local/bin/server"]"#);
+    }
+
+    #[test]
+    fn test_parse_dockerfile_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Dockerfile,
+            "FROM rust:1.70\n# Build it.\nRUN cargo build\n",
+            &["FROM rust:1.70", "RUN cargo build"],
+        );
+    }
+
+    #[test]
+    fn test_parse_fsharp() {
+        let source_code = r#"
+namespace Widgets
+
+open System
+
+/// A shape to draw.
+type Shape =
+    | Circle of radius: float
+    | Square of side: float
+
+type Canvas(width: int) =
+    let mutable shapes = []
+    member this.Width = width
+    member this.Add(shape: Shape) =
+        shapes <- shape :: shapes
+        this
+
+module Geometry =
+    let pi = 3.14159
+
+    (* Areas are in square pixels. *)
+    let area shape =
+        match shape with
+        | Circle r -> pi * r * r
+        | Square s -> s * s
+
+[<EntryPoint>]
+let main argv =
+    printfn "%A" (Geometry.area (Circle 1.0))
+    0
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::FSharp);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "namespace Widgets",
+                "open System",
+                "type Shape =\n    | Circle of radius: float\n    | Square of side: float",
+                "type Canvas(width: int) =\n    let mutable shapes = []\n    member this.Width = width\n    member this.Add(shape: Shape) = ...",
+                "module Geometry =\n\n    let pi = 3.14159\n\n    let area shape = ...",
+                "[<EntryPoint>]\nlet main argv = ...",
+            ]
+        );
+        assert_eq!((result[5].start_line, result[5].end_line), (26, 29));
+    }
+
+    #[test]
+    fn test_parse_fsharp_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::FSharp,
+            "module App\n\nlet greet name =\n    // Say hello.\n    printfn \"Hello %s\" name\n",
+            &["module App", "let greet name = ..."],
+        );
+    }
+
+    #[test]
+    fn test_parse_hcl() {
+        let source_code = r#"
+terraform {
+  required_version = ">= 1.5"
+}
+
+provider "aws" {
+  region = var.region
+}
+
+variable "region" {}
+
+variable "instance_type" {
+  type    = string
+  default = "t3.micro"
+}
+
+locals {
+  name = "web-${var.region}"
+}
+
+data "aws_ami" "ubuntu" {
+  most_recent = true
+}
+
+# The web server.
+resource "aws_instance" "web" {
+  ami           = data.aws_ami.ubuntu.id
+  instance_type = var.instance_type
+  tags = {
+    Name = local.name
+  }
+
+  lifecycle { create_before_destroy = true }
+
+  provisioner "remote-exec" {
+    inline = ["sudo apt-get update"]
+  }
+}
+
+output "address" {
+  value = aws_instance.web.public_ip
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Hcl);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "provider \"aws\" {\n  region = var.region\n}",
+                "variable \"region\" {}",
+                "variable \"instance_type\" {\n  type    = string\n  default = \"t3.micro\"\n}",
+                r#"resource "aws_instance" "web" {
+  ami           = data.aws_ami.ubuntu.id
+  instance_type = var.instance_type
+  tags = {
+    Name = local.name
+  }
+
+  lifecycle { create_before_destroy = true }
+
+  provisioner "remote-exec" {
+    # ...
+  }
+}"#,
+                "output \"address\" {\n  value = aws_instance.web.public_ip\n}",
+            ]
+        );
+        assert_eq!((result[3].start_line, result[3].end_line), (25, 37));
+
+        config.set_indentation(Indentation::Tabs);
+        let result = parse(
+            "module \"vpc\" {\n  tags {\n    Name = \"vpc\"\n  }\n}",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            result[0].content,
+            "module \"vpc\" {\n  tags {\n  \t# ...\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn test_parse_hcl_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Hcl,
+            "resource \"aws_instance\" \"web\" {\n  # The image.\n  ami = \"ami-1\"\n}\n",
+            &["resource \"aws_instance\" \"web\" {\n  # The image.\n  ami = \"ami-1\"\n}"],
+        );
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let source_code = r#"
+package test
 
 import (
 	"context"
@@ -313,21 +1899,190 @@ func Setup(t *testing.T, setupConfig *SetupConfig) (*SetupFixture, error) {
     }
 
     #[test]
-    fn test_parse_rust() {
+    fn test_parse_python() {
         let source_code = r#"
-use std::collections::HashMap;
+from __future__ import annotations
+import os
+from dataclasses import dataclass
 
-pub struct Point {
-    x: f64,
-    y: f64,
-}
+import pytest
 
-pub enum Shape {
-    Circle(Point, f64),
-    Rectangle(Point, Point),
-}
+MAX_POINTS = 1_000
+_DEFAULT_SCALE: float = 1.0
+logger = logging.getLogger(__name__)
+Coordinates: TypeAlias = tuple[float, float]
+type Vector = list[float]
 
-pub type PointMap = HashMap<String, Point>;
+
+@dataclass(frozen=True)
+class Point:
+    """A point in the plane."""
+
+    x: float
+    y: float = 0.0
+
+    @property
+    def norm(self) -> float:
+        return (self.x**2 + self.y**2) ** 0.5
+
+    def scale(self, factor: float) -> Point:
+        # Scaling keeps the point frozen.
+        return Point(self.x * factor, self.y * factor)
+
+
+@app.route("/points", methods=["GET"])
+@login_required
+def list_points(request):
+    return []
+
+
+@pytest.fixture
+def origin():
+    return Point(0.0)
+
+
+async def fetch_points(client: Client) -> list[Point]:
+    return await client.get("/points")
+
+
+if __name__ == "__main__":
+    main()
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Python);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 12);
+        assert_eq!(result[0].content, "from __future__ import annotations");
+        assert_eq!(result[1].content, "import os");
+        assert_eq!(result[2].content, "from dataclasses import dataclass");
+        assert_eq!(result[3].content, "import pytest");
+        assert_eq!(result[4].content, "MAX_POINTS = 1_000");
+        assert_eq!(result[5].content, "_DEFAULT_SCALE: float = 1.0");
+        assert_eq!(
+            result[6].content,
+            "Coordinates: TypeAlias = tuple[float, float]"
+        );
+        assert_eq!(result[7].content, "type Vector = list[float]");
+        assert_eq!(
+            result[8].content,
+            r#"@dataclass(frozen=True)
+class Point:
+    """A point in the plane."""
+    x: float
+    y: float = 0.0
+    @property
+    def norm(self) -> float:
+        ...
+    def scale(self, factor: float) -> Point:
+        ..."#
+        );
+        assert_eq!((result[8].start_line, result[8].end_line), (14, 27));
+        assert_eq!(
+            result[9].content,
+            r#"@app.route("/points", methods=["GET"])
+@login_required
+def list_points(request):
+    ..."#
+        );
+        assert_eq!(
+            result[10].content,
+            r#"@pytest.fixture
+def origin():
+    ..."#
+        );
+        assert_eq!(
+            result[11].content,
+            r#"async def fetch_points(client: Client) -> list[Point]:
+    ..."#
+        );
+    }
+
+    #[test]
+    fn test_parse_python_nested() {
+        let source_code = r#"
+def make_handler(prefix):
+    count = 0
+
+    def handle(request):
+        # Handlers close over the prefix.
+        return prefix + request.path
+
+    class Handler:
+        def __call__(self, request):
+            return handle(request)
+
+        class Options:
+            timeout: int = 30
+
+    return Handler()
+
+
+class Outer:
+    class Inner:
+        @staticmethod
+        def build():
+            def helper():
+                pass
+
+            return helper
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Python);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].content,
+            r#"def make_handler(prefix):
+    def handle(request):
+        ...
+    class Handler:
+        def __call__(self, request):
+            ...
+        class Options:
+            timeout: int = 30"#
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 15));
+        assert_eq!(
+            result[1].content,
+            r#"class Outer:
+    class Inner:
+        @staticmethod
+        def build():
+            def helper():
+                ..."#
+        );
+    }
+
+    #[test]
+    fn test_parse_rust() {
+        let source_code = r#"
+use std::collections::HashMap;
+
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+pub enum Shape {
+    Circle(Point, f64),
+    Rectangle(Point, Point),
+}
+
+pub type PointMap = HashMap<String, Point>;
+
+pub const ORIGIN: Point = Point { x: 0.0, y: 0.0 };
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[macro_export]
+macro_rules! point {
+    ($x:expr, $y:expr) => {
+        Point { x: $x, y: $y }
+    };
+    ($x:expr) => {
+        point!($x, $x)
+    };
+}
 
 pub fn distance(p1: &Point, p2: &Point) -> f64 {
     // ...
@@ -340,8 +2095,9 @@ pub fn area(shape: &Shape) -> f64 {
         .trim();
         let config = default_parse_config_for_language(Language::Rust);
         let result = parse(source_code, &config).unwrap();
-        assert_eq!(result.len(), 6);
+        assert_eq!(result.len(), 9);
         assert_eq!(result[0].content, "use std::collections::HashMap;");
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 1));
         assert_eq!(
             result[1].content,
             r#"pub struct Point {
@@ -349,6 +2105,7 @@ pub fn area(shape: &Shape) -> f64 {
     y: f64,
 }"#
         );
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 6));
         assert_eq!(
             result[2].content,
             r#"pub enum Shape {
@@ -362,15 +2119,2611 @@ pub fn area(shape: &Shape) -> f64 {
         );
         assert_eq!(
             result[4].content,
+            "pub const ORIGIN: Point = Point { x: 0.0, y: 0.0 };"
+        );
+        assert_eq!(
+            result[5].content,
+            "static COUNTER: AtomicUsize = AtomicUsize::new(0);"
+        );
+        assert_eq!(
+            result[6].content,
+            r#"#[macro_export]
+macro_rules! point {
+    ($x:expr, $y:expr) => { ... };
+    ($x:expr) => { ... };
+}"#
+        );
+        assert_eq!((result[6].start_line, result[6].end_line), (19, 27));
+        assert_eq!(
+            result[7].content,
             r#"pub fn distance(p1: &Point, p2: &Point) -> f64 {
     // ...
 }"#
         );
         assert_eq!(
-            result[5].content,
+            result[8].content,
             r#"pub fn area(shape: &Shape) -> f64 {
     // ...
 }"#
         );
     }
+
+    #[test]
+    fn test_parse_item_kinds() {
+        let source_code = r#"
+use std::fmt;
+
+/// A point.
+pub struct Point {
+    x: f64,
+}
+
+pub const ORIGIN: f64 = 0.0;
+
+pub fn distance(p1: &Point, p2: &Point) -> f64 {
+    (p1.x - p2.x).abs()
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_include_doc_comments(true);
+        let result = parse(source_code, &config).unwrap();
+        let items = result
+            .iter()
+            .map(|key_content| (key_content.kind, key_content.name.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![
+                (ItemKind::Import, None),
+                (ItemKind::Type, Some("Point")),
+                (ItemKind::Constant, Some("ORIGIN")),
+                (ItemKind::Function, Some("distance")),
+            ]
+        );
+        for key_content in &result {
+            assert!(source_code[key_content.byte_range.clone()]
+                .starts_with(key_content.content.lines().next().unwrap()));
+        }
+        assert_eq!(
+            &source_code[result[1].byte_range.clone()],
+            "/// A point.\npub struct Point {\n    x: f64,\n}"
+        );
+
+        let config = default_parse_config_for_language(Language::Toml);
+        let source_code = "title = \"Example\"\n\n[server]\nhost = \"localhost\"\n";
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result[1].kind, ItemKind::Other);
+        assert_eq!(
+            &source_code[result[1].byte_range.clone()],
+            "[server]\nhost = \"localhost\""
+        );
+    }
+
+    #[test]
+    fn test_outline() {
+        let source_code = r#"
+use std::fmt;
+
+pub const ORIGIN: f64 = 0.0;
+
+pub enum Shape {
+    Circle(f64),
+}
+
+impl Shape {
+    pub fn area(&self) -> f64 {
+        let square = |x: f64| x * x;
+        square(1.0)
+    }
+}
+
+mod tests {
+    fn test_area() {}
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Rust);
+        let symbols = outline(source_code, &config)
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind, symbol.start_line, symbol.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            vec![
+                ("ORIGIN".to_string(), ItemKind::Constant, 3, 0),
+                ("Shape".to_string(), ItemKind::Type, 5, 0),
+                ("area".to_string(), ItemKind::Function, 10, 0),
+                ("tests".to_string(), ItemKind::Module, 16, 0),
+                ("test_area".to_string(), ItemKind::Function, 17, 1),
+            ]
+        );
+
+        let source_code = r#"
+class Shape:
+    @property
+    def area(self):
+        return 0
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Python);
+        let symbols = outline(source_code, &config);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!((symbols[1].name.as_str(), symbols[1].depth), ("area", 1));
+        assert_eq!((symbols[1].start_line, symbols[1].end_line), (2, 4));
+
+        let config = default_parse_config_for_language(Language::Toml);
+        assert!(outline("[package]", &config).is_empty());
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let source_code = r#"
+fn area(shape: &Shape) -> f64 {
+    shape.width() * shape.height()
+}
+
+fn total(shapes: &[Shape]) -> f64 {
+    let sum = shapes.iter().map(area).sum::<f64>();
+    fn round(x: f64) -> f64 {
+        x.round()
+    }
+    round(sum) + geometry::area(&shapes[0]) + parse::<f64>("0").unwrap()
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Rust);
+        let functions = function_calls(source_code, &config);
+        let calls = functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.calls.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            vec![
+                ("area", vec!["width".to_string(), "height".to_string()]),
+                (
+                    "total",
+                    vec![
+                        "sum".to_string(),
+                        "map".to_string(),
+                        "iter".to_string(),
+                        "round".to_string(),
+                        "area".to_string(),
+                        "unwrap".to_string(),
+                        "parse".to_string(),
+                    ]
+                ),
+                ("round", vec!["round".to_string()]),
+            ]
+        );
+        assert_eq!((functions[1].start_line, functions[1].end_line), (5, 11));
+
+        let source_code = r#"
+class Shape:
+    def area(self):
+        return self.width() * helpers.height(self)
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Python);
+        let functions = function_calls(source_code, &config);
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].calls, vec!["width", "height"]);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let source_code = r#"
+// Shapes.
+pub enum Shape {
+    Circle(f64),
+    Square(f64),
+}
+
+/* Areas
+   of shapes. */
+pub fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle(r) => 3.14 * r * r,
+        Shape::Square(s) if *s > 0.0 && s.is_finite() => s * s,
+        Shape::Square(_) => 0.0,
+    }
+}
+
+fn total(shapes: &[Shape]) -> f64 {
+    let mut sum = 0.0;
+    for shape in shapes {
+        sum += area(shape);
+    }
+    sum
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Rust);
+        assert_eq!(
+            metrics(source_code, &config),
+            Some(FileMetrics {
+                lines_of_code: 18,
+                functions: 2,
+                types: 1,
+                complexity: 7,
+            })
+        );
+
+        let config = default_parse_config_for_language(Language::Python);
+        let metrics = metrics("x = 1 if y else 2\n", &config).unwrap();
+        assert_eq!((metrics.functions, metrics.complexity), (0, 2));
+
+        let config = default_parse_config_for_language(Language::Toml);
+        assert_eq!(super::metrics("[package]", &config), None);
+    }
+
+    #[test]
+    fn test_parse_syntax_errors() {
+        let source_code = r#"
+fn main() {
+    let x = 1
+    println!("{}", x);
+}
+
+fn broken( {
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        let parsed = parse_with_syntax_errors(source_code, &config).unwrap();
+        assert!(!parsed.key_contents.is_empty());
+        let lines = parsed
+            .syntax_errors
+            .iter()
+            .map(|error| error.start_line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 6]);
+        assert_eq!(parsed.syntax_errors[0].missing, None);
+        assert_eq!(
+            parsed.syntax_errors[0].to_string(),
+            "line 2: unexpected syntax"
+        );
+        assert_eq!(parsed.syntax_errors[1].missing.as_deref(), Some(")"));
+        assert_eq!(parsed.syntax_errors[1].to_string(), "line 6: missing `)`");
+
+        let parsed = parse_with_syntax_errors("fn main() {}", &config).unwrap();
+        assert!(parsed.syntax_errors.is_empty());
+        assert!(parsed.diagnostics.nodes > 0);
+
+        config.set_fail_on_syntax_errors(true);
+        match parse(source_code, &config) {
+            Err(ParseError::SyntaxErrors(errors)) => assert_eq!(errors.len(), 2),
+            _ => panic!("expected syntax errors"),
+        }
+        assert!(parse("fn main() {}", &config).is_ok());
+    }
+
+    #[test]
+    fn test_parse_with_pool() {
+        let config = default_parse_config_for_language(Language::Rust);
+        let pool = ParserPool::new();
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let (config, pool) = (&config, &pool);
+                scope.spawn(move || {
+                    let source_code = format!("fn f{}() {{\n    run();\n}}", i);
+                    let parsed = parse_with_pool(&source_code, config, pool).unwrap();
+                    assert_eq!(parsed.key_contents[0].name, Some(format!("f{}", i)));
+                });
+            }
+        });
+        let idle = pool.idle_parsers();
+        assert!((1..=4).contains(&idle));
+
+        parse_with_pool("fn main() {}", &config, &pool).unwrap();
+        assert_eq!(pool.idle_parsers(), idle);
+    }
+
+    #[test]
+    fn test_parse_timeout_and_cancellation() {
+        let source_code = format!(
+            "const NESTED: u32 = {}1{};",
+            "(".repeat(50_000),
+            ")".repeat(50_000)
+        );
+        let mut config = default_parse_config_for_language(Language::Rust);
+        let pool = ParserPool::new();
+        config.set_timeout(Some(Duration::from_micros(1)));
+        assert!(matches!(
+            parse_with_pool(&source_code, &config, &pool),
+            Err(ParseError::TimedOut(timeout)) if timeout == Duration::from_micros(1)
+        ));
+
+        // The parser that timed out is reused without the timeout.
+        config.set_timeout(None);
+        assert!(parse_with_pool(&source_code, &config, &pool).is_ok());
+        assert_eq!(pool.idle_parsers(), 1);
+
+        let token = CancellationToken::new();
+        let parsed = parse_cancellable("fn main() {}", &config, &pool, Some(&token)).unwrap();
+        assert_eq!(parsed.key_contents.len(), 1);
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(
+            parse_cancellable("fn main() {}", &config, &pool, Some(&token)),
+            Err(ParseError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors_say_where() {
+        let source_code = "fn main() {}\n\nstruct Point;\n";
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.add_selector(Selector::new(
+            "struct_item",
+            SelectorAction::Custom(Box::new(|_node, _cursor, _source_code| {
+                Err(ParseError::CustomActionFailed("no fields".into()))
+            })),
+        ));
+        let error = match parse(source_code, &config) {
+            Err(error) => error,
+            Ok(_) => panic!("expected the custom selector to fail"),
+        };
+        assert!(matches!(
+            &error,
+            ParseError::SelectorFailed { kind, line: 3, column: 1, source }
+                if kind == "struct_item"
+                    && matches!(**source, ParseError::CustomActionFailed(_))
+        ));
+        assert_eq!(
+            error.to_string(),
+            "custom selector for `struct_item` at line 3, column 1 failed: custom selector action \
+             failed: no fields"
+        );
+
+        let Grammar::TreeSitter(language_config) = &config.grammar else {
+            unreachable!()
+        };
+        let tree = to_tree(source_code, language_config, ParserPool::global()).unwrap();
+        let function = tree.root_node().child(0).unwrap();
+        assert_eq!(node_text(&function, source_code).unwrap(), "fn main() {}");
+        assert!(matches!(
+            node_text(&function, "fn"),
+            Err(ParseError::InvalidNodeText { kind, line: 1, column: 1 }) if kind == "function_item"
+        ));
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let rust = default_parse_config_for_language(Language::Rust);
+        let python = default_parse_config_for_language(Language::Python);
+        let sources = (0..16)
+            .map(|i| match i % 2 {
+                0 => format!("fn f{}() {{\n    run();\n}}", i),
+                _ => format!("def f{}():\n    run()\n", i),
+            })
+            .collect::<Vec<_>>();
+        let inputs = sources
+            .iter()
+            .enumerate()
+            .map(|(i, source_code)| {
+                let config = if i % 2 == 0 { &rust } else { &python };
+                (source_code.as_str(), config)
+            })
+            .collect::<Vec<_>>();
+        let pool = ParserPool::new();
+        let results = parse_many(&inputs, &pool, 4);
+        assert_eq!(results.len(), 16);
+        for (i, result) in results.into_iter().enumerate() {
+            let parsed = result.unwrap();
+            assert_eq!(parsed.key_contents[0].name, Some(format!("f{}", i)));
+        }
+        assert!((1..=8).contains(&pool.idle_parsers()));
+
+        assert!(parse_many(&[], &pool, 4).is_empty());
+        let results = parse_many(&[("fn main() {}", &rust)], &pool, 0);
+        assert_eq!(results[0].as_ref().unwrap().key_contents.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let registry = LanguageRegistry::new();
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let digested = parse_file(&directory.join("src/registry.rs"), &registry).unwrap();
+        assert_eq!(digested.language, Language::Rust);
+        assert!(digested.syntax_errors.is_empty());
+        assert!(digested
+            .key_contents
+            .iter()
+            .any(|key_content| key_content.name.as_deref() == Some("LanguageRegistry")));
+
+        assert!(matches!(
+            parse_file(&directory.join("../LICENSE"), &registry),
+            Err(ParseError::UnknownLanguage(path)) if path.ends_with("LICENSE")
+        ));
+        assert!(matches!(
+            parse_file(&directory.join("missing.rs"), &registry),
+            Err(ParseError::ReadingFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rust_modules() {
+        let source_code = r#"
+mod geometry;
+
+pub mod shapes {
+    use super::Point;
+
+    pub const SIDES: usize = 4;
+
+    pub fn area(width: f64, height: f64) -> f64 {
+        width * height
+    }
+
+    impl Point {
+        fn origin() -> Point {
+            Point::default()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn test_area() {
+            assert_eq!(super::area(2.0, 3.0), 6.0);
+        }
+    }
+}
+
+mod empty {}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Rust);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].content,
+            r#"pub mod shapes {
+    use super::Point;
+
+    pub const SIDES: usize = 4;
+
+    pub fn area(width: f64, height: f64) -> f64 {
+        // ...
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn test_area() {
+            // ...
+        }
+    }
+}"#
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (3, 25));
+        assert_eq!(result[1].content, "mod empty {}");
+    }
+
+    #[test]
+    fn test_parse_granularity() {
+        let source_code = r#"
+pub struct Point {
+    x: f64,
+}
+
+pub fn norm(point: &Point) -> f64 {
+    point.x.abs()
+}
+"#
+        .trim();
+        let contents = |granularity: Granularity| {
+            let mut config = default_parse_config_for_language(Language::Rust);
+            config.set_granularity(granularity);
+            parse(source_code, &config)
+                .unwrap()
+                .into_iter()
+                .map(|key_content| key_content.content)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            contents(Granularity::Signatures),
+            vec!["pub struct Point", "pub fn norm(point: &Point) -> f64"]
+        );
+        assert_eq!(
+            contents(Granularity::Types),
+            vec![
+                "pub struct Point {\n    x: f64,\n}",
+                "pub fn norm(point: &Point) -> f64 {\n    // ...\n}"
+            ]
+        );
+        assert_eq!(
+            contents(Granularity::SmallBodies),
+            vec![
+                "pub struct Point {\n    x: f64,\n}",
+                "pub fn norm(point: &Point) -> f64 {\n    point.x.abs()\n}"
+            ]
+        );
+        assert_eq!(contents(Granularity::Full), vec![source_code]);
+
+        let config = parse_config_from_toml(Language::Markdown, "granularity = \"full\"").unwrap();
+        let parsed = parse("Some notes\nwithout headings\n", &config).unwrap();
+        assert_eq!(parsed[0].content, "Some notes\nwithout headings");
+        assert_eq!((parsed[0].start_line, parsed[0].end_line), (1, 2));
+    }
+
+    #[test]
+    fn test_parse_rust_attributes() {
+        let source_code = r#"
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Settings {
+    pub name: String,
+}
+
+/// Load settings from the environment.
+#[cfg(feature = "env")]
+#[must_use]
+pub fn from_env() -> Settings {
+    Settings { name: std::env::var("NAME").unwrap() }
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "#[derive(Debug, Deserialize)]\n#[serde(rename_all = \"snake_case\")]\npub struct Settings {\n    pub name: String,\n}"
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 5));
+        assert_eq!(
+            result[1].content,
+            "#[cfg(feature = \"env\")]\n#[must_use]\npub fn from_env() -> Settings {\n    // ...\n}"
+        );
+        assert_eq!(result[1].start_line, 8);
+
+        config.set_signatures_only(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[1].content,
+            "/// Load settings from the environment.\n#[cfg(feature = \"env\")]\n#[must_use]\npub fn from_env() -> Settings"
+        );
+        assert_eq!(result[1].start_line, 7);
+    }
+
+    #[test]
+    fn test_parse_doc_comments() {
+        let source_code = r#"
+//! Geometry primitives.
+
+/// A point in the plane.
+#[derive(Debug)]
+pub struct Point {
+    x: f64, // metres
+}
+
+// Not a doc comment.
+pub fn origin() -> Point {
+    Point { x: 0.0 }
+}
+
+/// The distance between two points.
+
+pub fn distance(p1: &Point, p2: &Point) -> f64 {
+    (p1.x - p2.x).abs()
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "#[derive(Debug)]\npub struct Point {\n    x: f64, // metres\n}"
+        );
+
+        config.set_include_doc_comments(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0].content, "//! Geometry primitives.");
+        assert_eq!(
+            result[1].content,
+            r#"/// A point in the plane.
+#[derive(Debug)]
+pub struct Point {
+    x: f64, // metres
+}"#
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 7));
+        assert!(result[2].content.starts_with("pub fn origin() -> Point {"));
+        assert!(result[3].content.starts_with("pub fn distance("));
+
+        let source_code = r#"
+def area(width, height):
+    """The area of a rectangle."""
+    return width * height
+
+
+class Shape:
+    """A shape with an area."""
+
+    def area(self):
+        """The area of the shape."""
+        raise NotImplementedError
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_include_doc_comments(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            r#"def area(width, height):
+    """The area of a rectangle."""
+    ..."#
+        );
+        assert_eq!(
+            result[1].content,
+            r#"class Shape:
+    """A shape with an area."""
+    def area(self):
+        """The area of the shape."""
+        ..."#
+        );
+    }
+
+    #[test]
+    fn test_parse_leading_comments() {
+        let source_code = r#"
+// Retries because the registry is flaky.
+fn fetch() {
+    run();
+}
+
+// Unrelated note.
+
+/// Sends the request.
+fn send() {
+    run();
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_include_doc_comments(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result[0].content, "fn fetch() {\n    // ...\n}");
+        assert_eq!(result[0].start_line, 2);
+
+        let config = parse_config_from_toml(Language::Rust, "leading_comments = true").unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "// Retries because the registry is flaky.\nfn fetch() {\n    // ...\n}"
+        );
+        assert_eq!(result[0].start_line, 1);
+        assert_eq!(
+            result[1].content,
+            "/// Sends the request.\nfn send() {\n    // ...\n}"
+        );
+    }
+
+    #[test]
+    fn test_parse_max_depth() {
+        let source_code = r#"
+class Shape:
+    def area(self):
+        return 0
+
+def main():
+    pass
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "class Shape:\n    def area(self):\n        ..."
+        );
+
+        config.set_max_depth(Some(1));
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec!["class Shape:\n    ...", "def main():\n    ..."]
+        );
+
+        config.set_max_depth(Some(0));
+        assert!(parse(source_code, &config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_elision_threshold() {
+        let source_code = r#"
+pub fn x(&self) -> f64 { self.x }
+
+pub fn norm(&self) -> f64 {
+    (self.x * self.x + self.y * self.y).sqrt()
+}
+
+pub fn scale(&mut self, factor: f64) {
+    self.x *= factor;
+    self.y *= factor;
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_elision_threshold(3);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].content, "pub fn x(&self) -> f64 { self.x }");
+        assert_eq!(
+            result[1].content,
+            "pub fn norm(&self) -> f64 {\n    (self.x * self.x + self.y * self.y).sqrt()\n}"
+        );
+        assert_eq!(
+            result[2].content,
+            "pub fn scale(&mut self, factor: f64) {\n    // ...\n}"
+        );
+
+        let source_code = r#"
+def name(self):
+    return self._name
+
+
+def rename(self, name):
+    self._name = name
+    self.changed = True
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_elision_threshold(1);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result[0].content, "def name(self):\n    return self._name");
+        assert_eq!(result[1].content, "def rename(self, name):\n    ...");
+    }
+
+    #[test]
+    fn test_parse_elision_summary() {
+        let source_code = r#"
+pub fn scale(&mut self, factor: f64) {
+    self.x *= factor;
+
+    // Keep the length in step.
+    self.length = self.norm() * factor;
+    self.notify(Event::Scaled);
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_elision_summary(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "pub fn scale(&mut self, factor: f64) {\n    // ... (4 lines, 2 calls elided)\n}"
+        );
+
+        let source_code = r#"
+def rename(self, name):
+    self._name = name
+    self.notify(name)
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_elision_summary(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "def rename(self, name):\n    ... (2 lines, 1 call elided)"
+        );
+
+        let mut config = ParseConfig::new(Language::Rust, Indentation::Spaces(4));
+        config
+            .add_query("(function_item body: (block) @elide) @capture")
+            .unwrap();
+        config.set_elision_summary(true);
+        let result = parse("fn one() -> u32 {\n    1\n}", &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "fn one() -> u32 {\n    // ... (1 line, 0 calls elided)\n}"
+        );
+    }
+
+    #[test]
+    fn test_parse_strip_comments() {
+        let source_code = r#"
+/// A point in the plane.
+pub struct Point {
+    // Horizontal.
+    x: f64,
+    y: f64, // Vertical.
+    z: /* unused */ f64,
+}
+
+impl Point {
+    /// How far from the origin.
+    pub fn norm(&self) -> f64 {
+        // Pythagoras.
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_include_doc_comments(true);
+        config.set_strip_comments(true);
+        config.add_selector(Selector::new("impl_item", SelectorAction::CaptureAll));
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "/// A point in the plane.\npub struct Point {\n    x: f64,\n    y: f64,\n    z: f64,\n}"
+        );
+        assert_eq!(
+            result[1].content,
+            "impl Point {\n    pub fn norm(&self) -> f64 {\n        (self.x * self.x + self.y * self.y).sqrt()\n    }\n}"
+        );
+
+        let source_code = r#"
+def area(width, height):
+    # Rectangles only.
+    return width * height  # Square units.
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_elision_threshold(3);
+        config.set_strip_comments(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "def area(width, height):\n    return width * height"
+        );
+    }
+
+    #[test]
+    fn test_parse_signatures_only() {
+        let source_code = r#"
+use std::fmt;
+
+/// A point in the plane.
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+/// How far `point` is from the origin.
+pub fn norm(point: &Point) -> f64 {
+    (point.x * point.x + point.y * point.y).sqrt()
+}
+
+pub fn origin() -> Point {
+    Point { x: 0.0, y: 0.0 }
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_signatures_only(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result
+                .iter()
+                .map(|r| r.content.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "use std::fmt;",
+                "/// A point in the plane.\npub struct Point",
+                "/// How far `point` is from the origin.\npub fn norm(point: &Point) -> f64",
+                "pub fn origin() -> Point",
+            ]
+        );
+
+        let source_code = r#"
+class Shape:
+    """A shape in the plane."""
+
+    def area(self):
+        """The area of the shape."""
+        raise NotImplementedError
+
+    def _check(self):
+        pass
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_signatures_only(true);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "class Shape:\n    \"\"\"A shape in the plane.\"\"\"\n    \
+             def area(self):\n        \"\"\"The area of the shape.\"\"\"\n    def _check(self):"
+        );
+    }
+
+    #[test]
+    fn test_parse_rust_query() {
+        let source_code = r#"
+use std::fmt;
+
+fn helper() -> u32 {
+    1
+}
+
+pub fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle(_, r) => r * r,
+    }
+}
+
+pub fn test_area() {
+    assert_eq!(area(&circle()), 1.0);
+}
+"#
+        .trim();
+        let mut config = ParseConfig::new(Language::Rust, Indentation::Spaces(4));
+        config.add_query("(use_declaration) @capture").unwrap();
+        config
+            .add_query(
+                r#"(function_item
+                    (visibility_modifier)
+                    name: (identifier) @name
+                    body: (block) @elide
+                    (#not-match? @name "^test_")) @capture"#,
+            )
+            .unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "use std::fmt;");
+        assert_eq!(
+            result[1].content,
+            r#"pub fn area(shape: &Shape) -> f64 {
+    // ...
+}"#
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (7, 11));
+
+        assert!(matches!(
+            config.add_query("(function_item) @item"),
+            Err(ParseError::QueryWithoutCapture)
+        ));
+        assert!(matches!(
+            config.add_query("(function_item"),
+            Err(ParseError::QueryError(_))
+        ));
+        let mut config = ParseConfig::new(Language::Toml, Indentation::Spaces(4));
+        assert!(matches!(
+            config.add_query("(table) @capture"),
+            Err(ParseError::ScannerUnsupported("queries"))
+        ));
+    }
+
+    #[test]
+    fn test_selector_priority() {
+        let source_code = "fn one() -> u32 {\n    1\n}";
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.add_selector(Selector::new("function_item", SelectorAction::CaptureAll));
+        assert_eq!(
+            parse(source_code, &config).unwrap()[0].content,
+            "fn one() -> u32 {\n    1\n}"
+        );
+
+        // A higher priority comes first, however recently the others were added.
+        config.add_selector(
+            Selector::new("function_item", SelectorAction::CaptureWithoutBlock).with_priority(1),
+        );
+        config.add_selector(Selector::new("function_item", SelectorAction::CaptureAll));
+        assert_eq!(
+            parse(source_code, &config).unwrap()[0].content,
+            "fn one() -> u32 {\n    // ...\n}"
+        );
+
+        config.remove_selector("function_item");
+        assert!(config.get_selector_action("function_item").is_none());
+    }
+
+    #[test]
+    fn test_selector_predicates() {
+        let source_code = r#"
+func HandleLogin(w http.ResponseWriter, r *http.Request) {
+	authenticate(r)
+}
+
+func authenticate(r *http.Request) {
+	check(r)
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Go);
+        config.remove_selector("function_declaration");
+        config.add_selector(
+            Selector::new("function_declaration", SelectorAction::CaptureWithoutBlock)
+                .with_name_matching("^Handle")
+                .unwrap(),
+        );
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name.as_deref(), Some("HandleLogin"));
+
+        let source_code = r#"
+fn is_empty(&self) -> bool {
+    self.len() == 0
+}
+
+fn len(&self) -> usize {
+    self.items.len()
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.add_selector(
+            Selector::new("function_item", SelectorAction::CaptureAll)
+                .with_field_equals("return_type", "bool"),
+        );
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[0].content,
+            "fn is_empty(&self) -> bool {\n    self.len() == 0\n}"
+        );
+        assert_eq!(result[1].content, "fn len(&self) -> usize {\n    // ...\n}");
+        assert!(matches!(
+            config.get_selector_action("function_item"),
+            Some(SelectorAction::CaptureWithoutBlock)
+        ));
+
+        assert!(matches!(
+            Selector::new("function_item", SelectorAction::CaptureAll).with_name_matching("("),
+            Err(ParseError::InvalidNamePattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rust_config_file() {
+        let source_code = r#"
+use std::fmt;
+
+pub type Id = u64;
+
+pub fn id() -> Id {
+    1
+}
+
+impl Id {
+    fn next() {}
+}
+"#
+        .trim();
+        let config = parse_config_from_toml(
+            Language::Rust,
+            r#"
+queries = ["(impl_item) @capture"]
+
+[selectors]
+type_item = "skip"
+function_item = "capture_all"
+"#,
+        )
+        .unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].content, "use std::fmt;");
+        assert_eq!(result[1].content, "pub fn id() -> Id {\n    1\n}");
+        assert_eq!(result[2].content, "impl Id {\n    fn next() {}\n}");
+
+        let config = parse_config_from_toml(
+            Language::Rust,
+            r#"
+defaults = false
+indentation = "tabs"
+
+[selectors]
+source_file = "select_only"
+function_item = "capture_without_block"
+"#,
+        )
+        .unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content, "pub fn id() -> Id {\n\t// ...\n}");
+
+        // A selector of lower priority than the default is only a fallback.
+        let config = parse_config_from_toml(
+            Language::Rust,
+            r#"
+[selectors]
+function_item = { action = "capture_all", priority = -1 }
+"#,
+        )
+        .unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result[2].content, "pub fn id() -> Id {\n    // ...\n}");
+
+        let config = parse_config_from_toml(
+            Language::Rust,
+            r#"
+[selectors]
+function_item = [{ action = "skip" }, { action = "capture_all", name = "^i" }]
+"#,
+        )
+        .unwrap();
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result
+                .iter()
+                .map(|r| r.content.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "use std::fmt;",
+                "pub type Id = u64;",
+                "pub fn id() -> Id {\n    1\n}"
+            ]
+        );
+
+        assert!(matches!(
+            parse_config_from_toml(Language::Rust, "[selectors]\nfunction_item = \"elide\""),
+            Err(ParseError::InvalidConfigFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_config_from_toml_scanner() {
+        let config = parse_config_from_toml(Language::Sql, "indentation = \"tabs\"").unwrap();
+        assert_eq!(config.indent_value, "\t");
+        assert!(parse_config_from_toml(Language::Yaml, "granularity = \"full\"").is_ok());
+
+        let unsupported = [
+            ("doc_comments = true", "doc_comments"),
+            ("elision_summary = true", "elision_summary"),
+            ("elision_threshold = 3", "elision_threshold"),
+            ("granularity = \"small_bodies\"", "granularity"),
+            (
+                "injections = [\"(string) @injection.content\"]",
+                "injections",
+            ),
+            ("leading_comments = true", "leading_comments"),
+            ("max_depth = 1", "max_depth"),
+            ("queries = [\"(table) @capture\"]", "queries"),
+            ("[selectors]\ntable = \"capture_all\"", "selectors"),
+            ("signatures_only = true", "signatures_only"),
+            ("strip_comments = false", "strip_comments"),
+            ("tests = \"skip\"", "tests"),
+        ];
+        for language in [
+            Language::Clojure,
+            Language::CMake,
+            Language::Dart,
+            Language::Dockerfile,
+            Language::FSharp,
+            Language::Hcl,
+            Language::Json,
+            Language::Julia,
+            Language::Jupyter,
+            Language::Make,
+            Language::Markdown,
+            Language::Nix,
+            Language::Proto,
+            Language::Sql,
+            Language::Svelte,
+            Language::Toml,
+            Language::Unknown,
+            Language::Yaml,
+        ] {
+            for (source, setting) in unsupported {
+                match parse_config_from_toml(language, source) {
+                    Err(e @ ParseError::ScannerUnsupported(_)) => assert_eq!(
+                        e.to_string(),
+                        format!(
+                            "language is parsed by a scanner, which does not support {}",
+                            setting
+                        )
+                    ),
+                    _ => panic!("{:?} accepted `{}`", language, source),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_bash() {
+        let source_code = r#"
+#!/usr/bin/env bash
+# Deploy script
+set -euo pipefail
+
+VERSION="1.2.3"
+export PREFIX=/usr/local
+
+build() {
+    make all
+}
+
+function deploy {
+    echo "$VERSION"
+}
+
+build
+deploy
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Bash);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 5);
+        assert_eq!(result[0].content, "#!/usr/bin/env bash");
+        assert_eq!(result[1].content, r#"VERSION="1.2.3""#);
+        assert_eq!(result[2].content, "export PREFIX=/usr/local");
+        assert_eq!(
+            result[3].content,
+            r#"build() {
+    # ...
+}"#
+        );
+        assert_eq!(
+            result[4].content,
+            r#"function deploy {
+    # ...
+}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_clojure() {
+        let source_code = r#"
+(ns widgets.core
+  (:require [clojure.string :as str]))
+
+;; The default size, in pixels.
+(def ^:private default-size 12)
+
+(defn render
+  "Render a widget to a string."
+  [widget]
+  (str/join \( (:children widget)))
+
+(defn resize
+  ([widget] (resize widget default-size))
+  ([widget size] (assoc widget :size size)))
+
+(defrecord Button [label on-click]
+  Object
+  (toString [_] label))
+
+(comment
+  (render {:children []}))
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Clojure);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "(ns widgets.core\n  (:require [clojure.string :as str]))",
+                "(def default-size ...)",
+                "(defn render\n  \"Render a widget to a string.\"\n  [widget] ...)",
+                "(defn resize\n  ([widget] ...)\n  ([widget size] ...))",
+                "(defrecord Button [label on-click] ...)",
+            ]
+        );
+        assert_eq!((result[2].start_line, result[2].end_line), (7, 10));
+    }
+
+    #[test]
+    fn test_parse_clojure_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Clojure,
+            "(ns app.core)\n\n(defn greet [name]\n  ;; Say hello.\n  (str \"Hello, \" name))\n",
+            &["(ns app.core)", "(defn greet [name] ...)"],
+        );
+    }
+
+    #[test]
+    fn test_parse_cmake() {
+        let source_code = r#"
+cmake_minimum_required(VERSION 3.20)
+project(widgets VERSION 1.0 LANGUAGES CXX)
+
+option(WIDGETS_BUILD_TESTS "Build the tests (slow)" ON)
+
+add_library(widgets
+    src/widget.cpp
+    src/button.cpp)
+target_link_libraries(widgets PUBLIC fmt::fmt)
+
+if(WIDGETS_BUILD_TESTS)
+  add_executable(widgets_test test/widget_test.cpp)
+endif()
+
+add_custom_target(docs ALL
+    COMMAND doxygen ${CMAKE_SOURCE_DIR}/Doxyfile
+    WORKING_DIRECTORY ${CMAKE_BINARY_DIR})
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::CMake);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 6);
+        assert_eq!(
+            result[0].content,
+            "project(widgets VERSION 1.0 LANGUAGES CXX)"
+        );
+        assert_eq!(
+            result[1].content,
+            r#"option(WIDGETS_BUILD_TESTS "Build the tests (slow)" ON)"#
+        );
+        assert_eq!(
+            result[2].content,
+            r#"add_library(widgets
+    src/widget.cpp
+    src/button.cpp)"#
+        );
+        assert_eq!((result[2].start_line, result[2].end_line), (6, 8));
+        assert_eq!(
+            result[3].content,
+            "target_link_libraries(widgets PUBLIC fmt::fmt)"
+        );
+        assert_eq!(
+            result[4].content,
+            "add_executable(widgets_test test/widget_test.cpp)"
+        );
+        assert_eq!(result[5].content, "add_custom_target(docs ALL ...)");
+    }
+
+    #[test]
+    fn test_parse_cmake_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::CMake,
+            "project(app)\n# The binary.\nadd_executable(app main.cpp)\n",
+            &["project(app)", "add_executable(app main.cpp)"],
+        );
+    }
+
+    #[test]
+    fn test_parse_cpp() {
+        let source_code = r#"
+#include <vector>
+
+namespace outer {
+namespace inner {
+
+template <typename T>
+class Box {
+public:
+    T get() const { return value_; }
+    void set(T value);
+
+private:
+    T value_;
+};
+
+}  // namespace inner
+
+int add(int a, int b) {
+    return a + b;
+}
+
+}  // namespace outer
+
+struct Point {
+    int x;
+    int y;
+};
+
+int declared(int x);
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Cpp);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0].content, "#include <vector>");
+        assert_eq!(
+            result[1].content,
+            r#"namespace outer {
+namespace inner {
+
+template <typename T>
+class Box {
+public:
+    T get() const {
+        // ...
+    }
+    void set(T value);
+
+private:
+    T value_;
+};
+
+}  // namespace inner
+
+int add(int a, int b) {
+    // ...
+}
+
+}"#
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 22));
+        assert_eq!(
+            result[2].content,
+            r#"struct Point {
+    int x;
+    int y;
+};"#
+        );
+        assert_eq!(result[3].content, "int declared(int x);");
+    }
+
+    #[test]
+    fn test_parse_sql() {
+        let source_code = r#"
+-- Users and their sessions.
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL DEFAULT 'anonymous; user'
+);
+
+CREATE UNIQUE INDEX users_name ON users (name);
+
+INSERT INTO users (name) VALUES ('admin');
+
+CREATE OR REPLACE VIEW active_users AS SELECT * FROM users;
+
+CREATE FUNCTION user_count() RETURNS integer AS $$
+BEGIN
+    RETURN (SELECT count(*) FROM users);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE PROCEDURE purge()
+BEGIN
+    IF 1 = 1 THEN
+        DELETE FROM users;
+    END IF;
+END;
+
+DELIMITER //
+CREATE PROCEDURE touch()
+BEGIN
+    UPDATE users SET name = name;
+END //
+DELIMITER ;
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Sql);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 6);
+        assert_eq!(
+            result[0].content,
+            r#"CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL DEFAULT 'anonymous; user'
+);"#
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (2, 5));
+        assert_eq!(
+            result[1].content,
+            "CREATE UNIQUE INDEX users_name ON users (name);"
+        );
+        assert_eq!(
+            result[2].content,
+            "CREATE OR REPLACE VIEW active_users AS SELECT * FROM users;"
+        );
+        assert_eq!(
+            result[3].content,
+            r#"CREATE FUNCTION user_count() RETURNS integer AS $$
+    -- ...
+$$ LANGUAGE plpgsql;"#
+        );
+        assert_eq!(
+            result[4].content,
+            r#"CREATE PROCEDURE purge()
+BEGIN
+    -- ...
+END;"#
+        );
+        assert_eq!(
+            result[5].content,
+            r#"CREATE PROCEDURE touch()
+BEGIN
+    -- ...
+END;"#
+        );
+    }
+
+    /// Assert that parsing `source_code` in `language`, which is parsed by a scanner, captures
+    /// `expected` whatever the settings that only apply to tree-sitter grammars, and the whole source
+    /// code at `Granularity::Full`.
+    fn assert_scanner_ignores_settings(language: Language, source_code: &str, expected: &[&str]) {
+        let mut config = default_parse_config_for_language(language);
+        assert!(config.is_scanned());
+        let contents = |config: &ParseConfig| {
+            parse(source_code, config)
+                .unwrap()
+                .into_iter()
+                .map(|key_content| key_content.content)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&config), expected);
+
+        config.add_selector(Selector::new("comment", SelectorAction::CaptureAll));
+        config.set_include_doc_comments(true);
+        config.set_include_leading_comments(true);
+        config.set_granularity(Granularity::SmallBodies);
+        config.set_elision_summary(true);
+        config.set_strip_comments(true);
+        config.set_signatures_only(true);
+        config.set_test_code(TestCode::Skip);
+        config.set_max_depth(Some(0));
+        config.set_fail_on_syntax_errors(true);
+        assert_eq!(contents(&config), expected);
+
+        config.set_granularity(Granularity::Full);
+        assert_eq!(contents(&config), [source_code.trim_end()]);
+    }
+
+    #[test]
+    fn test_parse_sql_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Sql,
+            "CREATE TABLE users (\n  -- The key.\n  id INT\n);\n",
+            &["CREATE TABLE users (\n  -- The key.\n  id INT\n);"],
+        );
+    }
+
+    #[test]
+    fn test_parse_java() {
+        let source_code = r#"
+package com.example.widgets;
+
+import java.util.List;
+
+/** A button that can be clicked. */
+@Deprecated
+public class Button implements Widget {
+    private final String label;
+
+    public Button(String label) {
+        this.label = label;
+    }
+
+    @Override
+    public void render(List<String> lines) {
+        lines.add(label);
+    }
+
+    enum Size { SMALL, LARGE }
+}
+
+interface Widget {
+    void render(List<String> lines);
+}
+
+record Point(int x, int y) {
+    Point {
+        if (x < 0) throw new IllegalArgumentException();
+    }
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Java);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "package com.example.widgets;",
+                "import java.util.List;",
+                r#"@Deprecated
+public class Button implements Widget {
+    private final String label;
+
+    public Button(String label) {
+        // ...
+    }
+
+    @Override
+    public void render(List<String> lines) {
+        // ...
+    }
+
+    enum Size { SMALL, LARGE }
+}"#,
+                "interface Widget {\n    void render(List<String> lines);\n}",
+                "record Point(int x, int y) {\n    Point {\n        // ...\n    }\n}",
+            ]
+        );
+        assert_eq!((result[2].start_line, result[2].end_line), (6, 20));
+    }
+
+    #[test]
+    fn test_parse_php() {
+        let source_code = r#"
+<?php
+
+namespace App\Shapes;
+
+use App\Contracts\Shape;
+
+const UNIT = 1.0;
+
+/** A circle of a given radius. */
+final class Circle implements Shape
+{
+    private float $radius;
+
+    public function __construct(float $radius)
+    {
+        $this->radius = $radius;
+    }
+
+    public function area(): float
+    {
+        return M_PI * $this->radius ** 2;
+    }
+}
+
+interface Named
+{
+    public function name(): string;
+}
+
+function scale(Shape $shape, float $factor): float
+{
+    return $shape->area() * $factor;
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Php);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "namespace App\\Shapes;",
+                "use App\\Contracts\\Shape;",
+                "const UNIT = 1.0;",
+                r#"final class Circle implements Shape
+{
+    private float $radius;
+
+    public function __construct(float $radius)
+    {
+        // ...
+    }
+
+    public function area(): float
+    {
+        // ...
+    }
+}"#,
+                "interface Named\n{\n    public function name(): string;\n}",
+                "function scale(Shape $shape, float $factor): float\n{\n    // ...\n}",
+            ]
+        );
+        assert_eq!((result[3].start_line, result[3].end_line), (10, 23));
+
+        // Elided bodies follow the configured indentation.
+        config.set_indentation(Indentation::Tabs);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result[5].content,
+            "function scale(Shape $shape, float $factor): float\n{\n\t// ...\n}"
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let source_code = r#"
+{
+  // Comments are allowed, as in tsconfig.json.
+  "name": "widgets",
+  "description": "A library of widgets for building user interfaces in the browser",
+  "keywords": ["ui", "widgets", "browser"],
+  "scripts": {
+    "build": "tsc",
+    "test": "jest"
+  },
+  "contributors": [
+    { "name": "Ada", "roles": { "maintainer": { "since": 2021 } } },
+    { "name": "Grace" },
+  ],
+  "private": true
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Json);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                r#""name": "widgets""#,
+                r#""description": "A library of widgets for building user ...""#,
+                r#""keywords": [3 items]"#,
+                "\"scripts\": {\n  \"build\": \"tsc\",\n  \"test\": \"jest\",\n}",
+                r#""contributors": [
+  {
+    "name": "Ada",
+    "roles": {
+      "maintainer": {1 key},
+    },
+  },
+  ... 2 items
+]"#,
+                r#""private": true"#,
+            ]
+        );
+        assert_eq!((result[3].start_line, result[3].end_line), (6, 9));
+    }
+
+    #[test]
+    fn test_parse_json_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Json,
+            "{\"name\": \"app\", \"version\": \"1.0.0\"}\n",
+            &["\"name\": \"app\"", "\"version\": \"1.0.0\""],
+        );
+    }
+
+    #[test]
+    fn test_parse_julia() {
+        let source_code = r#"
+module Shapes
+
+using LinearAlgebra
+import Base: show,
+    length
+
+export Circle, area
+
+#=
+Shapes with an area.
+=#
+abstract type Shape end
+
+Base.@kwdef struct Circle <: Shape
+    radius::Float64 = 1.0
+end
+
+"The area of a circle."
+function area(c::Circle)
+    if c.radius < 0
+        error("negative radius")
+    end
+    return pi * c.radius^2
+end
+
+perimeter(c::Circle) = 2 * pi * c.radius
+
+const UNIT = Circle(radius = 1.0)
+
+end
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Julia);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].content,
+            r#"module Shapes
+
+using LinearAlgebra
+
+import Base: show,
+    length
+
+export Circle, area
+
+abstract type Shape end
+
+Base.@kwdef struct Circle <: Shape
+    radius::Float64 = 1.0
+end
+
+function area(c::Circle)
+    # ...
+end
+
+perimeter(c::Circle) = ...
+
+end"#
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 30));
+    }
+
+    #[test]
+    fn test_parse_julia_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Julia,
+            "function greet(name)\n    # Say hello.\n    println(name)\nend\n",
+            &["function greet(name)\n    # ...\nend"],
+        );
+    }
+
+    #[test]
+    fn test_parse_make() {
+        let source_code = "
+# Build settings.
+CC ?= gcc
+CFLAGS := -O2 -Wall \\
+\t-Wextra
+SOURCES = $(wildcard src/*.c)
+OBJECTS = $(SOURCES:.c=.o)
+
+include config.mk
+
+.PHONY: all clean
+
+all: server
+
+server: $(OBJECTS)
+\t$(CC) $(CFLAGS) -o $@ $^
+
+# Objects are rebuilt when any header changes.
+%.o: %.c $(wildcard src/*.h)
+\t$(CC) $(CFLAGS) -c -o $@ $<
+
+clean: ; rm -f server $(OBJECTS)
+"
+        .trim();
+        let config = default_parse_config_for_language(Language::Make);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 9);
+        assert_eq!(result[0].content, "CC ?= gcc");
+        assert_eq!(result[1].content, "CFLAGS := -O2 -Wall -Wextra");
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 4));
+        assert_eq!(result[2].content, "SOURCES = $(wildcard src/*.c)");
+        assert_eq!(result[3].content, "OBJECTS = $(SOURCES:.c=.o)");
+        assert_eq!(result[4].content, ".PHONY: all clean");
+        assert_eq!(result[5].content, "all: server");
+        assert_eq!(result[6].content, "server: $(OBJECTS)\n\t# ...");
+        assert_eq!((result[6].start_line, result[6].end_line), (14, 15));
+        assert_eq!(result[7].content, "%.o: %.c $(wildcard src/*.h)\n\t# ...");
+        assert_eq!(result[8].content, "clean:\n\t# ...");
+    }
+
+    #[test]
+    fn test_parse_make_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Make,
+            "# Build it.\nbuild:\n\tcargo build\n",
+            &["build:\n\t# ..."],
+        );
+    }
+
+    #[test]
+    fn test_parse_jupyter() {
+        let source_code = r###"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": [
+    "# Sales\n",
+    "\n",
+    "Monthly sales by region."
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": ["{\"source\": [1, 2]}\n"]
+    }
+   ],
+   "source": [
+    "import pandas as pd\n",
+    "\n",
+    "def load(path):\n",
+    "    return pd.read_csv(path)"
+   ]
+  },
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": "## By region"
+  }
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"###;
+        let config = default_parse_config_for_language(Language::Jupyter);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result
+                .iter()
+                .map(|key_content| (key_content.content.as_str(), key_content.start_line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("# Sales", 7),
+                ("import pandas as pd", 24),
+                ("def load(path):\n    ...", 26),
+                ("## By region", 33),
+            ]
+        );
+
+        assert!(parse("not json", &config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_jupyter_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Jupyter,
+            r##"{"cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis"]},
+                {"cell_type": "code", "metadata": {}, "outputs": [],
+                 "source": ["def greet(name):\n", "    # Say hello.\n", "    print(name)\n"]}
+            ]}"##,
+            &["# Analysis", "def greet(name):\n    ..."],
+        );
+    }
+
+    #[test]
+    fn test_parse_test_code() {
+        let source_code = r#"
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        assert_eq!(add(1, 2), 3);
+    }
+
+    #[tokio::test]
+    async fn test_add_async() {
+        assert_eq!(add(2, 2), 4);
+    }
+}
+"#
+        .trim();
+        let contents = |config: &ParseConfig, source_code: &str| {
+            parse(source_code, config)
+                .unwrap()
+                .into_iter()
+                .map(|key_content| key_content.content)
+                .collect::<Vec<_>>()
+        };
+        let mut config = default_parse_config_for_language(Language::Rust);
+        config.set_test_code(TestCode::Summarize);
+        assert_eq!(
+            contents(&config, source_code),
+            vec![
+                "pub fn add(a: i32, b: i32) -> i32 {\n    // ...\n}",
+                "mod tests // ... (2 tests, 13 lines elided)",
+            ]
+        );
+        config.set_test_code(TestCode::Skip);
+        assert_eq!(
+            contents(&config, source_code),
+            vec!["pub fn add(a: i32, b: i32) -> i32 {\n    // ...\n}"]
+        );
+
+        let source_code = r#"
+package widgets
+
+func TestParse(t *testing.T) {
+	Parse("")
+}
+
+func Testify(s string) {}
+
+func ExampleParse() {
+	Parse("")
+}
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Go);
+        config.set_test_code(TestCode::Summarize);
+        assert_eq!(
+            contents(&config, source_code),
+            vec![
+                "func TestParse(t *testing.T)",
+                "func Testify(s string) {\n\t// ...\n}",
+                "func ExampleParse()",
+            ]
+        );
+
+        let source_code = r#"
+class Widget:
+    def render(self):
+        pass
+
+    def test_render(self):
+        assert self.render() is None
+
+class TestWidget(unittest.TestCase):
+    def setUp(self):
+        self.widget = Widget()
+
+    def test_render(self):
+        self.widget.render()
+
+@pytest.mark.slow
+def test_everything():
+    pass
+"#
+        .trim();
+        let mut config = default_parse_config_for_language(Language::Python);
+        config.set_test_code(TestCode::Summarize);
+        assert_eq!(
+            contents(&config, source_code),
+            vec![
+                "class Widget:\n    def render(self):\n        ...\n    def test_render(self):",
+                "class TestWidget(unittest.TestCase): # ... (1 test, 6 lines elided)",
+                "@pytest.mark.slow\ndef test_everything():",
+            ]
+        );
+        config.set_test_code(TestCode::Skip);
+        assert_eq!(
+            contents(&config, source_code),
+            vec!["class Widget:\n    def render(self):\n        ..."]
+        );
+    }
+
+    #[test]
+    fn test_parse_injections() {
+        let source_code = r#"
+def find_user(cursor, user_id):
+    cursor.execute("""
+        SELECT id, name
+        FROM users
+        WHERE id = ?
+    """, (user_id,))
+    print("SELECT is not SQL here")
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Python);
+        let result = parse(source_code, &config).unwrap();
+        let sql = result
+            .iter()
+            .find(|key_content| key_content.content.starts_with("~~~"))
+            .unwrap();
+        assert_eq!(
+            sql.content,
+            "~~~sql\nSELECT id, name\nFROM users\nWHERE id = ?\n~~~"
+        );
+        assert_eq!((sql.start_line, sql.end_line), (2, 6));
+        assert_eq!(result.len(), 2);
+
+        let source_code = r##"
+fn migrate(conn: &Connection) {
+    conn.execute_batch(r#"
+        CREATE TABLE users (id INTEGER PRIMARY KEY);
+        INSERT INTO users VALUES (1);
+    "#);
+}
+"##
+        .trim();
+        let config = default_parse_config_for_language(Language::Rust);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result.last().unwrap().content,
+            "~~~sql\nCREATE TABLE users (id INTEGER PRIMARY KEY);\n~~~"
+        );
+
+        let source_code = r#"
+package store
+
+func (s *Store) Count(ctx context.Context) {
+	s.db.QueryRowContext(ctx, `SELECT count(*) FROM users`)
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Go);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(
+            result.last().unwrap().content,
+            "~~~sql\nSELECT count(*) FROM users\n~~~"
+        );
+
+        let mut config = default_parse_config_for_language(Language::Python);
+        config
+            .add_injection(
+                r#"(expression_statement (assignment
+                     left: (identifier) @_name
+                     right: (string) @injection.content)
+                   (#eq? @_name "SCHEMA")
+                   (#set! injection.language "sql"))"#,
+            )
+            .unwrap();
+        let result = parse(
+            "SCHEMA = 'CREATE INDEX users_name ON users (name);'",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            result.last().unwrap().content,
+            "~~~sql\nCREATE INDEX users_name ON users (name);\n~~~"
+        );
+        assert!(matches!(
+            config.add_injection("(string) @sql"),
+            Err(ParseError::InjectionWithoutContent)
+        ));
+
+        let config = parse_config_from_toml(
+            Language::Python,
+            r#"injections = ['((string) @injection.content (#set! injection.language "yaml"))']"#,
+        )
+        .unwrap();
+        let result = parse("'name: widgets'", &config).unwrap();
+        assert_eq!(result[0].content, "~~~yaml\nname: widgets\n~~~");
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let source_code = r#"
+---
+title: Widgets
+---
+
+# Widgets
+
+A library of widgets
+for building user interfaces.
+
+More detail that is left out.
+
+## Install
+
+```sh
+# Not a heading.
+cargo add widgets
+```
+
+Add the crate to your manifest.
+
+Usage
+-----
+See the examples.
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Markdown);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "~~~yaml\ntitle: Widgets\n~~~",
+                "# Widgets\n\nA library of widgets\nfor building user interfaces.",
+                "## Install\n\nAdd the crate to your manifest.",
+                "Usage\n-----\n\nSee the examples.",
+            ]
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 3));
+        assert_eq!((result[1].start_line, result[1].end_line), (5, 8));
+    }
+
+    #[test]
+    fn test_parse_markdown_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Markdown,
+            "# Title\n\n<!-- Draft. -->\nSome text.\n\n## Usage\n",
+            &["# Title\n\n<!-- Draft. -->\nSome text.", "## Usage"],
+        );
+    }
+
+    #[test]
+    fn test_parse_nix() {
+        let flake = r#"
+{
+  description = "An example flake";
+
+  inputs = {
+    nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+    flake-utils.url = "github:numtide/flake-utils";
+  };
+
+  outputs = { self, nixpkgs, flake-utils }:
+    let
+      system = "x86_64-linux";
+      pkgs = nixpkgs.legacyPackages.${system};
+    in {
+      packages.${system}.default = pkgs.hello;
+      devShells.${system}.default = pkgs.mkShell {
+        buildInputs = [ pkgs.cargo ];
+      };
+    };
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Nix);
+        let result = parse(flake, &config).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].content,
+            r#"{
+  description = "An example flake";
+  inputs = {
+    nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+    flake-utils.url = "github:numtide/flake-utils";
+  };
+  outputs = { self, nixpkgs, flake-utils }: {
+      packages.${system}.default = pkgs.hello;
+      devShells.${system}.default = ...;
+    };
+}"#
+        );
+
+        let derivation = r#"
+{ lib, stdenv, fetchurl }:
+
+stdenv.mkDerivation rec {
+  pname = "hello";
+  version = "2.12";
+  src = fetchurl {
+    url = "mirror://gnu/hello/hello-${version}.tar.gz";
+  };
+}
+"#
+        .trim();
+        let result = parse(derivation, &config).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "{ lib, stdenv, fetchurl }:");
+        assert_eq!(
+            result[1].content,
+            r#"stdenv.mkDerivation rec {
+  pname = "hello";
+  version = "2.12";
+  src = ...;
+}"#
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 9));
+    }
+
+    #[test]
+    fn test_parse_nix_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Nix,
+            "{ pkgs }:\npkgs.mkShell {\n  # Tools.\n  buildInputs = [ pkgs.cargo ];\n}\n",
+            &[
+                "{ pkgs }:",
+                "pkgs.mkShell {\n  buildInputs = [ pkgs.cargo ];\n}",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_parse_proto() {
+        let source_code = r#"
+syntax = "proto3";
+
+package example.v1;
+
+import "google/api/annotations.proto";
+
+option go_package = "example.com/example/v1;examplev1";
+
+// A user of the service.
+message User {
+    string id = 1;
+    string name = 2; // "display; name"
+}
+
+enum Role {
+    ROLE_UNSPECIFIED = 0;
+    ROLE_ADMIN = 1;
+}
+
+service UserService {
+    option (example.v1.service_option) = true;
+
+    rpc GetUser(GetUserRequest) returns (User) {
+        option (google.api.http) = { get: "/v1/users/{id}" };
+    }
+    rpc ListUsers(ListUsersRequest) returns (stream User);
+}
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Proto);
+        let result = parse(source_code, &config).unwrap();
+        assert_eq!(result.len(), 5);
+        assert_eq!(result[0].content, "package example.v1;");
+        assert_eq!(
+            result[1].content,
+            r#"import "google/api/annotations.proto";"#
+        );
+        assert_eq!(
+            result[2].content,
+            r#"message User {
+    string id = 1;
+    string name = 2; // "display; name"
+}"#
+        );
+        assert_eq!((result[2].start_line, result[2].end_line), (10, 13));
+        assert_eq!(
+            result[3].content,
+            r#"enum Role {
+    ROLE_UNSPECIFIED = 0;
+    ROLE_ADMIN = 1;
+}"#
+        );
+        assert_eq!(
+            result[4].content,
+            r#"service UserService {
+    rpc GetUser(GetUserRequest) returns (User);
+    rpc ListUsers(ListUsersRequest) returns (stream User);
+}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_proto_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Proto,
+            "syntax = \"proto3\";\n\nmessage User {\n  // The name.\n  string name = 1;\n}\n",
+            &["message User {\n  // The name.\n  string name = 1;\n}"],
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        let source_code = r#"
+-- Geometry helpers.
+local M = {}
+
+M.origin = { x = 0, y = 0 }
+
+M.unit = 1
+
+
+
+
+local function square(x)
+  return x * x
+end
+
+function M.distance(a, b)
+  return math.sqrt(square(a.x - b.x) + square(a.y - b.y))
+end
+
+return M
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Unknown);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "-- Geometry helpers.\nlocal M = {}\n\nM.origin = { x = 0, y = 0 }\n\nM.unit = 1",
+                "local function square(x)",
+                "function M.distance(a, b)",
+            ]
+        );
+        assert_eq!((result[0].start_line, result[0].end_line), (1, 6));
+        assert_eq!((result[2].start_line, result[2].end_line), (15, 15));
+    }
+
+    #[test]
+    fn test_parse_unknown_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Unknown,
+            "# Greets people.\nclass Greeter\nend\n",
+            &["# Greets people.\nclass Greeter\nend"],
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let source_code = r#"
+# A deployment and its service.
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  labels:
+    app: web
+spec:
+  replicas: 3 # Scaled by the autoscaler.
+  template:
+    spec:
+      containers:
+      - name: web
+        image: "web:1.0 #1"
+---
+apiVersion: v1
+kind: Service
+spec:
+  ports:
+  - port: 80
+  - port: 443
+description: |
+  Serves the website.
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Yaml);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "apiVersion: apps/v1",
+                "kind: Deployment",
+                "metadata:\n  name: web\n  labels: ...",
+                "spec:\n  replicas: 3\n  template: ...",
+                "---\napiVersion: v1",
+                "kind: Service",
+                "spec:\n  ports: ...",
+                "description: ...",
+            ]
+        );
+        assert_eq!((result[3].start_line, result[3].end_line), (8, 14));
+    }
+
+    #[test]
+    fn test_parse_yaml_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Yaml,
+            "# Service.\nname: app\nports:\n  - 80\n",
+            &["name: app", "ports:\n  - 80"],
+        );
+    }
+
+    #[test]
+    fn test_parse_svelte() {
+        let source_code = r#"
+<script lang="ts">
+  import { onMount } from "svelte";
+  import { writable } from "svelte/store";
+
+  export let title: string;
+  export let count = 0;
+  let items = writable([]);
+
+  function increment(by: number) {
+    count += by;
+  }
+
+  const reset = () => {
+    count = 0;
+  };
+
+  $: doubled = count * 2;
+</script>
+
+<!-- The header. -->
+<h1 on:click={() => count > 1 && reset()}>{title}</h1>
+<main class="app">
+  <button on:click={() => increment(1)}>Add</button>
+</main>
+<br />
+
+<style>
+  h1 { color: red; }
+</style>
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Svelte);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                r#"import { onMount } from "svelte";"#,
+                r#"import { writable } from "svelte/store";"#,
+                "export let title: string;",
+                "export let count = 0;",
+                "function increment(by: number) {\n  // ...\n}",
+                "const reset = () => {\n  // ...\n}",
+                "<h1 on:click={() => count > 1 && reset()}>...</h1>",
+                r#"<main class="app">...</main>"#,
+                "<br />",
+            ]
+        );
+        assert_eq!((result[4].start_line, result[4].end_line), (9, 11));
+        assert_eq!((result[7].start_line, result[7].end_line), (22, 24));
+    }
+
+    #[test]
+    fn test_parse_svelte_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Svelte,
+            "<script>\n  // The name.\n  export let name;\n</script>\n\n<h1>Hello {name}</h1>\n",
+            &["export let name;", "<h1>...</h1>"],
+        );
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let source_code = r#"
+title = "Example"
+
+[server]
+host = "localhost"
+ports = [
+    8080,
+    8081,
+]
+
+[[server.routes]]
+path = "/"
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Toml);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "title = ...",
+                "[server]\nhost = ...\nports = ...",
+                "[[server.routes]]\npath = ...",
+            ]
+        );
+        assert_eq!((result[1].start_line, result[1].end_line), (3, 8));
+    }
+
+    #[test]
+    fn test_parse_toml_ignores_tree_sitter_settings() {
+        assert_scanner_ignores_settings(
+            Language::Toml,
+            "[package]\n# The name.\nname = \"app\"\n",
+            &["[package]\nname = \"app\""],
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_cargo_manifest() {
+        let source_code = r#"
+[package]
+name = "code-digest"
+version = "0.1.0" # Bumped on release.
+
+[features]
+default = ["git"]
+git = []
+
+[dependencies]
+clap = { version = "4.3.0", features = ["derive"] }
+language_parsers = { path = "../language_parsers" }
+
+[target.'cfg(unix)'.dev-dependencies]
+nix = "0.26"
+"#
+        .trim();
+        let config = default_parse_config_for_language(Language::Toml);
+        let result = parse(source_code, &config).unwrap();
+        let contents = result
+            .iter()
+            .map(|key_content| key_content.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                "[package]\nname = \"code-digest\"\nversion = \"0.1.0\"",
+                "[features]\ndefault = [\"git\"]\ngit = []",
+                "[dependencies]\nclap = ...\nlanguage_parsers = ...",
+                "[target.'cfg(unix)'.dev-dependencies]\nnix = ...",
+            ]
+        );
+    }
 }
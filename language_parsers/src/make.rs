@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A Makefile is a sequence of logical lines, each continued by a trailing backslash. Variable
+// definitions are kept whole, rules are kept as their targets and prerequisites with their recipes
+// elided, and directives such as `include` and conditionals are skipped.
+
+use crate::KeyContent;
+
+/// Directives that are neither variables nor rules.
+const DIRECTIVES: &[&str] = &[
+    "include", "-include", "sinclude", "ifeq", "ifneq", "ifdef", "ifndef", "else", "endif",
+    "vpath", "unexport",
+];
+
+struct Line {
+    text: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let lines = logical_lines(source_code);
+    let mut result = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        i += 1;
+        let text = line.text.trim_end();
+        let first_word = text.split_whitespace().next().unwrap_or_default();
+        if text.starts_with('\t') || text.trim().is_empty() || DIRECTIVES.contains(&first_word) {
+            continue;
+        }
+
+        if first_word == "define" {
+            // A multi-line variable, kept as its name.
+            let mut end_line = line.end_line;
+            while let Some(next) = lines.get(i) {
+                i += 1;
+                end_line = next.end_line;
+                if next.text.trim() == "endef" {
+                    break;
+                }
+            }
+            result.push(KeyContent {
+                content: format!("{}\n...\nendef", text),
+                start_line: line.start_line,
+                end_line,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        match definition_kind(text) {
+            Some(Definition::Variable) => result.push(KeyContent {
+                content: text.to_string(),
+                start_line: line.start_line,
+                end_line: line.end_line,
+                ..Default::default()
+            }),
+            Some(Definition::Rule) => {
+                // A recipe either follows the prerequisites after `;` or is on the following lines
+                // starting with a tab.
+                let (header, inline_recipe) = match text.split_once(';') {
+                    Some((header, _)) => (header.trim_end(), true),
+                    None => (text, false),
+                };
+                let mut end_line = line.end_line;
+                let mut has_recipe = inline_recipe;
+                while let Some(next) = lines.get(i) {
+                    if !next.text.starts_with('\t') && !next.text.trim().is_empty() {
+                        break;
+                    }
+                    if next.text.starts_with('\t') {
+                        has_recipe = true;
+                        end_line = next.end_line;
+                    }
+                    i += 1;
+                }
+                let content = if has_recipe {
+                    format!("{}\n{}# ...", header, indent)
+                } else {
+                    header.to_string()
+                };
+                result.push(KeyContent {
+                    content,
+                    start_line: line.start_line,
+                    end_line,
+                    ..Default::default()
+                });
+            }
+            None => {}
+        }
+    }
+    result
+}
+
+/// Split a Makefile into logical lines, joining lines that end in a backslash and dropping
+/// comments outside recipes.
+fn logical_lines(source_code: &str) -> Vec<Line> {
+    let mut result: Vec<Line> = vec![];
+    let mut continued = false;
+    for (i, line) in source_code.lines().enumerate() {
+        let (line, continues) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let line = if line.starts_with('\t') {
+            line
+        } else {
+            strip_comment(line)
+        };
+        match result.last_mut() {
+            Some(last) if continued => {
+                last.text.truncate(last.text.trim_end().len());
+                last.text.push(' ');
+                last.text.push_str(line.trim());
+                last.end_line = i + 1;
+            }
+            _ => result.push(Line {
+                text: line.to_string(),
+                start_line: i + 1,
+                end_line: i + 1,
+            }),
+        }
+        continued = continues;
+    }
+    result
+}
+
+/// A line without its comment, which starts at a `#` not escaped by a backslash.
+fn strip_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '#' if !escaped => return &line[..i],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+enum Definition {
+    Variable,
+    Rule,
+}
+
+/// Whether a line defines a variable, e.g. `CFLAGS := -O2`, or a rule, e.g. `all: build test`,
+/// which is decided by whether an assignment operator comes before the first `:`.
+fn definition_kind(line: &str) -> Option<Definition> {
+    for (i, c) in line.char_indices() {
+        match c {
+            // Also the end of `?=`, `+=` and `!=`.
+            '=' => return Some(Definition::Variable),
+            // `:=`, `::=` and `:::=` assign, while `:` and `::` start a rule.
+            ':' if line[i..].trim_start_matches(':').starts_with('=') => {
+                return Some(Definition::Variable)
+            }
+            ':' => return Some(Definition::Rule),
+            _ => {}
+        }
+    }
+    None
+}
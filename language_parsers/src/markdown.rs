@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Markdown is digested as its headings, each kept with the first paragraph under it, which is
+// usually enough to tell what a README or design document covers. Fenced code blocks are skipped,
+// and YAML front matter is digested as YAML in a nested section.
+
+use crate::injection::{digest, nested_section};
+use crate::{KeyContent, Language};
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let lines = source_code.lines().collect::<Vec<_>>();
+    let mut result: Vec<KeyContent> = vec![];
+
+    // The heading waiting for its first paragraph, and whether that paragraph has started.
+    let mut current: Option<usize> = None;
+    let mut in_paragraph = false;
+
+    let mut i = front_matter_end(&lines);
+    if i > 0 {
+        let front_matter = lines[1..i - 1].join("\n");
+        if !front_matter.trim().is_empty() {
+            result.push(KeyContent {
+                content: nested_section("yaml", &digest(Language::Yaml, front_matter)),
+                start_line: 1,
+                end_line: i,
+                ..Default::default()
+            });
+        }
+    }
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if let Some(fence) = fence(trimmed) {
+            i += 1;
+            while i < lines.len() && !lines[i].trim().starts_with(fence) {
+                i += 1;
+            }
+            i += 1;
+            if in_paragraph {
+                (current, in_paragraph) = (None, false);
+            }
+            continue;
+        }
+        if trimmed.is_empty() {
+            if in_paragraph {
+                (current, in_paragraph) = (None, false);
+            }
+            i += 1;
+            continue;
+        }
+
+        let heading_lines = if is_atx_heading(line) {
+            1
+        } else if !in_paragraph
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| is_setext_underline(next))
+        {
+            2
+        } else {
+            0
+        };
+        if heading_lines > 0 {
+            result.push(KeyContent {
+                content: lines[i..i + heading_lines]
+                    .iter()
+                    .map(|line| line.trim())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                start_line: i + 1,
+                end_line: i + heading_lines,
+                ..Default::default()
+            });
+            (current, in_paragraph) = (Some(result.len() - 1), false);
+            i += heading_lines;
+            continue;
+        }
+
+        if let Some(heading) = current {
+            let key_content = &mut result[heading];
+            key_content
+                .content
+                .push_str(if in_paragraph { "\n" } else { "\n\n" });
+            key_content.content.push_str(line.trim_end());
+            key_content.end_line = i + 1;
+            in_paragraph = true;
+        }
+        i += 1;
+    }
+    result
+}
+
+/// The index of the first line after any YAML front matter, which is between `---` lines at the
+/// start of the document.
+fn front_matter_end(lines: &[&str]) -> usize {
+    if lines.first().map(|line| line.trim_end()) != Some("---") {
+        return 0;
+    }
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim_end() == "---")
+        .map_or(0, |end| end + 2)
+}
+
+/// The fence that opens a fenced code block, if the line opens one.
+pub(crate) fn fence(trimmed: &str) -> Option<&'static str> {
+    ["```", "~~~"]
+        .into_iter()
+        .find(|&fence| trimmed.starts_with(fence))
+}
+
+/// Whether a line is a heading such as `## Usage`, which may be indented by up to three spaces.
+pub(crate) fn is_atx_heading(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    line.len() - trimmed.len() < 4
+        && (1..=6).contains(&level)
+        && trimmed[level..]
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace)
+}
+
+/// Whether a line underlines the line before it as a heading, e.g. `=====` or `-----`.
+fn is_setext_underline(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Metrics are computed from the syntax tree, so that comments and blank lines are told apart from
+// code exactly, and cyclomatic complexity is approximated by counting the nodes that branch: each
+// adds a path through the function it is in.
+
+use serde::Serialize;
+use tree_sitter as ts;
+
+use crate::item::{symbols, ItemKind};
+
+/// Node kinds that branch, across languages.
+const BRANCH_KINDS: &[&str] = &[
+    // Conditionals.
+    "if_statement",
+    "if_expression",
+    "if_let_expression",
+    "elif_clause",
+    "if_clause",
+    "conditional_expression",
+    "ternary_expression",
+    // Loops.
+    "for_statement",
+    "for_expression",
+    "for_in_clause",
+    "for_range_loop",
+    "enhanced_for_statement",
+    "foreach_statement",
+    "c_style_for_statement",
+    "while_statement",
+    "while_expression",
+    "loop_expression",
+    "do_statement",
+    // Cases.
+    "match_arm",
+    "case_clause",
+    "case_item",
+    "case_statement",
+    "expression_case",
+    "type_case",
+    "communication_case",
+    "switch_label",
+    // Exceptions.
+    "catch_clause",
+    "except_clause",
+    // Short-circuiting operators.
+    "&&",
+    "||",
+    "and",
+    "or",
+];
+
+/// Size and complexity measures of one file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FileMetrics {
+    /// Lines with code on them, not counting blank lines or lines with only comments.
+    pub lines_of_code: usize,
+
+    /// Named functions and methods.
+    pub functions: usize,
+
+    /// Named types, such as structs, enums, classes, and traits.
+    pub types: usize,
+
+    /// The approximate cyclomatic complexity of the file: the number of functions, or 1 if there
+    /// are none, plus the number of branches such as conditionals, loops, match arms, catch
+    /// clauses, and `&&` or `||`. This is the sum of the complexities of its functions.
+    pub complexity: usize,
+}
+
+pub(crate) fn file_metrics(root_node: &ts::Node, source_code: &str) -> FileMetrics {
+    let mut code_lines = vec![false; source_code.lines().count() + 1];
+    let mut branches = 0;
+    let mut cursor = root_node.walk();
+    visit(&mut cursor, &mut code_lines, &mut branches);
+
+    let symbols = symbols(root_node, source_code);
+    let count = |kind| symbols.iter().filter(|symbol| symbol.kind == kind).count();
+    let functions = count(ItemKind::Function);
+    FileMetrics {
+        lines_of_code: code_lines.iter().filter(|&&is_code| is_code).count(),
+        functions,
+        types: count(ItemKind::Type),
+        complexity: functions.max(1) + branches,
+    }
+}
+
+/// Mark the lines of the tokens under the cursor's node that are not comments, and count branches.
+fn visit(cursor: &mut ts::TreeCursor, code_lines: &mut [bool], branches: &mut usize) {
+    let node = cursor.node();
+    if BRANCH_KINDS.contains(&node.kind()) {
+        *branches += 1;
+    }
+    if node.kind().contains("comment") {
+        return;
+    }
+    if node.child_count() == 0 {
+        if node.start_byte() < node.end_byte() {
+            let rows = node.start_position().row..=node.end_position().row;
+            for row in rows {
+                if let Some(is_code) = code_lines.get_mut(row) {
+                    *is_code = true;
+                }
+            }
+        }
+        return;
+    }
+    if cursor.goto_first_child() {
+        loop {
+            visit(cursor, code_lines, branches);
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+        cursor.goto_parent();
+    }
+}
@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A Nix file is a single expression, usually a function of an argument set returning an attribute
+// set, e.g. a derivation or a flake. The scanner keeps the argument set and outlines the attribute
+// set: flake inputs are kept whole, flake outputs are kept as their arguments and attribute names,
+// other attributes are kept if they fit on one line, and longer values are elided.
+
+use crate::scan::{first_word, key_content, line_indentation, text, tokenize, Lexicon, Spanned};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["#"],
+    block_comments: &[("/*", "*/")],
+    quotes: &["''", "\""],
+    word_characters: &['-', '\'', '.'],
+};
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    let mut result = vec![];
+    let mut expression = &tokens[..];
+    if let Some(colon) = function_arguments(expression) {
+        let arguments = &expression[..=colon];
+        result.push(key_content(
+            text(arguments, source_code).to_string(),
+            arguments,
+            source_code,
+        ));
+        expression = &expression[colon + 1..];
+    }
+
+    let expression = skip_prefixes(expression);
+    let attribute_set = expression
+        .iter()
+        .position(|spanned| spanned.is_punctuation('{'))
+        .and_then(|open| Some(&expression[..=matching_brace(expression, open)?]));
+    if let Some(attribute_set) = attribute_set {
+        result.push(key_content(
+            attribute_set_outline(attribute_set, source_code),
+            attribute_set,
+            source_code,
+        ));
+    }
+    result
+}
+
+/// If the expression is a function, the index of the `:` ending its arguments. Arguments are either
+/// a name, as in `pkgs: ...`, or an argument set optionally bound to a name, as in
+/// `{ pkgs, ... }: ...` or `args@{ pkgs, ... }: ...`.
+fn function_arguments(tokens: &[Spanned]) -> Option<usize> {
+    let mut i = 0;
+    if first_word(tokens).is_some() {
+        if tokens.get(1)?.is_punctuation(':') {
+            return Some(1);
+        }
+        if !tokens.get(1)?.is_punctuation('@') {
+            return None;
+        }
+        i = 2;
+    }
+    if !tokens.get(i)?.is_punctuation('{') {
+        return None;
+    }
+    i = matching_brace(tokens, i)? + 1;
+    if tokens.get(i)?.is_punctuation('@') {
+        i += 2;
+    }
+    tokens.get(i)?.is_punctuation(':').then_some(i)
+}
+
+/// Skip `let ... in`, `with ...;` and `assert ...;` before the body of an expression.
+fn skip_prefixes(mut tokens: &[Spanned]) -> &[Spanned] {
+    loop {
+        let end = match first_word(tokens) {
+            Some("let") => tokens
+                .iter()
+                .enumerate()
+                .scan(0, |depth, (i, spanned)| {
+                    if spanned.is_word("let") {
+                        *depth += 1;
+                    } else if spanned.is_word("in") {
+                        *depth -= 1;
+                    }
+                    Some((i, *depth))
+                })
+                .find(|&(_, depth)| depth == 0)
+                .map(|(i, _)| i),
+            Some("with" | "assert") => split_bindings(tokens).first().map(|first| first.len() - 1),
+            _ => return tokens,
+        };
+        match end {
+            Some(end) => tokens = &tokens[end + 1..],
+            None => return tokens,
+        }
+    }
+}
+
+fn matching_brace(tokens: &[Spanned], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate().skip(open) {
+        if spanned.is_punctuation('{') {
+            depth += 1;
+        } else if spanned.is_punctuation('}') {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Split the inside of an attribute set or `let` into bindings, each ending in `;`.
+fn split_bindings(tokens: &[Spanned]) -> Vec<&[Spanned]> {
+    let mut bindings = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        if ['(', '[', '{'].iter().any(|&c| spanned.is_punctuation(c)) || spanned.is_word("let") {
+            depth += 1;
+        } else if [')', ']', '}'].iter().any(|&c| spanned.is_punctuation(c))
+            || spanned.is_word("in")
+        {
+            depth -= 1;
+        } else if spanned.is_punctuation(';') && depth <= 0 {
+            bindings.push(&tokens[start..=i]);
+            start = i + 1;
+            depth = 0;
+        }
+    }
+    bindings
+}
+
+/// An attribute set, from anything before its opening brace such as `stdenv.mkDerivation rec` to its
+/// closing brace, with each binding on its own line.
+fn attribute_set_outline(tokens: &[Spanned], source_code: &str) -> String {
+    let open = tokens
+        .iter()
+        .position(|spanned| spanned.is_punctuation('{'))
+        .unwrap_or(0);
+    let close = tokens.len() - 1;
+    let mut result = text(&tokens[..=open], source_code).to_string();
+    result.push('\n');
+    for binding in split_bindings(&tokens[open + 1..close]) {
+        result.push_str(line_indentation(source_code, binding[0].range.start));
+        result.push_str(&binding_outline(binding, source_code));
+        result.push('\n');
+    }
+    result.push_str(line_indentation(source_code, tokens[close].range.start));
+    result.push('}');
+    result
+}
+
+fn binding_outline(binding: &[Spanned], source_code: &str) -> String {
+    let equals = match binding
+        .iter()
+        .position(|spanned| spanned.is_punctuation('='))
+    {
+        Some(equals) if first_word(binding) != Some("inherit") => equals,
+        _ => return text(binding, source_code).to_string(),
+    };
+    let name = text(&binding[..equals], source_code);
+    let value = &binding[equals + 1..binding.len() - 1];
+
+    if name == "inputs" || name.starts_with("inputs.") {
+        return text(binding, source_code).to_string();
+    }
+    if name == "outputs" {
+        if let Some(colon) = function_arguments(value) {
+            let body = skip_prefixes(&value[colon + 1..]);
+            let body = match body.first() {
+                Some(first)
+                    if first.is_punctuation('{')
+                        && matching_brace(body, 0) == Some(body.len() - 1) =>
+                {
+                    attribute_set_outline(body, source_code)
+                }
+                _ => "...".to_string(),
+            };
+            return format!(
+                "{} = {} {};",
+                name,
+                text(&value[..=colon], source_code),
+                body
+            );
+        }
+    }
+    if !text(value, source_code).contains('\n') {
+        return text(binding, source_code).to_string();
+    }
+    format!("{} = ...;", name)
+}
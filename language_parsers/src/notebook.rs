@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A Jupyter notebook is JSON holding a list of cells, each with a type and its source as a list of
+// lines. The notebook is digested cell by cell in order: the headings of markdown cells, which
+// outline it, and what the default Python config captures from code cells, which skips outputs
+// entirely. Line numbers are those of each cell's source in the JSON, assuming one line of source
+// per line of JSON as Jupyter saves notebooks.
+
+use serde::Deserialize;
+
+use crate::markdown::{fence, is_atx_heading};
+use crate::scan::line_number;
+use crate::{default_parse_config_for_language, KeyContent, Language};
+
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+
+    #[serde(default)]
+    source: Source,
+}
+
+/// Cell source is usually a list of lines, each ending with its newline, but may be one string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Lines(Vec<String>),
+    Text(String),
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::Lines(vec![])
+    }
+}
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let notebook: Notebook = match serde_json::from_str(source_code) {
+        Ok(notebook) => notebook,
+        Err(_) => return vec![],
+    };
+    let python = default_parse_config_for_language(Language::Python);
+
+    // The line of each cell's `"source"` key. A quote inside a string is escaped, so output that
+    // happens to mention a source key does not match.
+    let source_keys = source_code
+        .match_indices("\"source\":")
+        .map(|(position, _)| line_number(source_code, position))
+        .collect::<Vec<_>>();
+
+    let mut result = vec![];
+    for (i, cell) in notebook.cells.iter().enumerate() {
+        let (text, offset) = match &cell.source {
+            Source::Lines(lines) => (lines.concat(), 1),
+            Source::Text(text) => (text.clone(), 0),
+        };
+        let first_line = source_keys.get(i).map_or(1, |line| line + offset);
+        match cell.cell_type.as_str() {
+            "markdown" => result.extend(headings(&text, first_line)),
+            "code" => {
+                let key_contents = crate::parse(&text, &python).unwrap_or_default();
+                result.extend(key_contents.into_iter().map(|key_content| KeyContent {
+                    start_line: first_line + key_content.start_line - 1,
+                    end_line: first_line + key_content.end_line - 1,
+                    byte_range: 0..0,
+                    ..key_content
+                }));
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// The headings in markdown, outside fenced code blocks, with their lines counted from
+/// `first_line`.
+fn headings(markdown: &str, first_line: usize) -> Vec<KeyContent> {
+    let mut result = vec![];
+    let mut open_fence = None;
+    for (i, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim();
+        match open_fence {
+            Some(fence) if trimmed.starts_with(fence) => open_fence = None,
+            Some(_) => {}
+            None if fence(trimmed).is_some() => open_fence = fence(trimmed),
+            None if is_atx_heading(line) => result.push(KeyContent {
+                content: trimmed.to_string(),
+                start_line: first_line + i,
+                end_line: first_line + i,
+                ..Default::default()
+            }),
+            None => {}
+        }
+    }
+    result
+}
@@ -0,0 +1,36 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+use crate::elide::NestedDefinitions;
+
+// A braced namespace holds its declarations in a compound_statement, the same node kind as a
+// function body, but function bodies are never searched so only namespace bodies are reached.
+const NESTED_DEFINITIONS: NestedDefinitions = NestedDefinitions {
+    functions: &["function_definition", "method_declaration"],
+    containers: &[
+        "namespace_definition",
+        "compound_statement",
+        "class_declaration",
+        "interface_declaration",
+        "trait_declaration",
+        "enum_declaration",
+        "declaration_list",
+        "enum_declaration_list",
+    ],
+    line_comment: "//",
+};
+
+/// The source of a top-level PHP definition with every function and method body in it elided,
+/// keeping properties, constants and abstract or interface method signatures as they are.
+pub(crate) fn elide_function_bodies(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    crate::elide::elide_function_bodies(node, source_code, indent, &NESTED_DEFINITIONS)
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Protocol Buffers files have a small, regular syntax, so they are split into top-level statements
+// by a scanner rather than a tree-sitter grammar. Package and import statements and message and enum
+// definitions are kept whole, services are kept as their rpc signatures, and file options are left
+// out.
+
+use crate::scan::{first_word, key_content, text, tokenize, Lexicon, Spanned};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    quotes: &["\"", "'"],
+    word_characters: &['.'],
+};
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    split_statements(&tokens)
+        .into_iter()
+        .filter_map(|statement| {
+            let content = match first_word(statement)? {
+                "package" | "import" | "message" | "enum" => {
+                    text(statement, source_code).to_string()
+                }
+                "service" => service_signatures(statement, source_code, indent)?,
+                _ => return None,
+            };
+            Some(key_content(content, statement, source_code))
+        })
+        .collect()
+}
+
+/// Split tokens into statements, each ending in either a `;` or the `}` closing a block.
+fn split_statements(tokens: &[Spanned]) -> Vec<&[Spanned]> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        let end_of_statement = if spanned.is_punctuation('{') {
+            depth += 1;
+            false
+        } else if spanned.is_punctuation('}') {
+            depth -= 1;
+            depth <= 0
+        } else {
+            spanned.is_punctuation(';') && depth <= 0
+        };
+        if end_of_statement {
+            // A `;` on its own, e.g. after a closing brace, is an empty statement.
+            if i > start || !spanned.is_punctuation(';') {
+                statements.push(&tokens[start..=i]);
+            }
+            start = i + 1;
+            depth = 0;
+        }
+    }
+    statements
+}
+
+/// A service with just the signatures of its rpcs, leaving out options set on the service or on
+/// each rpc.
+fn service_signatures(statement: &[Spanned], source_code: &str, indent: &str) -> Option<String> {
+    let open = statement
+        .iter()
+        .position(|spanned| spanned.is_punctuation('{'))?;
+    let mut result = text(&statement[..=open], source_code).to_string();
+    result.push('\n');
+
+    let body = &statement[open + 1..statement.len() - 1];
+    for rpc in split_statements(body) {
+        if first_word(rpc) != Some("rpc") {
+            continue;
+        }
+        let signature_end = rpc
+            .iter()
+            .position(|spanned| spanned.is_punctuation('{') || spanned.is_punctuation(';'))
+            .unwrap_or(rpc.len());
+        result.push_str(indent);
+        result.push_str(text(&rpc[..signature_end], source_code));
+        result.push_str(";\n");
+    }
+    result.push('}');
+    Some(result)
+}
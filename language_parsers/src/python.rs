@@ -0,0 +1,91 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+/// The text of a statement worth keeping, or an empty string. Class bodies keep their attributes
+/// and docstring, and modules keep statements from `module_assignment`. Statements in function
+/// bodies are not kept.
+pub(crate) fn statement(node: &ts::Node, source_code: &str) -> String {
+    let enclosing = node.parent().and_then(|parent| match parent.kind() {
+        "block" => parent.parent(),
+        _ => Some(parent),
+    });
+    match enclosing.map(|enclosing| enclosing.kind()) {
+        Some("module") => module_assignment(node, source_code),
+        Some("class_definition") => {
+            let is_attribute = node.named_child_count() == 1
+                && node
+                    .named_child(0)
+                    .is_some_and(|child| child.kind() == "assignment");
+            let is_docstring = node.prev_named_sibling().is_none()
+                && node
+                    .named_child(0)
+                    .is_some_and(|child| child.kind() == "string");
+            if is_attribute || is_docstring {
+                source_code[node.byte_range()].trim().to_string()
+            } else {
+                String::new()
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// The text of a module-level statement that assigns a constant, such as `MAX_RETRIES = 3`, or
+/// declares a type alias, such as `Url: TypeAlias = str` or `type Vector = list[float]`, or an
+/// empty string for any other statement.
+fn module_assignment(node: &ts::Node, source_code: &str) -> String {
+    let assignment = match node.named_child(0) {
+        Some(assignment) if node.named_child_count() == 1 && assignment.kind() == "assignment" => {
+            assignment
+        }
+        _ => return String::new(),
+    };
+    let text = source_code[node.byte_range()].trim();
+
+    // Grammars that predate PEP 695 parse `type Vector = ...` either as an assignment to `type`
+    // with an error for the name, or as an error for `type` followed by an assignment.
+    if assignment.has_error() && text.starts_with("type ") {
+        return text.to_string();
+    }
+    if let Some(previous) = node.prev_sibling() {
+        if previous.kind() == "ERROR"
+            && &source_code[previous.byte_range()] == "type"
+            && previous.start_position().row == node.start_position().row
+        {
+            return format!("type {}", text);
+        }
+    }
+
+    let is_constant = assignment
+        .child_by_field_name("left")
+        .filter(|left| left.kind() == "identifier")
+        .is_some_and(|left| is_constant_name(&source_code[left.byte_range()]));
+    let is_type_alias = assignment
+        .child_by_field_name("type")
+        .is_some_and(|annotation| {
+            let annotation = &source_code[annotation.byte_range()];
+            annotation == "TypeAlias" || annotation.ends_with(".TypeAlias")
+        });
+    if is_constant || is_type_alias {
+        text.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Whether a name is written as a constant, in upper case such as `MAX_RETRIES` or `_DEFAULT`.
+fn is_constant_name(name: &str) -> bool {
+    name.chars().any(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Query selectors pick out nodes with a tree-sitter query, written in the same syntax as the `.scm`
+// files that ship with grammars, rather than by node kind alone. The node captured as `@capture` is
+// kept, with any node inside it captured as `@elide` replaced by a `...` comment unless it is within
+// the config's elision threshold. Other captures, such as `@name`, are only there for predicates
+// like `#match?`. A pattern can set the comment that replaces elided nodes with
+// `(#set! line_comment "#")`, and otherwise it is `//`.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use tree_sitter as ts;
+
+use crate::elide::elide_ranges;
+use crate::strip_comments::strip_comments;
+use crate::{KeyContent, ParseConfig, ParseError, ParseResult};
+
+const DEFAULT_LINE_COMMENT: &str = "//";
+
+/// A captured node, with the comment for the pattern that captured it and the ranges to elide.
+struct Captured<'tree, 'query> {
+    node: ts::Node<'tree>,
+    line_comment: &'query str,
+    elided: Vec<Range<usize>>,
+}
+
+pub(crate) struct QuerySelector {
+    query: ts::Query,
+    capture: u32,
+    elide: Option<u32>,
+}
+
+impl QuerySelector {
+    pub(crate) fn new(language: ts::Language, source: &str) -> ParseResult<QuerySelector> {
+        let query = ts::Query::new(language, source)?;
+        let capture = query
+            .capture_index_for_name("capture")
+            .ok_or(ParseError::QueryWithoutCapture)?;
+        let elide = query.capture_index_for_name("elide");
+        Ok(QuerySelector {
+            query,
+            capture,
+            elide,
+        })
+    }
+
+    /// The captured nodes in source order, each with the nodes to elide inside it. A node captured by
+    /// several matches is kept once.
+    pub(crate) fn key_contents(
+        &self,
+        root_node: ts::Node,
+        source_code: &str,
+        config: &ParseConfig,
+    ) -> Vec<KeyContent> {
+        let mut captured: BTreeMap<(usize, usize), Captured> = BTreeMap::new();
+        let mut cursor = ts::QueryCursor::new();
+        for query_match in cursor.matches(&self.query, root_node, source_code.as_bytes()) {
+            let line_comment = self.line_comment(query_match.pattern_index);
+            let mut elided = vec![];
+            for capture in query_match.captures {
+                if Some(capture.index) == self.elide && !config.keeps_whole(&capture.node) {
+                    elided.push(capture.node.byte_range());
+                }
+            }
+            for capture in query_match.captures {
+                if capture.index != self.capture {
+                    continue;
+                }
+                let node = capture.node;
+                let entry = captured
+                    .entry((node.start_byte(), node.end_byte()))
+                    .or_insert_with(|| Captured {
+                        node,
+                        line_comment,
+                        elided: vec![],
+                    });
+                entry.elided.extend(
+                    elided
+                        .iter()
+                        .filter(|range| {
+                            node.start_byte() <= range.start && range.end <= node.end_byte()
+                        })
+                        .cloned(),
+                );
+            }
+        }
+
+        captured
+            .into_values()
+            .filter_map(|captured| {
+                let content = elide_ranges(
+                    &captured.node,
+                    source_code,
+                    &config.indent_value,
+                    captured.line_comment,
+                    &outermost(captured.elided),
+                    config.elision_summary,
+                );
+                let content = match config.strip_comments {
+                    true => strip_comments(&content, &captured.node, source_code),
+                    false => content,
+                };
+                (!content.is_empty()).then(|| KeyContent::new(content, &captured.node, source_code))
+            })
+            .collect()
+    }
+
+    fn line_comment(&self, pattern_index: usize) -> &str {
+        self.query
+            .property_settings(pattern_index)
+            .iter()
+            .find(|property| &*property.key == "line_comment")
+            .and_then(|property| property.value.as_deref())
+            .unwrap_or(DEFAULT_LINE_COMMENT)
+    }
+}
+
+/// The ranges sorted, without duplicates or ranges nested inside another.
+fn outermost(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    let mut result: Vec<Range<usize>> = vec![];
+    for range in ranges {
+        if result.last().is_none_or(|last| last.end <= range.start) {
+            result.push(range);
+        }
+    }
+    result
+}
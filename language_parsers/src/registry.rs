@@ -0,0 +1,267 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::Language;
+
+/// File names that are in a language whatever their extension, or that have none.
+const FILE_NAMES: &[(&str, Language)] = &[
+    ("CMakeLists.txt", Language::CMake),
+    ("Dockerfile", Language::Dockerfile),
+    ("Makefile", Language::Make),
+    ("makefile", Language::Make),
+    ("GNUmakefile", Language::Make),
+];
+
+const EXTENSIONS: &[(&str, Language)] = &[
+    ("bash", Language::Bash),
+    ("sh", Language::Bash),
+    ("cc", Language::Cpp),
+    ("cpp", Language::Cpp),
+    ("cxx", Language::Cpp),
+    ("c++", Language::Cpp),
+    ("hh", Language::Cpp),
+    ("hpp", Language::Cpp),
+    ("hxx", Language::Cpp),
+    ("h++", Language::Cpp),
+    ("clj", Language::Clojure),
+    ("cljs", Language::Clojure),
+    ("cljc", Language::Clojure),
+    ("cmake", Language::CMake),
+    ("dart", Language::Dart),
+    ("dockerfile", Language::Dockerfile),
+    ("fs", Language::FSharp),
+    ("fsx", Language::FSharp),
+    ("go", Language::Go),
+    ("hcl", Language::Hcl),
+    ("tf", Language::Hcl),
+    ("java", Language::Java),
+    ("ipynb", Language::Jupyter),
+    ("jl", Language::Julia),
+    ("json", Language::Json),
+    ("md", Language::Markdown),
+    ("markdown", Language::Markdown),
+    ("mk", Language::Make),
+    ("nix", Language::Nix),
+    ("php", Language::Php),
+    ("proto", Language::Proto),
+    ("py", Language::Python),
+    ("rs", Language::Rust),
+    ("sql", Language::Sql),
+    ("svelte", Language::Svelte),
+    ("toml", Language::Toml),
+    ("yaml", Language::Yaml),
+    ("yml", Language::Yaml),
+];
+
+/// Interpreters named in a shebang line, without any version number such as the `3` of `python3`.
+const INTERPRETERS: &[(&str, Language)] = &[
+    ("bash", Language::Bash),
+    ("dash", Language::Bash),
+    ("ksh", Language::Bash),
+    ("sh", Language::Bash),
+    ("julia", Language::Julia),
+    ("make", Language::Make),
+    ("php", Language::Php),
+    ("python", Language::Python),
+];
+
+/// Languages by name, as in config file names, with common aliases.
+const NAMES: &[(&str, Language)] = &[
+    ("bash", Language::Bash),
+    ("sh", Language::Bash),
+    ("shell", Language::Bash),
+    ("clojure", Language::Clojure),
+    ("cmake", Language::CMake),
+    ("cpp", Language::Cpp),
+    ("c++", Language::Cpp),
+    ("dart", Language::Dart),
+    ("dockerfile", Language::Dockerfile),
+    ("fsharp", Language::FSharp),
+    ("go", Language::Go),
+    ("hcl", Language::Hcl),
+    ("java", Language::Java),
+    ("json", Language::Json),
+    ("julia", Language::Julia),
+    ("make", Language::Make),
+    ("markdown", Language::Markdown),
+    ("nix", Language::Nix),
+    ("php", Language::Php),
+    ("proto", Language::Proto),
+    ("python", Language::Python),
+    ("rust", Language::Rust),
+    ("sql", Language::Sql),
+    ("svelte", Language::Svelte),
+    ("toml", Language::Toml),
+    ("yaml", Language::Yaml),
+];
+
+/// Which language a file is in, by its file name or else its extension. It starts with the
+/// languages' usual file names and extensions, and more can be registered, such as `pyi` for
+/// Python stubs. Files that are neither can be recognized by their contents instead.
+#[derive(Clone, Debug)]
+pub struct LanguageRegistry {
+    file_names: HashMap<String, Language>,
+    extensions: HashMap<String, Language>,
+}
+
+impl LanguageRegistry {
+    pub fn new() -> LanguageRegistry {
+        let to_map = |mappings: &[(&str, Language)]| {
+            mappings
+                .iter()
+                .map(|&(key, language)| (key.to_string(), language))
+                .collect()
+        };
+        LanguageRegistry {
+            file_names: to_map(FILE_NAMES),
+            extensions: to_map(EXTENSIONS),
+        }
+    }
+
+    /// Treat files with `extension`, without the leading `.`, as being in `language`, replacing any
+    /// language it was registered for before.
+    pub fn register_extension(&mut self, extension: impl Into<String>, language: Language) {
+        self.extensions.insert(extension.into(), language);
+    }
+
+    /// Treat files named exactly `file_name`, such as `BUILD`, as being in `language`, whatever
+    /// their extension.
+    pub fn register_file_name(&mut self, file_name: impl Into<String>, language: Language) {
+        self.file_names.insert(file_name.into(), language);
+    }
+
+    /// The language of the file at `path`, or None if neither its name nor its extension is
+    /// registered.
+    pub fn language_for_path(&self, path: &Path) -> Option<Language> {
+        let file_name = path.file_name()?.to_str()?;
+        if let Some(&language) = self.file_names.get(file_name) {
+            return Some(language);
+        }
+        let extension = path.extension()?.to_str()?;
+        self.extensions.get(extension).copied()
+    }
+
+    /// The language of the file at `path` with contents `source_code`, by its name or extension
+    /// if registered, and otherwise by its contents: the interpreter in a `#!` line, as in
+    /// `#!/usr/bin/env python3`, or for a file without an extension, a first line that only one
+    /// language starts with, such as `FROM` in a Dockerfile or `package main` in Go.
+    pub fn language_for_file(&self, path: &Path, source_code: &str) -> Option<Language> {
+        self.language_for_path(path)
+            .or_else(|| language_from_shebang(source_code))
+            .or_else(|| {
+                path.extension()
+                    .is_none()
+                    .then(|| language_from_first_line(source_code))
+                    .flatten()
+            })
+    }
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        LanguageRegistry::new()
+    }
+}
+
+/// The language with a name such as `python` or `sql`, ignoring case.
+pub(crate) fn language_named(name: &str) -> Option<Language> {
+    NAMES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .map(|&(_, language)| language)
+}
+
+fn language_from_shebang(source_code: &str) -> Option<Language> {
+    let mut words = source_code
+        .lines()
+        .next()?
+        .strip_prefix("#!")?
+        .split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    // `env` runs the interpreter after it, possibly with options of its own such as `-S`.
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|&&(name, _)| name == interpreter)
+        .map(|&(_, language)| language)
+}
+
+fn language_from_first_line(source_code: &str) -> Option<Language> {
+    let line = source_code
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))?;
+    match line.split_whitespace().next()? {
+        "<?php" => Some(Language::Php),
+        "FROM" => Some(Language::Dockerfile),
+        // Java package declarations end with a `;`.
+        "package" if !line.ends_with(';') => Some(Language::Go),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_for_path() {
+        let mut registry = LanguageRegistry::new();
+        let language = |path: &str| registry.language_for_path(Path::new(path));
+        assert_eq!(language("src/lib.rs"), Some(Language::Rust));
+        assert_eq!(language("build/Makefile"), Some(Language::Make));
+        assert_eq!(language("typing.pyi"), None);
+        assert_eq!(language("BUILD"), None);
+
+        registry.register_extension("pyi", Language::Python);
+        registry.register_file_name("BUILD", Language::Python);
+        registry.register_extension("h", Language::Cpp);
+        let language = |path: &str| registry.language_for_path(Path::new(path));
+        assert_eq!(language("typing.pyi"), Some(Language::Python));
+        assert_eq!(language("pkg/BUILD"), Some(Language::Python));
+        assert_eq!(language("include/point.h"), Some(Language::Cpp));
+    }
+
+    #[test]
+    fn test_language_for_file() {
+        let registry = LanguageRegistry::new();
+        let language = |path: &str, source_code: &str| {
+            registry.language_for_file(Path::new(path), source_code)
+        };
+        assert_eq!(language("main.rs", "#!/bin/sh\n"), Some(Language::Rust));
+        assert_eq!(
+            language("bin/tool", "#!/usr/bin/env python3\nprint()\n"),
+            Some(Language::Python)
+        );
+        assert_eq!(
+            language("bin/tool", "#!/usr/bin/env -S python3.11 -u\n"),
+            Some(Language::Python)
+        );
+        assert_eq!(language("run.cgi", "#!/bin/bash\n"), Some(Language::Bash));
+        assert_eq!(language("bin/tool", "#!/usr/bin/perl\n"), None);
+        assert_eq!(
+            language("docker/base", "# syntax=docker/dockerfile:1\nFROM alpine\n"),
+            Some(Language::Dockerfile)
+        );
+        assert_eq!(
+            language("cmd/tool", "// Command tool.\npackage main\n"),
+            Some(Language::Go)
+        );
+        assert_eq!(language("Example", "package com.example;\n"), None);
+        assert_eq!(language("notes.txt", "package main\n"), None);
+        assert_eq!(language("bin/tool", "from os import path\n"), None);
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+use tree_sitter as ts;
+
+use crate::elide::elide_ranges;
+use crate::scan::line_indentation;
+
+/// Items inside a module that are kept whole.
+const WHOLE_ITEMS: &[&str] = &[
+    "use_declaration",
+    "struct_item",
+    "enum_item",
+    "type_item",
+    "const_item",
+    "static_item",
+];
+
+/// An inline module, `mod foo { ... }`, with the items in it captured as they are at the top level
+/// and indented under the module's header. Nested modules are outlined the same way. A module
+/// declared in another file, `mod foo;`, is skipped.
+pub(crate) fn module_outline(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    let body = match node.child_by_field_name("body") {
+        Some(body) => body,
+        None => return String::new(),
+    };
+    let header = source_code[node.start_byte()..body.start_byte()].trim();
+    let mut items = vec![];
+    let mut cursor = body.walk();
+    for item in body.named_children(&mut cursor) {
+        let content = match item.kind() {
+            kind if WHOLE_ITEMS.contains(&kind) => source_code[item.byte_range()].to_string(),
+            "function_item" => match item.child_by_field_name("body") {
+                Some(function_body) => elide_ranges(
+                    &item,
+                    source_code,
+                    indent,
+                    "//",
+                    &[function_body.byte_range()],
+                    false,
+                ),
+                None => source_code[item.byte_range()].to_string(),
+            },
+            "function_signature_item" => source_code[item.byte_range()].to_string(),
+            "macro_definition" => {
+                // The arms are laid out relative to the macro's own indentation.
+                let item_indent = line_indentation(source_code, item.start_byte());
+                macro_outline(&item, source_code, indent)
+                    .replace('\n', &format!("\n{}", item_indent))
+            }
+            "mod_item" => module_outline(&item, source_code, indent),
+            _ => continue,
+        };
+        if !content.is_empty() {
+            let start = first_attribute(&item)
+                .map_or(item.start_byte(), |attribute| attribute.start_byte());
+            items.push(format!(
+                "{}{}{}",
+                line_indentation(source_code, start),
+                &source_code[start..item.start_byte()],
+                content
+            ));
+        }
+    }
+    if items.is_empty() {
+        return format!("{} {{}}", header);
+    }
+    format!(
+        "{} {{\n{}\n{}}}",
+        header,
+        items.join("\n\n"),
+        line_indentation(source_code, node.start_byte())
+    )
+}
+
+/// The first of the attributes directly above `node`, such as `#[derive(Debug)]` or
+/// `#[cfg(feature = "x")]`, which tree-sitter parses as siblings of the item they apply to.
+pub(crate) fn first_attribute<'a>(node: &ts::Node<'a>) -> Option<ts::Node<'a>> {
+    let mut first = None;
+    let mut previous = node.prev_sibling();
+    while let Some(attribute) = previous.filter(|previous| previous.kind() == "attribute_item") {
+        first = Some(attribute);
+        previous = attribute.prev_sibling();
+    }
+    first
+}
+
+/// A `macro_rules!` definition kept as its name and the pattern of each arm, with every expansion
+/// elided.
+pub(crate) fn macro_outline(node: &ts::Node, source_code: &str, indent: &str) -> String {
+    let name = match node.child_by_field_name("name") {
+        Some(name) => &source_code[name.byte_range()],
+        None => return source_code[node.byte_range()].trim().to_string(),
+    };
+    let mut result = format!("macro_rules! {} {{\n", name);
+    let mut cursor = node.walk();
+    for rule in node.named_children(&mut cursor) {
+        if let Some(pattern) = rule.child_by_field_name("left") {
+            result.push_str(&format!(
+                "{}{} => {{ ... }};\n",
+                indent,
+                &source_code[pattern.byte_range()]
+            ));
+        }
+    }
+    result.push('}');
+    result
+}
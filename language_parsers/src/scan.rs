@@ -0,0 +1,198 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Shared tokenizing for languages parsed by a hand-written scanner rather than a tree-sitter
+// grammar. Tokens are coarse, just words, strings and single punctuation characters, which is
+// enough to find where statements and blocks begin and end.
+
+use std::ops::Range;
+
+use crate::KeyContent;
+
+pub(crate) enum Token {
+    Word(String),
+    Punctuation(char),
+
+    /// A quoted string, which may contain braces and delimiters.
+    String,
+}
+
+pub(crate) struct Spanned {
+    pub token: Token,
+    pub range: Range<usize>,
+}
+
+impl Spanned {
+    pub fn is_punctuation(&self, c: char) -> bool {
+        matches!(self.token, Token::Punctuation(p) if p == c)
+    }
+
+    pub fn is_word(&self, word: &str) -> bool {
+        matches!(&self.token, Token::Word(w) if w == word)
+    }
+}
+
+/// The lexical rules of a language that matter for tokenizing.
+pub(crate) struct Lexicon {
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [(&'static str, &'static str)],
+
+    /// String delimiters, with longer ones such as `"""` before shorter ones. Backslash escapes the
+    /// next character inside a string.
+    pub quotes: &'static [&'static str],
+
+    /// Characters other than letters, digits and `_` that can appear in a word.
+    pub word_characters: &'static [char],
+}
+
+impl Lexicon {
+    fn is_word_character(&self, c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || self.word_characters.contains(&c)
+    }
+}
+
+pub(crate) fn tokenize(source_code: &str, lexicon: &Lexicon) -> Vec<Spanned> {
+    let mut tokens = vec![];
+    let mut position = 0;
+    'outer: while position < source_code.len() {
+        let rest = &source_code[position..];
+        let start = position;
+        let c = rest.chars().next().unwrap();
+
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+        // Block comments first, since some languages start them like line comments, e.g. `#=`.
+        for (open, close) in lexicon.block_comments {
+            if let Some(comment) = rest.strip_prefix(open) {
+                position += comment
+                    .find(close)
+                    .map_or(rest.len(), |end| open.len() + end + close.len());
+                continue 'outer;
+            }
+        }
+        if lexicon
+            .line_comments
+            .iter()
+            .any(|comment| rest.starts_with(comment))
+        {
+            position += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        for quote in lexicon.quotes {
+            if rest.starts_with(quote) {
+                position += quoted_length(rest, quote);
+                tokens.push(Spanned {
+                    token: Token::String,
+                    range: start..position,
+                });
+                continue 'outer;
+            }
+        }
+
+        let token = if lexicon.is_word_character(c) {
+            let length = rest
+                .find(|c: char| !lexicon.is_word_character(c))
+                .unwrap_or(rest.len());
+            position += length;
+            Token::Word(rest[..length].to_string())
+        } else {
+            position += c.len_utf8();
+            Token::Punctuation(c)
+        };
+        tokens.push(Spanned {
+            token,
+            range: start..position,
+        });
+    }
+    tokens
+}
+
+/// The length of the string starting with `quote` at the start of `rest`, including the quotes.
+fn quoted_length(rest: &str, quote: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(quote.len()) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if rest[i..].starts_with(quote) {
+            return i + quote.len();
+        }
+    }
+    rest.len()
+}
+
+pub(crate) fn first_word(tokens: &[Spanned]) -> Option<&str> {
+    match &tokens.first()?.token {
+        Token::Word(word) => Some(word),
+        _ => None,
+    }
+}
+
+/// The source code from the first token to the last.
+pub(crate) fn text<'a>(tokens: &[Spanned], source_code: &'a str) -> &'a str {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => &source_code[first.range.start..last.range.end],
+        _ => "",
+    }
+}
+
+/// Key content for `content` captured from the source code of `tokens`.
+pub(crate) fn key_content(content: String, tokens: &[Spanned], source_code: &str) -> KeyContent {
+    let start = tokens.first().map_or(0, |first| first.range.start);
+    let end = tokens.last().map_or(0, |last| last.range.end);
+    KeyContent {
+        content,
+        start_line: line_number(source_code, start),
+        end_line: line_number(source_code, end),
+        ..Default::default()
+    }
+}
+
+/// The bytes of every line from `start_line` to `end_line`, starting from 1, without the final line
+/// break.
+pub(crate) fn lines_byte_range(
+    source_code: &str,
+    start_line: usize,
+    end_line: usize,
+) -> Range<usize> {
+    let line_start = |line: usize| {
+        if line <= 1 {
+            return 0;
+        }
+        source_code
+            .match_indices('\n')
+            .nth(line - 2)
+            .map_or(source_code.len(), |(i, _)| i + 1)
+    };
+    let end = source_code[line_start(end_line)..]
+        .find('\n')
+        .map_or(source_code.len(), |i| line_start(end_line) + i);
+    line_start(start_line)..end
+}
+
+/// Whether there is a line break between two tokens.
+pub(crate) fn newline_between(source_code: &str, before: &Spanned, after: &Spanned) -> bool {
+    source_code[before.range.end..after.range.start].contains('\n')
+}
+
+/// The whitespace at the start of the line containing `byte`.
+pub(crate) fn line_indentation(source_code: &str, byte: usize) -> &str {
+    let line_start = source_code[..byte].rfind('\n').map_or(0, |i| i + 1);
+    let line = &source_code[line_start..byte];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// The line of the byte at `position`, starting from 1.
+pub(crate) fn line_number(source_code: &str, position: usize) -> usize {
+    source_code[..position].matches('\n').count() + 1
+}
@@ -0,0 +1,325 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// SQL dialects differ too much for a single tree-sitter grammar to parse real migration folders, so
+// SQL is split into statements by a small scanner instead. Statements are classified by their
+// leading keywords: CREATE TABLE, INDEX and VIEW are kept whole, CREATE FUNCTION and PROCEDURE are
+// kept with their bodies elided, and everything else is left out.
+
+use std::ops::Range;
+
+use crate::KeyContent;
+
+enum Token {
+    /// A keyword or unquoted identifier, in upper case.
+    Word(String),
+
+    /// A single-quoted string.
+    String,
+
+    /// A dollar-quoted string, e.g. `$$ ... $$` or `$body$ ... $body$`.
+    DollarString(String),
+
+    /// The end of a statement.
+    Delimiter,
+
+    Other,
+}
+
+struct Spanned {
+    token: Token,
+    range: Range<usize>,
+}
+
+/// Statement kinds after `CREATE` that are kept whole.
+const SCHEMA_KINDS: &[&str] = &["TABLE", "INDEX", "VIEW"];
+
+/// Statement kinds after `CREATE` that are kept with their bodies elided.
+const ROUTINE_KINDS: &[&str] = &["FUNCTION", "PROCEDURE"];
+
+/// How many tokens after `CREATE` to look for the kind of object created, past modifiers such as
+/// `OR REPLACE`, `UNIQUE` or `MATERIALIZED`.
+const CREATE_LOOKAHEAD: usize = 8;
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    split_statements(&tokenize(source_code))
+        .into_iter()
+        .filter_map(|statement| digest_statement(statement, source_code, indent))
+        .collect()
+}
+
+fn tokenize(source_code: &str) -> Vec<Spanned> {
+    let mut tokens = vec![];
+    let mut delimiter = ";".to_string();
+    let mut position = 0;
+    while position < source_code.len() {
+        let rest = &source_code[position..];
+        let start = position;
+        let c = rest.chars().next().unwrap();
+
+        if c.is_whitespace() {
+            position += c.len_utf8();
+        } else if rest.starts_with("--") {
+            position += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            position += comment.find("*/").map_or(rest.len(), |end| end + 4);
+        } else if delimiter != ";" && rest.starts_with(delimiter.as_str()) {
+            position += delimiter.len();
+            tokens.push(Spanned {
+                token: Token::Delimiter,
+                range: start..position,
+            });
+        } else if c == ';' {
+            position += 1;
+            let token = if delimiter == ";" {
+                Token::Delimiter
+            } else {
+                Token::Other
+            };
+            tokens.push(Spanned {
+                token,
+                range: start..position,
+            });
+        } else if c == '\'' || c == '"' || c == '`' {
+            position += quoted_length(rest, c);
+            let token = if c == '\'' {
+                Token::String
+            } else {
+                Token::Other
+            };
+            tokens.push(Spanned {
+                token,
+                range: start..position,
+            });
+        } else if let Some(tag) = dollar_quote_tag(rest) {
+            let body_end = rest[tag.len()..]
+                .find(tag)
+                .map_or(rest.len(), |end| tag.len() + end + tag.len());
+            position += body_end;
+            tokens.push(Spanned {
+                token: Token::DollarString(tag.to_string()),
+                range: start..position,
+            });
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = rest[..length].to_uppercase();
+            position += length;
+
+            // A MySQL client command that changes the statement delimiter until the end of the
+            // file or the next DELIMITER command, so that routine bodies can contain `;`.
+            if word == "DELIMITER" && is_line_start(source_code, start) {
+                let line_end = rest.find('\n').unwrap_or(rest.len());
+                let new_delimiter = rest[length..line_end].trim();
+                if !new_delimiter.is_empty() {
+                    delimiter = new_delimiter.to_string();
+                }
+                position = start + line_end;
+                continue;
+            }
+            tokens.push(Spanned {
+                token: Token::Word(word),
+                range: start..position,
+            });
+        } else {
+            position += c.len_utf8();
+            tokens.push(Spanned {
+                token: Token::Other,
+                range: start..position,
+            });
+        }
+    }
+    tokens
+}
+
+/// The length of the quoted string or identifier at the start of `rest`, including the quotes. A
+/// doubled quote inside is an escaped quote.
+fn quoted_length(rest: &str, quote: char) -> usize {
+    let mut chars = rest.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            if chars.peek().is_some_and(|&(_, next)| next == quote) {
+                chars.next();
+                continue;
+            }
+            return i + 1;
+        }
+    }
+    rest.len()
+}
+
+/// The tag of the dollar quote at the start of `rest`, e.g. `$$` or `$body$`, if there is one.
+fn dollar_quote_tag(rest: &str) -> Option<&str> {
+    if !rest.starts_with('$') {
+        return None;
+    }
+    let tag_end = rest[1..].find(|c: char| !(c.is_alphanumeric() || c == '_'))? + 1;
+    if !rest[tag_end..].starts_with('$') {
+        return None;
+    }
+    if rest[1..tag_end].starts_with(|c: char| c.is_ascii_digit()) {
+        // A positional parameter such as $1.
+        return None;
+    }
+    Some(&rest[..tag_end + 1])
+}
+
+fn is_line_start(source_code: &str, position: usize) -> bool {
+    source_code[..position]
+        .chars()
+        .rev()
+        .take_while(|&c| c != '\n')
+        .all(char::is_whitespace)
+}
+
+/// Split tokens into statements at delimiters. Inside a routine written without a custom
+/// delimiter, delimiters within `BEGIN ... END` blocks do not end the statement.
+fn split_statements(tokens: &[Spanned]) -> Vec<&[Spanned]> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match &spanned.token {
+            Token::Delimiter if depth == 0 => {
+                if start < i {
+                    statements.push(&tokens[start..i]);
+                }
+                start = i + 1;
+            }
+            Token::Word(word) if created_kind(&tokens[start..]).is_some_and(is_routine) => {
+                if word == "BEGIN" || word == "CASE" {
+                    depth += 1;
+                } else if word == "END" && depth > 0 && !ends_control_flow(tokens.get(i + 1)) {
+                    depth -= 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        statements.push(&tokens[start..]);
+    }
+    statements
+}
+
+/// Whether the token after an `END` shows it closes a control flow statement such as `END IF`,
+/// rather than a `BEGIN` or `CASE`.
+fn ends_control_flow(next: Option<&Spanned>) -> bool {
+    matches!(
+        next.map(|next| &next.token),
+        Some(Token::Word(word)) if ["IF", "LOOP", "WHILE", "REPEAT", "FOR"].contains(&word.as_str())
+    )
+}
+
+/// The kind of object a `CREATE` statement creates, e.g. `TABLE`, or None for other statements.
+fn created_kind(statement: &[Spanned]) -> Option<&str> {
+    match &statement.first()?.token {
+        Token::Word(word) if word == "CREATE" => {}
+        _ => return None,
+    }
+    statement
+        .iter()
+        .skip(1)
+        .take(CREATE_LOOKAHEAD)
+        .find_map(|spanned| match &spanned.token {
+            Token::Word(word)
+                if SCHEMA_KINDS.contains(&word.as_str())
+                    || ROUTINE_KINDS.contains(&word.as_str()) =>
+            {
+                Some(word.as_str())
+            }
+            _ => None,
+        })
+}
+
+fn is_routine(kind: &str) -> bool {
+    ROUTINE_KINDS.contains(&kind)
+}
+
+fn digest_statement(statement: &[Spanned], source_code: &str, indent: &str) -> Option<KeyContent> {
+    let kind = created_kind(statement)?;
+    let start = statement.first()?.range.start;
+    let end = statement.last()?.range.end;
+
+    let mut content = String::with_capacity(end - start);
+    match routine_body(statement, indent).filter(|_| is_routine(kind)) {
+        Some((body, elided)) => {
+            content.push_str(&source_code[start..body.start]);
+            content.push_str(&elided);
+            content.push_str(&source_code[body.end..end]);
+        }
+        None => content.push_str(&source_code[start..end]),
+    }
+    content.push(';');
+
+    Some(KeyContent {
+        content,
+        start_line: line_number(source_code, start),
+        end_line: line_number(source_code, end),
+        ..Default::default()
+    })
+}
+
+/// Where the body of a routine is, and what to replace it with. Bodies are either a quoted string
+/// after `AS`, as in PostgreSQL, or a `BEGIN ... END` block.
+fn routine_body(statement: &[Spanned], indent: &str) -> Option<(Range<usize>, String)> {
+    for (i, spanned) in statement.iter().enumerate() {
+        match &spanned.token {
+            Token::Word(word) if word == "AS" => match statement.get(i + 1).map(|t| &t.token) {
+                Some(Token::DollarString(tag)) => {
+                    return Some((
+                        statement[i + 1].range.clone(),
+                        format!("{}\n{}-- ...\n{}", tag, indent, tag),
+                    ));
+                }
+                Some(Token::String) => {
+                    return Some((
+                        statement[i + 1].range.clone(),
+                        format!("'\n{}-- ...\n'", indent),
+                    ));
+                }
+                _ => {}
+            },
+            Token::Word(word) if word == "BEGIN" => {
+                let end = matching_end(&statement[i..])?;
+                return Some((
+                    spanned.range.start..statement[i + end].range.end,
+                    format!("BEGIN\n{}-- ...\nEND", indent),
+                ));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The index of the `END` matching the `BEGIN` at the start of `tokens`.
+fn matching_end(tokens: &[Spanned]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        if let Token::Word(word) = &spanned.token {
+            if word == "BEGIN" || word == "CASE" {
+                depth += 1;
+            } else if word == "END" && !ends_control_flow(tokens.get(i + 1)) {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The line of the byte at `position`, starting from 1.
+fn line_number(source_code: &str, position: usize) -> usize {
+    source_code[..position].matches('\n').count() + 1
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Captured content is built from the source but is not a slice of it once bodies are elided, so
+// comments are removed by finding the text of each comment node, in source order, in what was
+// captured. Comments inside elided bodies are simply not found, and the `...` comments that stand
+// in for elided bodies are not in the source, so they are kept.
+
+use tree_sitter as ts;
+
+/// `content`, captured from `node`, without the comments in `node`. A comment alone on its line is
+/// removed with its line.
+pub(crate) fn strip_comments(content: &str, node: &ts::Node, source_code: &str) -> String {
+    let mut comments = vec![];
+    collect_comments(*node, &mut comments);
+
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    for comment in comments {
+        let text = source_code[comment.byte_range()].trim_end();
+        let start = match rest.find(text) {
+            Some(start) => start,
+            None => continue,
+        };
+        let before = &rest[..start];
+        let after = &rest[start + text.len()..];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let ends_line = after.trim_start_matches([' ', '\t']);
+        let ends_line = ends_line.is_empty() || ends_line.starts_with('\n');
+        if ends_line {
+            let alone = before[line_start..].trim().is_empty();
+            let before = if alone { &before[..line_start] } else { before };
+            result.push_str(before.trim_end_matches([' ', '\t']));
+            rest = after.trim_start_matches([' ', '\t']);
+            if alone {
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            }
+        } else {
+            // Only one of the spaces around the comment is kept.
+            result.push_str(before);
+            rest = match before.ends_with([' ', '\t']) {
+                true => after.trim_start_matches([' ', '\t']),
+                false => after,
+            };
+        }
+    }
+    result.push_str(rest);
+    result.trim().to_string()
+}
+
+fn collect_comments<'a>(node: ts::Node<'a>, comments: &mut Vec<ts::Node<'a>>) {
+    if node.kind().contains("comment") {
+        comments.push(node);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_comments(child, comments);
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// A Svelte component is markup with `<script>` and `<style>` blocks. Script blocks keep their
+// imports, exported props and function signatures, with function bodies elided, and markup is kept
+// as its top-level elements with their contents elided. Styles are skipped.
+
+use std::ops::Range;
+
+use crate::scan::{
+    first_word, key_content, line_number, newline_between, text, tokenize, Lexicon, Spanned, Token,
+};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    quotes: &["`", "\"", "'"],
+    word_characters: &['$'],
+};
+
+/// Elements that never have contents or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+pub(crate) fn parse(source_code: &str, indent: &str) -> Vec<KeyContent> {
+    let mut result = vec![];
+    let mut position = 0;
+    let mut depth = 0;
+
+    // The name and opening tag range of the top-level element whose closing tag is next at depth 0.
+    let mut top_level: Option<(&str, usize, usize)> = None;
+
+    while let Some(offset) = source_code[position..].find('<') {
+        let start = position + offset;
+        let rest = &source_code[start..];
+        if rest.starts_with("<!--") {
+            position = start + rest.find("-->").map_or(rest.len(), |end| end + 3);
+            continue;
+        }
+        let end = match tag_end(source_code, start) {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &source_code[start..end];
+        let name = tag_name(tag);
+        if name.is_empty() {
+            // A `<` in text rather than a tag.
+            position = start + 1;
+            continue;
+        }
+        position = end;
+
+        if tag.starts_with("</") {
+            depth = (depth - 1).max(0);
+            if depth == 0 {
+                if let Some((name, open_start, open_end)) = top_level.take() {
+                    result.push(KeyContent {
+                        content: format!("{}...</{}>", &source_code[open_start..open_end], name),
+                        start_line: line_number(source_code, open_start),
+                        end_line: line_number(source_code, end),
+                        ..Default::default()
+                    });
+                }
+            }
+        } else if name == "script" || name == "style" {
+            let close = format!("</{}>", name);
+            let body_end = source_code[end..]
+                .find(&close)
+                .map_or(source_code.len(), |body_end| end + body_end);
+            if name == "script" {
+                result.extend(script_digest(source_code, end..body_end, indent));
+            }
+            position = (body_end + close.len()).min(source_code.len());
+        } else if tag.ends_with("/>") || VOID_ELEMENTS.contains(&name) {
+            if depth == 0 {
+                result.push(KeyContent {
+                    content: tag.to_string(),
+                    start_line: line_number(source_code, start),
+                    end_line: line_number(source_code, end),
+                    ..Default::default()
+                });
+            }
+        } else {
+            if depth == 0 {
+                top_level = Some((name, start, end));
+            }
+            depth += 1;
+        }
+    }
+    result
+}
+
+/// The end of the tag starting at `start`, past its `>`, skipping over quoted attribute values and
+/// `{...}` expressions, which may contain `>`.
+fn tag_end(source_code: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    let mut braces = 0;
+    for (i, c) in source_code[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => braces += 1,
+            (None, '}') => braces -= 1,
+            (None, '>') if braces == 0 => return Some(start + i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The element name of a tag, such as `div` in `<div class="a">` or `</div>`, or `svelte:head`.
+fn tag_name(tag: &str) -> &str {
+    let name = tag.trim_start_matches('<').trim_start_matches('/');
+    let end = name
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | ':' | '.')))
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+fn script_digest(source_code: &str, range: Range<usize>, indent: &str) -> Vec<KeyContent> {
+    let script = &source_code[range.clone()];
+    let lines_before = line_number(source_code, range.start) - 1;
+    let tokens = tokenize(script, &LEXICON);
+    split_statements(&tokens, script)
+        .into_iter()
+        .filter_map(|statement| {
+            let content = statement_digest(statement, script, indent)?;
+            let mut key_content = key_content(content, statement, script);
+            key_content.start_line += lines_before;
+            key_content.end_line += lines_before;
+            Some(key_content)
+        })
+        .collect()
+}
+
+/// Imports and exported props are kept whole, and functions are kept as their signatures. Other
+/// statements, such as component state and reactive statements, are skipped.
+fn statement_digest(statement: &[Spanned], script: &str, indent: &str) -> Option<String> {
+    let is_function = statement.iter().enumerate().any(|(i, spanned)| {
+        spanned.is_word("function")
+            || (spanned.is_punctuation('=')
+                && statement.get(i + 1).is_some_and(|next| {
+                    next.is_punctuation('>') && next.range.start == spanned.range.end
+                }))
+    });
+    match first_word(statement)? {
+        "import" => Some(text(statement, script).to_string()),
+        "export" if !is_function => Some(text(statement, script).to_string()),
+        "export" | "function" | "async" | "const" | "let" | "var" if is_function => {
+            Some(signature(statement, script, indent))
+        }
+        _ => None,
+    }
+}
+
+/// A function with its body elided, if the body is a block at the end of the statement.
+fn signature(statement: &[Spanned], script: &str, indent: &str) -> String {
+    let end = match statement.last() {
+        Some(last) if last.is_punctuation(';') => statement.len() - 1,
+        _ => statement.len(),
+    };
+    let body = &statement[..end];
+    if !body.last().is_some_and(|last| last.is_punctuation('}')) {
+        return text(statement, script).to_string();
+    }
+    let mut braces = 0;
+    for (i, spanned) in body.iter().enumerate().rev() {
+        if spanned.is_punctuation('}') {
+            braces += 1;
+        } else if spanned.is_punctuation('{') {
+            braces -= 1;
+            if braces == 0 {
+                return format!("{} {{\n{}// ...\n}}", text(&body[..i], script), indent);
+            }
+        }
+    }
+    text(statement, script).to_string()
+}
+
+/// Split tokens into statements, each ending at `;` or a line break outside any brackets, unless
+/// the line ends in an operator and so continues on the next line.
+fn split_statements<'a>(tokens: &'a [Spanned], script: &str) -> Vec<&'a [Spanned]> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut brackets = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match spanned.token {
+            Token::Punctuation('(' | '[' | '{') => brackets += 1,
+            Token::Punctuation(')' | ']' | '}') => brackets -= 1,
+            _ => {}
+        }
+        let end_of_line = match tokens.get(i + 1) {
+            Some(next) => newline_between(script, spanned, next),
+            None => true,
+        };
+        let continues =
+            matches!(spanned.token, Token::Punctuation(c) if !matches!(c, ')' | ']' | '}' | ';'));
+        if ((end_of_line && !continues) || spanned.is_punctuation(';')) && brackets <= 0 {
+            statements.push(&tokens[start..=i]);
+            start = i + 1;
+            brackets = 0;
+        }
+    }
+    statements
+}
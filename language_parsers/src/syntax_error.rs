@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// tree-sitter recovers from syntax errors rather than failing, wrapping text it cannot parse in an
+// `ERROR` node and inserting zero-width `MISSING` nodes for tokens it expected, such as a `;`.
+// Parsing carries on around them, so the items captured from a file with syntax errors may be
+// incomplete or mangled.
+
+use std::fmt;
+use std::ops::Range;
+
+use tree_sitter as ts;
+
+/// Where tree-sitter found a syntax error while parsing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// The first line of the error in the source code, starting from 1.
+    pub start_line: usize,
+
+    /// The last line of the error in the source code, starting from 1.
+    pub end_line: usize,
+
+    /// The bytes of the error in the source code, empty for a missing token.
+    pub byte_range: Range<usize>,
+
+    /// The kind of token that was expected but missing, such as `;`, or None for text that could
+    /// not be parsed.
+    pub missing: Option<String>,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.missing {
+            Some(kind) => write!(f, "line {}: missing `{}`", self.start_line, kind),
+            None if self.start_line == self.end_line => {
+                write!(f, "line {}: unexpected syntax", self.start_line)
+            }
+            None => write!(
+                f,
+                "lines {}-{}: unexpected syntax",
+                self.start_line, self.end_line
+            ),
+        }
+    }
+}
+
+/// The syntax errors in a tree, in source order. Errors nested inside an `ERROR` node are part of
+/// it and not reported separately.
+pub(crate) fn syntax_errors(root_node: &ts::Node) -> Vec<SyntaxError> {
+    let mut result = vec![];
+    if root_node.has_error() {
+        collect(*root_node, &mut result);
+    }
+    result
+}
+
+fn collect(node: ts::Node, result: &mut Vec<SyntaxError>) {
+    if node.is_error() || node.is_missing() {
+        result.push(SyntaxError {
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            byte_range: node.byte_range(),
+            missing: node.is_missing().then(|| node.kind().to_string()),
+        });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.has_error() {
+            collect(child, result);
+        }
+    }
+}
+
+/// The errors as a comma-separated list, for error messages.
+pub(crate) fn describe(errors: &[SyntaxError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
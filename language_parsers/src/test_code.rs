@@ -0,0 +1,214 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// Tests often take as many tokens as the code they test while saying little about how it fits
+// together, so they can be summarized or left out. Test code is recognized by each language's
+// conventions: `#[cfg(test)]` modules and `#[test]` functions in Rust, `TestXxx`, `BenchmarkXxx`,
+// `FuzzXxx` and `ExampleXxx` functions in Go, `test_*` functions and `Test*` or `TestCase` classes
+// in Python, and `*Test` classes and `@Test` methods in Java. Whole files of tests are recognized by
+// their path.
+
+use std::path::Path;
+
+use tree_sitter as ts;
+
+use crate::{body_owner, header_end, signature, Language};
+
+/// How test code is digested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TestCode {
+    /// Digest tests like any other code
+    #[default]
+    Keep,
+
+    /// Reduce a test function to its signature, and a module or class of tests to its header and
+    /// how many tests it holds
+    Summarize,
+
+    /// Leave tests out
+    Skip,
+}
+
+/// Directories that hold tests.
+const TEST_DIRECTORIES: &[&str] = &["__tests__", "test", "tests"];
+
+/// Whether the file at `path` holds only tests, such as `tests/cli.rs`, `test_parser.py`,
+/// `parser_test.go` or `ParserTest.java`.
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_directory = path.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            component
+                .as_os_str()
+                .to_str()
+                .is_some_and(|name| TEST_DIRECTORIES.contains(&name))
+        })
+    });
+    let (stem, extension) = match (
+        path.file_stem().and_then(|stem| stem.to_str()),
+        path.extension().and_then(|extension| extension.to_str()),
+    ) {
+        (Some(stem), Some(extension)) => (stem, extension),
+        _ => return in_test_directory,
+    };
+    in_test_directory
+        || match extension {
+            "go" => stem.ends_with("_test"),
+            "java" => stem.ends_with("Test") || stem.ends_with("Tests"),
+            "py" => stem.starts_with("test_") || stem.ends_with("_test") || stem == "conftest",
+            _ => false,
+        }
+}
+
+/// Whether `node` is a test, or a module or class of them, in `language`.
+pub(crate) fn is_test(node: &ts::Node, source_code: &str, language: Language) -> bool {
+    let name = |node: &ts::Node| {
+        node.child_by_field_name("name")
+            .map_or("", |name| &source_code[name.byte_range()])
+    };
+    match (language, node.kind()) {
+        (Language::Rust, "mod_item") => rust_attributes(node, source_code)
+            .any(|attribute| attribute.replace(' ', "") == "cfg(test)"),
+        (Language::Rust, "function_item") => rust_attributes(node, source_code).any(|attribute| {
+            let path = attribute.split('(').next().unwrap_or_default().trim();
+            path.rsplit("::").next() == Some("test")
+        }),
+        (Language::Go, "function_declaration") => {
+            let name = name(node);
+            let parameters = node
+                .child_by_field_name("parameters")
+                .map_or("", |parameters| &source_code[parameters.byte_range()]);
+            ["Test", "Benchmark", "Fuzz", "Example"]
+                .into_iter()
+                .filter_map(|prefix| name.strip_prefix(prefix).map(|rest| (prefix, rest)))
+                .any(|(prefix, rest)| {
+                    // `Testify` is not a test, but `Test`, `TestParse` and `Test_parse` are.
+                    !rest.starts_with(|c: char| c.is_lowercase())
+                        && (prefix == "Example" || parameters.contains("testing."))
+                })
+        }
+        (Language::Python, "decorated_definition") => node
+            .child_by_field_name("definition")
+            .is_some_and(|definition| is_test(&definition, source_code, language)),
+        (Language::Python, "function_definition") => {
+            let name = name(node);
+            name == "test" || name.starts_with("test_")
+        }
+        (Language::Python, "class_definition") => {
+            let superclasses = node
+                .child_by_field_name("superclasses")
+                .map_or("", |superclasses| &source_code[superclasses.byte_range()]);
+            name(node).starts_with("Test") || superclasses.contains("TestCase")
+        }
+        (Language::Java, "class_declaration") => {
+            let name = name(node);
+            name.ends_with("Test") || name.ends_with("Tests")
+        }
+        (Language::Java, "method_declaration") => {
+            let mut cursor = node.walk();
+            let modifiers = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "modifiers")
+                .map_or("", |modifiers| &source_code[modifiers.byte_range()]);
+            modifiers.split_whitespace().any(|modifier| {
+                matches!(
+                    modifier.split('(').next(),
+                    Some("@Test" | "@ParameterizedTest" | "@RepeatedTest" | "@TestFactory")
+                )
+            })
+        }
+        _ => false,
+    }
+}
+
+/// A test reduced to its signature, or a module or class of tests reduced to its header followed
+/// by a comment such as `// ... (12 tests, 340 lines elided)`.
+pub(crate) fn summary(node: ts::Node, source_code: &str, language: Language) -> String {
+    let owner = body_owner(node);
+    let body = match owner.child_by_field_name("body") {
+        Some(body) if is_container(owner.kind()) => body,
+        _ => return signature(node, source_code),
+    };
+    let tests = count_tests(body, source_code, language);
+    let lines = node.end_position().row - node.start_position().row + 1;
+    format!(
+        "{} {} ... ({} {}, {} lines elided)",
+        source_code[node.start_byte()..header_end(owner, body)].trim(),
+        if language == Language::Python {
+            "#"
+        } else {
+            "//"
+        },
+        tests,
+        if tests == 1 { "test" } else { "tests" },
+        lines
+    )
+}
+
+fn is_container(kind: &str) -> bool {
+    matches!(kind, "mod_item" | "class_definition" | "class_declaration")
+}
+
+fn count_tests(node: ts::Node, source_code: &str, language: Language) -> usize {
+    let mut count = 0;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        // A nested module of tests is counted by the tests in it.
+        if !is_container(body_owner(child).kind()) && is_test(&child, source_code, language) {
+            count += 1;
+        } else {
+            count += count_tests(child, source_code, language);
+        }
+    }
+    count
+}
+
+/// The contents of the attributes directly above a Rust item, such as `cfg(test)` for
+/// `#[cfg(test)]`, nearest first.
+fn rust_attributes<'a>(
+    node: &ts::Node<'a>,
+    source_code: &'a str,
+) -> impl Iterator<Item = &'a str> + 'a {
+    let mut sibling = node.prev_sibling();
+    std::iter::from_fn(move || {
+        let attribute = sibling.filter(|sibling| sibling.kind() == "attribute_item")?;
+        sibling = attribute.prev_sibling();
+        let text = &source_code[attribute.byte_range()];
+        Some(text.trim_start_matches("#[").trim_end_matches(']').trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_test_path() {
+        for path in [
+            "tests/cli.rs",
+            "crate/tests/fixtures/input.json",
+            "python/test/helpers.py",
+            "parser_test.go",
+            "test_parser.py",
+            "tests/conftest.py",
+            "src/test/java/ParserTest.java",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{}", path);
+        }
+        for path in [
+            "src/lib.rs",
+            "parser.go",
+            "testing.py",
+            "src/contest.py",
+            "Tests.md",
+        ] {
+            assert!(!is_test_path(Path::new(path)), "{}", path);
+        }
+    }
+}
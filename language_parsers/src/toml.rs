@@ -0,0 +1,125 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// TOML is digested as its tables, each kept as its header and the keys it defines with their
+// values elided. Cargo manifests, recognized by a `[package]` or `[workspace]` table, keep the
+// values of those tables and of `[features]` too, so that the crate and workspace layout is
+// visible, while dependency tables still list only the dependency names.
+
+use crate::scan::{line_number, newline_between, text, tokenize, Lexicon, Spanned, Token};
+use crate::KeyContent;
+
+const LEXICON: Lexicon = Lexicon {
+    line_comments: &["#"],
+    block_comments: &[],
+    quotes: &["\"\"\"", "'''", "\"", "'"],
+    word_characters: &['-', '.'],
+};
+
+/// Tables of a Cargo manifest that are kept with their values.
+const CARGO_KEPT_TABLES: &[&str] = &["package", "workspace", "workspace.package", "features"];
+
+struct Table<'a> {
+    /// The `[table]` or `[[array.of.tables]]` header, or None for keys before the first header.
+    header: Option<&'a [Spanned]>,
+    entries: Vec<&'a [Spanned]>,
+}
+
+impl Table<'_> {
+    fn name<'a>(&self, source_code: &'a str) -> Option<&'a str> {
+        let header = text(self.header?, source_code);
+        Some(header.trim_matches(|c| c == '[' || c == ']').trim())
+    }
+}
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let tokens = tokenize(source_code, &LEXICON);
+    let tables = split_tables(split_items(&tokens, source_code));
+    let cargo_manifest = tables
+        .iter()
+        .any(|table| matches!(table.name(source_code), Some("package" | "workspace")));
+
+    tables
+        .iter()
+        .filter(|table| table.header.is_some() || !table.entries.is_empty())
+        .map(|table| {
+            let keep_values = cargo_manifest
+                && table
+                    .name(source_code)
+                    .is_some_and(|name| CARGO_KEPT_TABLES.contains(&name));
+            let mut lines = vec![];
+            if let Some(header) = table.header {
+                lines.push(text(header, source_code).to_string());
+            }
+            for entry in &table.entries {
+                lines.push(entry_outline(entry, source_code, keep_values));
+            }
+
+            let first = table.header.or(table.entries.first().copied()).unwrap();
+            let last = table.entries.last().copied().unwrap_or(first);
+            KeyContent {
+                content: lines.join("\n"),
+                start_line: line_number(source_code, first[0].range.start),
+                end_line: line_number(source_code, last[last.len() - 1].range.end),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Split tokens into items, each a table header or a key and its value, ending at a line break
+/// outside any brackets.
+fn split_items<'a>(tokens: &'a [Spanned], source_code: &str) -> Vec<&'a [Spanned]> {
+    let mut items = vec![];
+    let mut start = 0;
+    let mut brackets = 0;
+    for (i, spanned) in tokens.iter().enumerate() {
+        match spanned.token {
+            Token::Punctuation('[' | '{') => brackets += 1,
+            Token::Punctuation(']' | '}') => brackets -= 1,
+            _ => {}
+        }
+        let end_of_line = match tokens.get(i + 1) {
+            Some(next) => newline_between(source_code, spanned, next),
+            None => true,
+        };
+        if end_of_line && brackets <= 0 {
+            items.push(&tokens[start..=i]);
+            start = i + 1;
+            brackets = 0;
+        }
+    }
+    items
+}
+
+fn split_tables(items: Vec<&[Spanned]>) -> Vec<Table<'_>> {
+    let mut tables = vec![Table {
+        header: None,
+        entries: vec![],
+    }];
+    for item in items {
+        if item[0].is_punctuation('[') {
+            tables.push(Table {
+                header: Some(item),
+                entries: vec![],
+            });
+        } else {
+            tables.last_mut().unwrap().entries.push(item);
+        }
+    }
+    tables
+}
+
+fn entry_outline(entry: &[Spanned], source_code: &str, keep_value: bool) -> String {
+    match entry.iter().position(|spanned| spanned.is_punctuation('=')) {
+        Some(equals) if !keep_value => format!("{} = ...", text(&entry[..equals], source_code)),
+        _ => text(entry, source_code).to_string(),
+    }
+}
@@ -10,14 +10,21 @@
 
 // reference: https://github.com/Wilfred/difftastic/blob/84af470128adf82302d47749ab9dc33e0e6409b2/src/parse/tree_sitter_parser.rs
 
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
 use tree_sitter as ts;
 
 use crate::Language;
 
 extern "C" {
+    fn tree_sitter_bash() -> ts::Language;
+    fn tree_sitter_cpp() -> ts::Language;
     fn tree_sitter_go() -> ts::Language;
-    fn tree_sitter_hcl() -> ts::Language;
     fn tree_sitter_java() -> ts::Language;
+    fn tree_sitter_php() -> ts::Language;
     fn tree_sitter_python() -> ts::Language;
     fn tree_sitter_rust() -> ts::Language;
 }
@@ -29,28 +36,153 @@ pub struct TreeSitterConfig {
 // from enum Language to TreeSitterConfig
 pub fn from_language(language: Language) -> TreeSitterConfig {
     match language {
+        Language::Bash => TreeSitterConfig {
+            language: unsafe { tree_sitter_bash() },
+        },
+        Language::Cpp => TreeSitterConfig {
+            language: unsafe { tree_sitter_cpp() },
+        },
         Language::Go => TreeSitterConfig {
             language: unsafe { tree_sitter_go() },
         },
-        Language::Hcl => TreeSitterConfig {
-            language: unsafe { tree_sitter_hcl() },
-        },
         Language::Java => TreeSitterConfig {
             language: unsafe { tree_sitter_java() },
         },
+        Language::Php => TreeSitterConfig {
+            language: unsafe { tree_sitter_php() },
+        },
         Language::Python => TreeSitterConfig {
             language: unsafe { tree_sitter_python() },
         },
         Language::Rust => TreeSitterConfig {
             language: unsafe { tree_sitter_rust() },
         },
+        Language::Clojure
+        | Language::CMake
+        | Language::Dart
+        | Language::Dockerfile
+        | Language::FSharp
+        | Language::Hcl
+        | Language::Json
+        | Language::Julia
+        | Language::Jupyter
+        | Language::Make
+        | Language::Markdown
+        | Language::Nix
+        | Language::Proto
+        | Language::Sql
+        | Language::Svelte
+        | Language::Toml
+        | Language::Unknown
+        | Language::Yaml => {
+            unreachable!(
+                "{:?} is parsed by a scanner rather than tree-sitter",
+                language
+            )
+        }
+    }
+}
+
+pub fn to_tree(src: &str, config: &TreeSitterConfig, pool: &ParserPool) -> Option<ts::Tree> {
+    to_tree_with_limits(src, config, pool, None, None)
+        .ok()
+        .flatten()
+}
+
+/// Parse as `to_tree` does, giving up with None once `timeout` has passed or `cancellation` is
+/// cancelled. It fails if the grammar was built for an incompatible version of tree-sitter.
+pub fn to_tree_with_limits(
+    src: &str,
+    config: &TreeSitterConfig,
+    pool: &ParserPool,
+    timeout: Option<Duration>,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Option<ts::Tree>, ts::LanguageError> {
+    let mut parser = pool.take(config.language)?;
+    // A timeout of zero means none, so a timeout too short to measure is rounded up.
+    parser.set_timeout_micros(timeout.map_or(0, |timeout| timeout.as_micros().max(1) as u64));
+    // SAFETY: the flag outlives the parse, and is unset before the parser goes back to the pool.
+    unsafe { parser.set_cancellation_flag(cancellation.map(|token| &*token.flag)) };
+    let tree = parser.parse(src, None);
+    if tree.is_none() {
+        // A parse that stops early leaves the parser part way through, to resume on the next call.
+        parser.reset();
+    }
+    parser.set_timeout_micros(0);
+    unsafe { parser.set_cancellation_flag(None) };
+    pool.put_back(config.language, parser);
+    Ok(tree)
+}
+
+/// A flag that stops the parses given it, for example when a file changes again while a digest of
+/// the previous version is still being built. Clones share the flag, so a clone can be kept to
+/// cancel with while another is passed to a parse on a different thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicUsize>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Stop the parses using this token, and any started with it later.
+    pub fn cancel(&self) {
+        self.flag.store(1, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst) != 0
     }
 }
 
-pub fn to_tree(src: &str, config: &TreeSitterConfig) -> Option<ts::Tree> {
-    let mut parser = ts::Parser::new();
-    parser
-        .set_language(config.language)
-        .expect("Incompatible tree-sitter version");
-    parser.parse(src, None)
+/// Parsers kept for reuse, keyed by language, since creating a parser and setting its language for
+/// every file is wasteful on large repositories. A pool can be shared between threads; each parse
+/// takes a parser out of the pool, or creates one if none are idle, and returns it afterwards.
+#[derive(Default)]
+pub struct ParserPool {
+    idle: Mutex<HashMap<ts::Language, Vec<ts::Parser>>>,
+}
+
+impl ParserPool {
+    pub fn new() -> ParserPool {
+        ParserPool::default()
+    }
+
+    /// The pool that `parse` uses.
+    pub fn global() -> &'static ParserPool {
+        static GLOBAL: OnceLock<ParserPool> = OnceLock::new();
+        GLOBAL.get_or_init(ParserPool::new)
+    }
+
+    fn take(&self, language: ts::Language) -> Result<ts::Parser, ts::LanguageError> {
+        let parser = self
+            .idle()
+            .get_mut(&language)
+            .and_then(|parsers| parsers.pop());
+        match parser {
+            Some(parser) => Ok(parser),
+            None => {
+                let mut parser = ts::Parser::new();
+                parser.set_language(language)?;
+                Ok(parser)
+            }
+        }
+    }
+
+    fn put_back(&self, language: ts::Language, parser: ts::Parser) {
+        self.idle().entry(language).or_default().push(parser);
+    }
+
+    /// The number of parsers waiting to be reused.
+    pub fn idle_parsers(&self) -> usize {
+        self.idle().values().map(Vec::len).sum()
+    }
+
+    // A thread that panicked while holding the lock cannot have left the map half-updated, so a
+    // poisoned lock is still safe to use.
+    fn idle(&self) -> MutexGuard<'_, HashMap<ts::Language, Vec<ts::Parser>>> {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
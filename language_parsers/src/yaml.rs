@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2023 Asim Ihsan.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+// YAML is digested by indentation alone: each top-level entry is kept with its second-level keys
+// or items, and anything nested more deeply is elided. This keeps the shape of Kubernetes manifests
+// and CI configs, such as the kinds of resources or the names of jobs, without their details.
+
+use crate::KeyContent;
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+
+    /// The line without indentation or a trailing comment.
+    text: &'a str,
+}
+
+impl Line<'_> {
+    fn is_item(&self) -> bool {
+        self.text == "-" || self.text.starts_with("- ")
+    }
+
+    /// Whether the line is a key whose value is a nested block, e.g. `metadata:`.
+    fn opens_block(&self) -> bool {
+        !self.is_item() && self.text.ends_with(':')
+    }
+}
+
+pub(crate) fn parse(source_code: &str, _indent: &str) -> Vec<KeyContent> {
+    let lines = significant_lines(source_code);
+    let mut result = vec![];
+    let mut new_document = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        if line.text.starts_with("---") || line.text == "..." {
+            new_document = !result.is_empty();
+            i += 1;
+            continue;
+        }
+        let end = block_end(&lines, i);
+        let mut content = if new_document {
+            "---\n".to_string()
+        } else {
+            String::new()
+        };
+        new_document = false;
+        content.push_str(&entry_outline(&lines[i..end]));
+        result.push(KeyContent {
+            content,
+            start_line: line.number,
+            end_line: lines[end - 1].number,
+            ..Default::default()
+        });
+        i = end;
+    }
+    result
+}
+
+/// The top-level line of an entry followed by its second-level lines, each outlined.
+fn entry_outline(entry: &[Line]) -> String {
+    let first = &entry[0];
+    if is_block_scalar(first.text) {
+        return line_outline(first, true);
+    }
+    let mut result = first.text.to_string();
+    let mut i = 1;
+    while i < entry.len() {
+        let end = block_end(entry, i);
+        result.push('\n');
+        result.push_str(&" ".repeat(entry[i].indent));
+        result.push_str(&line_outline(&entry[i], end > i + 1));
+        i = end;
+    }
+    result
+}
+
+/// A line with any nested value elided.
+fn line_outline(line: &Line, has_children: bool) -> String {
+    if !has_children {
+        return line.text.to_string();
+    }
+    if line.text == "-" {
+        return "- ...".to_string();
+    }
+    if line.text.ends_with(':') {
+        return format!("{} ...", line.text);
+    }
+    if is_block_scalar(line.text) {
+        if let Some((key, _)) = line.text.rsplit_once(' ') {
+            return format!("{} ...", key);
+        }
+    }
+    line.text.to_string()
+}
+
+/// Whether a line ends in a block scalar indicator such as `|`, `>-` or `|2`, so that the lines
+/// nested under it are text rather than structure.
+fn is_block_scalar(text: &str) -> bool {
+    let indicator = text.rsplit(' ').next().unwrap_or_default();
+    (indicator.starts_with('|') || indicator.starts_with('>'))
+        && indicator[1..]
+            .chars()
+            .all(|c| c == '+' || c == '-' || c.is_ascii_digit())
+        && indicator != text
+}
+
+/// The index past the lines nested under `lines[start]`. These are the following lines that are
+/// indented more deeply, or for a key opening a block, also items at the same indentation, since
+/// YAML allows `key:` to be followed by `- item` without further indentation.
+fn block_end(lines: &[Line], start: usize) -> usize {
+    let line = &lines[start];
+    let mut end = start + 1;
+    while let Some(next) = lines.get(end) {
+        let nested = next.indent > line.indent
+            || (line.opens_block() && next.indent == line.indent && next.is_item());
+        if !nested {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// The lines that are not blank, comments or directives such as `%YAML 1.2`.
+fn significant_lines(source_code: &str) -> Vec<Line<'_>> {
+    source_code
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let text = strip_comment(line.trim()).trim_end();
+            if text.is_empty() || line.starts_with('%') {
+                return None;
+            }
+            Some(Line {
+                number: i + 1,
+                indent: line.len() - line.trim_start().len(),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// A line without its trailing comment, which starts at a `#` after whitespace outside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && (previous.is_whitespace() || previous == ':') => {
+                quote = Some(c)
+            }
+            None if c == '#' && previous.is_whitespace() => return &line[..i],
+            None => {}
+        }
+        previous = c;
+    }
+    line
+}